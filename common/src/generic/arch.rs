@@ -0,0 +1,559 @@
+use super::flow;
+use crate::arch::{BelId, PipId, WireId};
+use crate::kernel::arch_api::ArchAPI;
+use crate::kernel::base_arch::BaseArch;
+use crate::kernel::base_context::BaseCtx;
+use crate::kernel::base_types::{ArcBounds, GraphicElement, Loc};
+use crate::kernel::cell::CellInfo;
+use crate::kernel::delay::DelayQuad;
+use crate::kernel::id_string::IdString;
+use crate::kernel::id_string_list::{IdStringList, NameTable};
+use crate::kernel::port::PortType;
+use crate::kernel::timing::{TimingClockingInfo, TimingPortClass};
+use crate::kernel::types::DecalXY;
+use core::iter::{Copied, Map};
+use core::ops::Range;
+use core::slice;
+use std::collections::{btree_map, BTreeMap};
+use thunderdome::Index;
+
+/// Generic arch delays are integer picoseconds.
+pub type Delay = i64;
+
+/// A group of bels, wires, pips and other groups, numbered in the order they were added.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GroupId(u32);
+
+impl GroupId {
+    pub const fn with_index(index: u32) -> Self {
+        Self(index)
+    }
+    pub const fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BelPinInfo {
+    pub wire: WireId,
+    pub dir: PortType,
+}
+
+#[derive(Debug, Clone)]
+pub struct BelInfo {
+    pub name: IdStringList,
+    pub bel_type: IdString,
+    pub loc: Loc,
+    pub global_buf: bool,
+    pub hidden: bool,
+    pub pins: BTreeMap<IdString, BelPinInfo>,
+    pub attrs: Vec<(IdString, String)>,
+    pub decal: Option<DecalXY>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WireInfo {
+    pub name: IdStringList,
+    pub wire_type: IdString,
+    pub x: i32,
+    pub y: i32,
+    pub downhill: Vec<PipId>,
+    pub uphill: Vec<PipId>,
+    pub bel_pins: Vec<(BelId, IdString)>,
+    pub attrs: Vec<(IdString, String)>,
+    pub decal: Option<DecalXY>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipInfo {
+    pub name: IdStringList,
+    pub pip_type: IdString,
+    pub src: WireId,
+    pub dst: WireId,
+    pub delay: DelayQuad<Delay>,
+    pub loc: Loc,
+    pub attrs: Vec<(IdString, String)>,
+    pub decal: Option<DecalXY>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GroupInfo {
+    pub name: IdStringList,
+    pub bels: Vec<BelId>,
+    pub wires: Vec<WireId>,
+    pub pips: Vec<PipId>,
+    pub groups: Vec<GroupId>,
+    pub decal: Option<DecalXY>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DecalInfo {
+    pub name: IdStringList,
+    pub graphics: Vec<GraphicElement>,
+}
+
+/// Cells that are placed together, each at a fixed offset from the bel of the root.
+#[derive(Debug, Clone)]
+pub struct ClusterInfo {
+    pub root: Index<CellInfo<Delay>>,
+    /// Every cell of the cluster with its offset, starting with the root at the origin.
+    pub cells: Vec<(Index<CellInfo<Delay>>, Loc)>,
+}
+
+/// The timing of every cell of one type.
+#[derive(Debug, Clone, Default)]
+pub struct CellTiming {
+    pub port_classes: BTreeMap<IdString, TimingPortClass>,
+    pub comb_delays: BTreeMap<(IdString, IdString), DelayQuad<Delay>>,
+    pub clocking_info: BTreeMap<IdString, Vec<TimingClockingInfo<Delay>>>,
+}
+
+/// A device built with a [`super::builder::GenericArchBuilder`]. Its ids index straight into the
+/// tables below, in the order the objects were added.
+pub struct GenericArch {
+    pub(crate) chip_name: String,
+    pub(crate) bels: Vec<BelInfo>,
+    pub(crate) wires: Vec<WireInfo>,
+    pub(crate) pips: Vec<PipInfo>,
+    pub(crate) groups: Vec<GroupInfo>,
+    pub(crate) decals: Vec<DecalInfo>,
+    pub(crate) bel_names: NameTable<BelId>,
+    pub(crate) wire_names: NameTable<WireId>,
+    pub(crate) pip_names: NameTable<PipId>,
+    pub(crate) group_names: NameTable<GroupId>,
+    pub(crate) bel_by_loc: BTreeMap<Loc, BelId>,
+    pub(crate) tile_bels: BTreeMap<(i32, i32), Vec<BelId>>,
+    pub(crate) tile_bel_dim_z: BTreeMap<(i32, i32), i32>,
+    pub(crate) bel_types: Vec<IdString>,
+    pub(crate) cell_timing: BTreeMap<IdString, CellTiming>,
+    pub(crate) clusters: BTreeMap<IdString, ClusterInfo>,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    // Routing delay estimates are `delay_offset` plus `delay_scale` per tile of distance.
+    pub(crate) delay_scale: Delay,
+    pub(crate) delay_offset: Delay,
+    pub(crate) base: BaseArch<GenericArch>,
+}
+
+impl GenericArch {
+    fn bel_info(&self, bel: BelId) -> &BelInfo {
+        &self.bels[bel.index().expect("Null bel id.") as usize]
+    }
+    fn wire_info(&self, wire: WireId) -> &WireInfo {
+        &self.wires[wire.index().expect("Null wire id.") as usize]
+    }
+    fn pip_info(&self, pip: PipId) -> &PipInfo {
+        &self.pips[pip.index().expect("Null pip id.") as usize]
+    }
+    fn group_info(&self, group: GroupId) -> &GroupInfo {
+        &self.groups[group.index() as usize]
+    }
+    fn distance_delay(&self, dx: i32, dy: i32) -> Delay {
+        self.delay_offset + self.delay_scale * (dx.abs() + dy.abs()) as Delay
+    }
+    fn cell_timing(&self, cell: &CellInfo<Delay>) -> Option<&CellTiming> {
+        self.cell_timing.get(&cell.cell_type())
+    }
+    fn cluster_offset(&self, cell: &CellInfo<Delay>) -> Option<Loc> {
+        let index = cell.self_index()?;
+        self.clusters
+            .values()
+            .flat_map(|cluster| cluster.cells.iter())
+            .find(|(member, _)| *member == index)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+impl ArchAPI for GenericArch {
+    type Delay = Delay;
+    type ArchArgs = ();
+    type BelId = BelId;
+    type WireId = WireId;
+    type PipId = PipId;
+    type GroupId = GroupId;
+    // Every bel type is its own bucket.
+    type BelBucketId = IdString;
+    type ClusterId = IdString;
+
+    type AllBels<'a> = Map<Range<u64>, fn(u64) -> BelId>;
+    type TileBels<'a> = Copied<slice::Iter<'a, BelId>>;
+    type BelPins<'a> = Copied<btree_map::Keys<'a, IdString, BelPinInfo>>;
+    type AllWires<'a> = Map<Range<u64>, fn(u64) -> WireId>;
+    type WireBelPins<'a> = Copied<slice::Iter<'a, (BelId, IdString)>>;
+    type AllPips<'a> = Map<Range<u64>, fn(u64) -> PipId>;
+    type DownhillPips<'a> = Copied<slice::Iter<'a, PipId>>;
+    type UphillPips<'a> = Copied<slice::Iter<'a, PipId>>;
+
+    fn base_arch(&self) -> &BaseArch<Self> {
+        &self.base
+    }
+    fn base_arch_mut(&mut self) -> &mut BaseArch<Self> {
+        &mut self.base
+    }
+
+    // Basic config
+    fn get_chip_name(&self) -> String {
+        self.chip_name.clone()
+    }
+    fn arch_args(&self) -> &Self::ArchArgs {
+        &()
+    }
+    fn get_grid_dim_x(&self) -> i32 {
+        self.width
+    }
+    fn get_grid_dim_y(&self) -> i32 {
+        self.height
+    }
+    fn get_tile_bel_dim_z(&self, x: i32, y: i32) -> i32 {
+        self.tile_bel_dim_z.get(&(x, y)).copied().unwrap_or(0)
+    }
+
+    // Bel methods
+    fn get_bels(&self) -> Self::AllBels<'_> {
+        (0..self.bels.len() as u64).map(BelId::with_index as fn(u64) -> BelId)
+    }
+    fn get_bel_name(&self, bel: BelId) -> IdStringList {
+        self.bel_info(bel).name.clone()
+    }
+    fn get_bel_by_name(&self, name: &IdStringList) -> Option<BelId> {
+        self.bel_names.get(name)
+    }
+    fn get_bel_checksum(&self, bel: BelId) -> u32 {
+        bel.index().unwrap_or(0) as u32
+    }
+    fn get_bel_location(&self, bel: BelId) -> Loc {
+        self.bel_info(bel).loc
+    }
+    fn get_bel_by_location(&self, loc: Loc) -> Option<BelId> {
+        self.bel_by_loc.get(&loc).copied()
+    }
+    fn get_bels_by_tile(&self, x: i32, y: i32) -> Self::TileBels<'_> {
+        self.tile_bels
+            .get(&(x, y))
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .copied()
+    }
+    fn get_bel_global_buf(&self, bel: BelId) -> bool {
+        self.bel_info(bel).global_buf
+    }
+    fn get_bel_type(&self, bel: BelId) -> IdString {
+        self.bel_info(bel).bel_type
+    }
+    fn get_bel_hidden(&self, bel: BelId) -> bool {
+        self.bel_info(bel).hidden
+    }
+    fn get_bel_attrs(&self, bel: BelId) -> Vec<(IdString, String)> {
+        self.bel_info(bel).attrs.clone()
+    }
+    fn get_bel_pin_wire(&self, bel: BelId, pin: IdString) -> Option<WireId> {
+        self.bel_info(bel).pins.get(&pin).map(|pin| pin.wire)
+    }
+    // A pin the bel doesn't have reads as the default input, a placer may ask with any port.
+    fn get_bel_pin_type(&self, bel: BelId, pin: IdString) -> PortType {
+        self.bel_info(bel)
+            .pins
+            .get(&pin)
+            .map(|pin| pin.dir)
+            .unwrap_or_default()
+    }
+    fn get_bel_pins(&self, bel: BelId) -> Self::BelPins<'_> {
+        self.bel_info(bel).pins.keys().copied()
+    }
+
+    // Wire methods
+    fn get_wires(&self) -> Self::AllWires<'_> {
+        (0..self.wires.len() as u64).map(WireId::with_index as fn(u64) -> WireId)
+    }
+    fn get_wire_by_name(&self, name: &IdStringList) -> Option<WireId> {
+        self.wire_names.get(name)
+    }
+    fn get_wire_name(&self, wire: WireId) -> IdStringList {
+        self.wire_info(wire).name.clone()
+    }
+    fn get_wire_type(&self, wire: WireId) -> IdString {
+        self.wire_info(wire).wire_type
+    }
+    fn get_wire_attrs(&self, wire: WireId) -> Vec<(IdString, String)> {
+        self.wire_info(wire).attrs.clone()
+    }
+    fn get_pips_downhill(&self, wire: WireId) -> Self::DownhillPips<'_> {
+        self.wire_info(wire).downhill.iter().copied()
+    }
+    fn get_pips_uphill(&self, wire: WireId) -> Self::UphillPips<'_> {
+        self.wire_info(wire).uphill.iter().copied()
+    }
+    fn get_wire_bel_pins(&self, wire: WireId) -> Self::WireBelPins<'_> {
+        self.wire_info(wire).bel_pins.iter().copied()
+    }
+    fn get_wire_checksum(&self, wire: WireId) -> u32 {
+        wire.index().unwrap_or(0) as u32
+    }
+    fn get_wire_delay(&self, _wire: WireId) -> DelayQuad<Delay> {
+        DelayQuad::new()
+    }
+
+    // Pip methods
+    fn get_pips(&self) -> Self::AllPips<'_> {
+        (0..self.pips.len() as u64).map(PipId::with_index as fn(u64) -> PipId)
+    }
+    fn get_pip_by_name(&self, name: &IdStringList) -> Option<PipId> {
+        self.pip_names.get(name)
+    }
+    fn get_pip_name(&self, pip: PipId) -> IdStringList {
+        self.pip_info(pip).name.clone()
+    }
+    fn get_pip_type(&self, pip: PipId) -> IdString {
+        self.pip_info(pip).pip_type
+    }
+    fn get_pip_attrs(&self, pip: PipId) -> Vec<(IdString, String)> {
+        self.pip_info(pip).attrs.clone()
+    }
+    fn get_pip_checksum(&self, pip: PipId) -> u32 {
+        pip.index().unwrap_or(0) as u32
+    }
+    fn get_pip_src_wire(&self, pip: PipId) -> WireId {
+        self.pip_info(pip).src
+    }
+    fn get_pip_dst_wire(&self, pip: PipId) -> WireId {
+        self.pip_info(pip).dst
+    }
+    fn get_pip_delay(&self, pip: PipId) -> DelayQuad<Delay> {
+        self.pip_info(pip).delay
+    }
+    fn get_pip_location(&self, pip: PipId) -> Loc {
+        self.pip_info(pip).loc
+    }
+
+    // Group methods
+    fn get_group_by_name(&self, name: &IdStringList) -> Option<GroupId> {
+        self.group_names.get(name)
+    }
+    fn get_group_name(&self, group: GroupId) -> IdStringList {
+        self.group_info(group).name.clone()
+    }
+    fn get_groups(&self) -> Vec<GroupId> {
+        (0..self.groups.len() as u32)
+            .map(GroupId::with_index)
+            .collect()
+    }
+    fn get_group_bels(&self, group: GroupId) -> Vec<BelId> {
+        self.group_info(group).bels.clone()
+    }
+    fn get_group_wires(&self, group: GroupId) -> Vec<WireId> {
+        self.group_info(group).wires.clone()
+    }
+    fn get_group_pips(&self, group: GroupId) -> Vec<PipId> {
+        self.group_info(group).pips.clone()
+    }
+    fn get_group_groups(&self, group: GroupId) -> Vec<GroupId> {
+        self.group_info(group).groups.clone()
+    }
+
+    // Delay methods
+    fn predict_delay(
+        &self,
+        src_bel: BelId,
+        _src_pin: IdString,
+        dst_bel: BelId,
+        _dst_pin: IdString,
+    ) -> Delay {
+        let (src, dst) = (
+            self.get_bel_location(src_bel),
+            self.get_bel_location(dst_bel),
+        );
+        self.distance_delay(dst.x() - src.x(), dst.y() - src.y())
+    }
+    fn get_delay_epsilon(&self) -> Delay {
+        1
+    }
+    fn get_ripup_delay_penalty(&self) -> Delay {
+        15
+    }
+    fn get_delay_ns(&self, delay: Delay) -> f32 {
+        delay as f32 / 1000.0
+    }
+    fn get_delay_from_ns(&self, ns: f32) -> Delay {
+        (ns * 1000.0).round() as Delay
+    }
+    fn get_delay_checksum(&self, delay: Delay) -> u32 {
+        delay as u32
+    }
+    fn estimate_delay(&self, src: WireId, dst: WireId) -> Delay {
+        let (src, dst) = (self.wire_info(src), self.wire_info(dst));
+        self.distance_delay(dst.x - src.x, dst.y - src.y)
+    }
+    fn get_route_bounding_box(&self, src: WireId, dst: WireId) -> ArcBounds {
+        let (src, dst) = (self.wire_info(src), self.wire_info(dst));
+        ArcBounds::new(
+            src.x.min(dst.x),
+            src.y.min(dst.y),
+            src.x.max(dst.x),
+            src.y.max(dst.y),
+        )
+    }
+
+    // Decal methods
+    fn get_decal_graphics(&self, decal: &DecalXY) -> Vec<GraphicElement> {
+        decal
+            .decal()
+            .index()
+            .and_then(|index| self.decals.get(index as usize))
+            .map_or_else(Vec::new, |decal| decal.graphics.clone())
+    }
+    fn get_bel_decal(&self, bel: BelId) -> Option<DecalXY> {
+        self.bel_info(bel).decal
+    }
+    fn get_wire_decal(&self, wire: WireId) -> Option<DecalXY> {
+        self.wire_info(wire).decal
+    }
+    fn get_pip_decal(&self, pip: PipId) -> Option<DecalXY> {
+        self.pip_info(pip).decal
+    }
+    fn get_group_decal(&self, group: GroupId) -> Option<DecalXY> {
+        self.group_info(group).decal
+    }
+
+    // Cell timing methods
+    fn get_cell_delay(
+        &self,
+        cell: &CellInfo<Delay>,
+        from_port: IdString,
+        to_port: IdString,
+    ) -> Option<DelayQuad<Delay>> {
+        self.cell_timing(cell)?
+            .comb_delays
+            .get(&(from_port, to_port))
+            .copied()
+    }
+    fn get_port_timing_class(
+        &self,
+        cell: &CellInfo<Delay>,
+        port: IdString,
+    ) -> (TimingPortClass, usize) {
+        let Some(timing) = self.cell_timing(cell) else {
+            return (TimingPortClass::Ignore, 0);
+        };
+        (
+            timing
+                .port_classes
+                .get(&port)
+                .copied()
+                .unwrap_or(TimingPortClass::Ignore),
+            timing.clocking_info.get(&port).map_or(0, Vec::len),
+        )
+    }
+    fn get_port_clocking_info(
+        &self,
+        cell: &CellInfo<Delay>,
+        port: IdString,
+        index: usize,
+    ) -> TimingClockingInfo<Delay> {
+        self.cell_timing(cell)
+            .and_then(|timing| timing.clocking_info.get(&port))
+            .and_then(|info| info.get(index))
+            .copied()
+            .unwrap_or_else(TimingClockingInfo::new)
+    }
+
+    // Placement validity checks
+    fn get_bel_bucket_name(&self, bucket: IdString) -> IdString {
+        bucket
+    }
+    fn get_bel_bucket_by_name(&self, name: IdString) -> Option<IdString> {
+        self.bel_types.contains(&name).then_some(name)
+    }
+    fn get_bel_bucket_for_bel(&self, bel: BelId) -> IdString {
+        self.get_bel_type(bel)
+    }
+    fn get_bel_bucket_for_cell_type(&self, cell_type: IdString) -> IdString {
+        cell_type
+    }
+    fn get_cell_types(&self) -> Vec<IdString> {
+        self.bel_types.clone()
+    }
+    fn get_bel_buckets(&self) -> Vec<IdString> {
+        self.bel_types.clone()
+    }
+
+    // Cluster methods
+    fn get_cluster_root_cell(&self, cluster: IdString) -> Option<Index<CellInfo<Delay>>> {
+        self.clusters.get(&cluster).map(|cluster| cluster.root)
+    }
+    fn get_cluster_bounds(&self, cluster: IdString) -> ArcBounds {
+        let Some(cluster) = self.clusters.get(&cluster) else {
+            return ArcBounds::default();
+        };
+        let offsets = cluster.cells.iter().map(|(_, offset)| offset);
+        let (x0, y0, x1, y1) = offsets.fold((0, 0, 0, 0), |(x0, y0, x1, y1), offset| {
+            (
+                x0.min(offset.x()),
+                y0.min(offset.y()),
+                x1.max(offset.x()),
+                y1.max(offset.y()),
+            )
+        });
+        ArcBounds::new(x0, y0, x1, y1)
+    }
+    fn get_cluster_offset(&self, cell: &CellInfo<Delay>) -> Loc {
+        self.cluster_offset(cell).unwrap_or_else(Loc::origin)
+    }
+    // Offsets are exact, a cluster cell can only go where its offset puts it.
+    fn is_cluster_strict(&self, cell: &CellInfo<Delay>) -> bool {
+        self.cluster_offset(cell).is_some()
+    }
+    fn get_cluster_placement(
+        &self,
+        cluster: IdString,
+        root_bel: BelId,
+    ) -> Option<Vec<(Index<CellInfo<Delay>>, BelId)>> {
+        let cluster = self.clusters.get(&cluster)?;
+        let root = self.get_bel_location(root_bel);
+        cluster
+            .cells
+            .iter()
+            .map(|(cell, offset)| {
+                let loc = Loc::new(
+                    root.x() + offset.x(),
+                    root.y() + offset.y(),
+                    root.z() + offset.z(),
+                );
+                Some((*cell, self.get_bel_by_location(loc)?))
+            })
+            .collect()
+    }
+
+    // Flow methods
+    fn pack(&mut self, ctx: &mut BaseCtx<Delay>) -> bool {
+        flow::report(flow::pack(ctx, self))
+    }
+    fn place(&mut self, ctx: &mut BaseCtx<Delay>) -> bool {
+        flow::report(flow::place(ctx, self))
+    }
+    fn route(&mut self, ctx: &mut BaseCtx<Delay>) -> bool {
+        flow::report(flow::route(ctx, self))
+    }
+}
+
+impl CellTiming {
+    /// Records a combinational arc, marking its ends as combinational unless they already have a
+    /// class.
+    pub(crate) fn add_delay(&mut self, from: IdString, to: IdString, delay: DelayQuad<Delay>) {
+        self.port_classes
+            .entry(from)
+            .or_insert(TimingPortClass::CombInput);
+        self.port_classes
+            .entry(to)
+            .or_insert(TimingPortClass::CombOutput);
+        self.comb_delays.insert((from, to), delay);
+    }
+    pub(crate) fn add_clocking(
+        &mut self,
+        port: IdString,
+        class: TimingPortClass,
+        info: TimingClockingInfo<Delay>,
+    ) {
+        self.port_classes.insert(port, class);
+        self.clocking_info.entry(port).or_default().push(info);
+    }
+}
@@ -0,0 +1,608 @@
+use super::arch::{
+    BelInfo, BelPinInfo, CellTiming, ClusterInfo, DecalInfo, Delay, GenericArch, GroupId,
+    GroupInfo, PipInfo, WireInfo,
+};
+use crate::arch::{BelId, DecalId, PipId, WireId};
+use crate::kernel::base_arch::BaseArch;
+use crate::kernel::base_context::BaseCtx;
+use crate::kernel::base_types::{GraphicElement, Loc};
+use crate::kernel::cell::CellInfo;
+use crate::kernel::delay::{self, DelayPair, DelayQuad, DelayTrait};
+use crate::kernel::device_db::{DeviceDb, DeviceDbError, TimingClass};
+use crate::kernel::id_string::IdString;
+use crate::kernel::id_string_list::{IdStringList, NameTable};
+use crate::kernel::port::PortType;
+use crate::kernel::timing::{ClockEdge, TimingClockingInfo, TimingPortClass};
+use crate::kernel::types::DecalXY;
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+use thunderdome::Index;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GenericArchError {
+    #[error("Name {0:?} is already taken.")]
+    DuplicateName(IdStringList),
+    #[error("Bel {0:?} doesn't exist.")]
+    UnknownBel(BelId),
+    #[error("Wire {0:?} doesn't exist.")]
+    UnknownWire(WireId),
+    #[error("Pip {0:?} doesn't exist.")]
+    UnknownPip(PipId),
+    #[error("Group {0:?} doesn't exist.")]
+    UnknownGroup(GroupId),
+    #[error("Decal {0:?} doesn't exist.")]
+    UnknownDecal(DecalId),
+    #[error("Bel {0:?} already has a pin {1:?}.")]
+    DuplicatePin(BelId, IdString),
+    #[error("There is already a bel at {0:?}.")]
+    LocationTaken(Loc),
+    #[error("Group {1:?} contains group {0:?}, so it can't be a member of it.")]
+    GroupCycle(GroupId, GroupId),
+    #[error("A cluster named {0:?} already exists.")]
+    DuplicateCluster(IdString),
+    #[error("Cluster {0:?} doesn't exist.")]
+    UnknownCluster(IdString),
+    #[error("Cell {0:?} is already in a cluster.")]
+    CellInCluster(Index<CellInfo<Delay>>),
+    #[error("Cluster {0:?} already has a cell at offset {1:?}.")]
+    OffsetTaken(IdString, Loc),
+    #[error("{0}")]
+    DeviceDb(#[from] DeviceDbError),
+}
+
+/// Builds a [`GenericArch`] one object at a time, in the spirit of nextpnr's generic arch. Names
+/// are interned by the caller, usually with the context the arch will be used from.
+pub struct GenericArchBuilder {
+    chip_name: String,
+    bels: Vec<BelInfo>,
+    wires: Vec<WireInfo>,
+    pips: Vec<PipInfo>,
+    groups: Vec<GroupInfo>,
+    decals: Vec<DecalInfo>,
+    bel_names: NameTable<BelId>,
+    wire_names: NameTable<WireId>,
+    pip_names: NameTable<PipId>,
+    group_names: NameTable<GroupId>,
+    decal_names: NameTable<DecalId>,
+    bel_by_loc: BTreeMap<Loc, BelId>,
+    cell_timing: BTreeMap<IdString, CellTiming>,
+    clusters: BTreeMap<IdString, ClusterInfo>,
+    delay_scale: Delay,
+    delay_offset: Delay,
+}
+
+// Checks `name` is free in `names`, then hands out the next id of `objects`.
+fn next_id<T: Ord + Copy, U>(
+    names: &mut NameTable<T>,
+    objects: &[U],
+    name: &IdStringList,
+    id: impl FnOnce(usize) -> T,
+) -> Result<T, GenericArchError> {
+    if names.get(name).is_some() {
+        return Err(GenericArchError::DuplicateName(name.clone()));
+    }
+    let id = id(objects.len());
+    names.insert(id, name.clone());
+    Ok(id)
+}
+
+impl GenericArchBuilder {
+    pub fn new(chip_name: &str) -> Self {
+        Self {
+            chip_name: chip_name.to_string(),
+            bels: Vec::new(),
+            wires: Vec::new(),
+            pips: Vec::new(),
+            groups: Vec::new(),
+            decals: Vec::new(),
+            bel_names: NameTable::new(),
+            wire_names: NameTable::new(),
+            pip_names: NameTable::new(),
+            group_names: NameTable::new(),
+            decal_names: NameTable::new(),
+            bel_by_loc: BTreeMap::new(),
+            cell_timing: BTreeMap::new(),
+            clusters: BTreeMap::new(),
+            delay_scale: 100,
+            delay_offset: 0,
+        }
+    }
+
+    /// Starts from everything in a device database. Each node becomes a single wire named after
+    /// its first tile wire, and bels and pips keep the database's numbering. Names are
+    /// `TILE/OBJECT`, with pips numbered within their tile as `TILE/pipN`.
+    pub fn from_device_db<D: DelayTrait>(
+        ctx: &mut BaseCtx<D>,
+        db: &DeviceDb,
+    ) -> Result<Self, GenericArchError> {
+        let missing = || DeviceDbError::InvalidReference("device object");
+        let delimiter = ctx.name_delimiter();
+        let mut builder = Self::new(db.name());
+        let mut wires = BTreeMap::new();
+        for wire in db.wires() {
+            let (tile, name) = db.wire_name(wire).ok_or_else(missing)?;
+            let name = IdStringList::parse(ctx, &format!("{tile}{delimiter}{name}"));
+            let wire_type = ctx.id(db.wire_type(wire).ok_or_else(missing)?);
+            let (x, y) = db.tile_location(db.wire_tile(wire).ok_or_else(missing)?.0);
+            wires.insert(wire, builder.add_wire(name, wire_type, x as i32, y as i32)?);
+        }
+        let wire = |wire: Option<WireId>| wire.and_then(|wire| wires.get(&wire).copied());
+        let pip_delay = |class: TimingClass| {
+            DelayQuad::with_min_max(
+                delay::Delay::with_delay(class.min_delay as Delay),
+                delay::Delay::with_delay(class.max_delay as Delay),
+            )
+        };
+        for pip in db.pips() {
+            let (tile, local) = db.pip_tile(pip).ok_or_else(missing)?;
+            let tile_name = db.tile_name(tile).ok_or_else(missing)?;
+            let name = IdStringList::parse(ctx, &format!("{tile_name}{delimiter}pip{local}"));
+            let src = wire(db.pip_src_wire(pip)).ok_or_else(missing)?;
+            let dst = wire(db.pip_dst_wire(pip)).ok_or_else(missing)?;
+            let class = db.pip_timing(pip).ok_or_else(missing)?;
+            let (x, y) = db.tile_location(tile);
+            let loc = Loc::new(x as i32, y as i32, 0);
+            builder.add_pip(name, IdString::new(), src, dst, pip_delay(class), loc)?;
+        }
+        for bel in db.bels() {
+            let (tile, name) = db.bel_name(bel).ok_or_else(missing)?;
+            let name = IdStringList::parse(ctx, &format!("{tile}{delimiter}{name}"));
+            let bel_type = ctx.id(db.bel_type(bel).ok_or_else(missing)?);
+            let loc = db.bel_location(bel).ok_or_else(missing)?;
+            let added = builder.add_bel(name, bel_type, loc, false, false)?;
+            for (pin, pin_wire, dir) in db.bel_pins(bel) {
+                let pin_wire = wire(Some(pin_wire)).ok_or_else(missing)?;
+                builder.add_bel_pin(added, ctx.id(pin), dir, pin_wire)?;
+            }
+        }
+        Ok(builder)
+    }
+
+    fn bel_mut(&mut self, bel: BelId) -> Result<&mut BelInfo, GenericArchError> {
+        bel.index()
+            .and_then(|index| self.bels.get_mut(index as usize))
+            .ok_or(GenericArchError::UnknownBel(bel))
+    }
+    fn wire_mut(&mut self, wire: WireId) -> Result<&mut WireInfo, GenericArchError> {
+        wire.index()
+            .and_then(|index| self.wires.get_mut(index as usize))
+            .ok_or(GenericArchError::UnknownWire(wire))
+    }
+    fn pip_mut(&mut self, pip: PipId) -> Result<&mut PipInfo, GenericArchError> {
+        pip.index()
+            .and_then(|index| self.pips.get_mut(index as usize))
+            .ok_or(GenericArchError::UnknownPip(pip))
+    }
+    fn group_mut(&mut self, group: GroupId) -> Result<&mut GroupInfo, GenericArchError> {
+        self.groups
+            .get_mut(group.index() as usize)
+            .ok_or(GenericArchError::UnknownGroup(group))
+    }
+    fn decal_mut(&mut self, decal: DecalId) -> Result<&mut DecalInfo, GenericArchError> {
+        decal
+            .index()
+            .and_then(|index| self.decals.get_mut(index as usize))
+            .ok_or(GenericArchError::UnknownDecal(decal))
+    }
+
+    // Wires, pips and bels
+    /// Adds a wire whose routing distance is measured from tile `x`, `y`.
+    pub fn add_wire(
+        &mut self,
+        name: IdStringList,
+        wire_type: IdString,
+        x: i32,
+        y: i32,
+    ) -> Result<WireId, GenericArchError> {
+        let wire = next_id(&mut self.wire_names, &self.wires, &name, |index| {
+            WireId::with_index(index as u64)
+        })?;
+        self.wires.push(WireInfo {
+            name,
+            wire_type,
+            x,
+            y,
+            downhill: Vec::new(),
+            uphill: Vec::new(),
+            bel_pins: Vec::new(),
+            attrs: Vec::new(),
+            decal: None,
+        });
+        Ok(wire)
+    }
+    pub fn add_pip(
+        &mut self,
+        name: IdStringList,
+        pip_type: IdString,
+        src: WireId,
+        dst: WireId,
+        delay: DelayQuad<Delay>,
+        loc: Loc,
+    ) -> Result<PipId, GenericArchError> {
+        self.wire_mut(src)?;
+        self.wire_mut(dst)?;
+        let pip = next_id(&mut self.pip_names, &self.pips, &name, |index| {
+            PipId::with_index(index as u64)
+        })?;
+        self.pips.push(PipInfo {
+            name,
+            pip_type,
+            src,
+            dst,
+            delay,
+            loc,
+            attrs: Vec::new(),
+            decal: None,
+        });
+        self.wire_mut(src)?.downhill.push(pip);
+        self.wire_mut(dst)?.uphill.push(pip);
+        Ok(pip)
+    }
+    pub fn add_bel(
+        &mut self,
+        name: IdStringList,
+        bel_type: IdString,
+        loc: Loc,
+        global_buf: bool,
+        hidden: bool,
+    ) -> Result<BelId, GenericArchError> {
+        if self.bel_by_loc.contains_key(&loc) {
+            return Err(GenericArchError::LocationTaken(loc));
+        }
+        let bel = next_id(&mut self.bel_names, &self.bels, &name, |index| {
+            BelId::with_index(index as u64)
+        })?;
+        self.bels.push(BelInfo {
+            name,
+            bel_type,
+            loc,
+            global_buf,
+            hidden,
+            pins: BTreeMap::new(),
+            attrs: Vec::new(),
+            decal: None,
+        });
+        self.bel_by_loc.insert(loc, bel);
+        Ok(bel)
+    }
+    /// Adds a pin to `bel` on `wire`.
+    pub fn add_bel_pin(
+        &mut self,
+        bel: BelId,
+        pin: IdString,
+        dir: PortType,
+        wire: WireId,
+    ) -> Result<(), GenericArchError> {
+        self.wire_mut(wire)?;
+        let pins = &mut self.bel_mut(bel)?.pins;
+        if pins.contains_key(&pin) {
+            return Err(GenericArchError::DuplicatePin(bel, pin));
+        }
+        pins.insert(pin, BelPinInfo { wire, dir });
+        self.wire_mut(wire)?.bel_pins.push((bel, pin));
+        Ok(())
+    }
+    pub fn add_bel_input(
+        &mut self,
+        bel: BelId,
+        pin: IdString,
+        wire: WireId,
+    ) -> Result<(), GenericArchError> {
+        self.add_bel_pin(bel, pin, PortType::In, wire)
+    }
+    pub fn add_bel_output(
+        &mut self,
+        bel: BelId,
+        pin: IdString,
+        wire: WireId,
+    ) -> Result<(), GenericArchError> {
+        self.add_bel_pin(bel, pin, PortType::Out, wire)
+    }
+    pub fn add_bel_inout(
+        &mut self,
+        bel: BelId,
+        pin: IdString,
+        wire: WireId,
+    ) -> Result<(), GenericArchError> {
+        self.add_bel_pin(bel, pin, PortType::InOut, wire)
+    }
+
+    // Attributes
+    pub fn add_bel_attribute(
+        &mut self,
+        bel: BelId,
+        key: IdString,
+        value: &str,
+    ) -> Result<(), GenericArchError> {
+        self.bel_mut(bel)?.attrs.push((key, value.to_string()));
+        Ok(())
+    }
+    pub fn add_wire_attribute(
+        &mut self,
+        wire: WireId,
+        key: IdString,
+        value: &str,
+    ) -> Result<(), GenericArchError> {
+        self.wire_mut(wire)?.attrs.push((key, value.to_string()));
+        Ok(())
+    }
+    pub fn add_pip_attribute(
+        &mut self,
+        pip: PipId,
+        key: IdString,
+        value: &str,
+    ) -> Result<(), GenericArchError> {
+        self.pip_mut(pip)?.attrs.push((key, value.to_string()));
+        Ok(())
+    }
+
+    // Groups
+    pub fn add_group(&mut self, name: IdStringList) -> Result<GroupId, GenericArchError> {
+        let group = next_id(&mut self.group_names, &self.groups, &name, |index| {
+            GroupId::with_index(index as u32)
+        })?;
+        self.groups.push(GroupInfo {
+            name,
+            ..Default::default()
+        });
+        Ok(group)
+    }
+    pub fn add_group_bel(&mut self, group: GroupId, bel: BelId) -> Result<(), GenericArchError> {
+        self.bel_mut(bel)?;
+        self.group_mut(group)?.bels.push(bel);
+        Ok(())
+    }
+    pub fn add_group_wire(&mut self, group: GroupId, wire: WireId) -> Result<(), GenericArchError> {
+        self.wire_mut(wire)?;
+        self.group_mut(group)?.wires.push(wire);
+        Ok(())
+    }
+    pub fn add_group_pip(&mut self, group: GroupId, pip: PipId) -> Result<(), GenericArchError> {
+        self.pip_mut(pip)?;
+        self.group_mut(group)?.pips.push(pip);
+        Ok(())
+    }
+    /// Makes `member` part of `group`. A group can't contain itself, directly or through other
+    /// groups.
+    pub fn add_group_group(
+        &mut self,
+        group: GroupId,
+        member: GroupId,
+    ) -> Result<(), GenericArchError> {
+        self.group_mut(member)?;
+        self.group_mut(group)?;
+        if self.group_contains(member, group) {
+            return Err(GenericArchError::GroupCycle(group, member));
+        }
+        self.group_mut(group)?.groups.push(member);
+        Ok(())
+    }
+    // Whether `inner` is `outer` or one of the groups inside it, however deeply nested.
+    fn group_contains(&self, outer: GroupId, inner: GroupId) -> bool {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![outer];
+        while let Some(group) = stack.pop() {
+            if group == inner {
+                return true;
+            }
+            if seen.insert(group) {
+                stack.extend(&self.groups[group.index() as usize].groups);
+            }
+        }
+        false
+    }
+
+    // Clusters
+    /// Starts a cluster of cells that are placed together, with `root` at its origin. Cells are
+    /// those of the context the arch will be used with.
+    pub fn add_cluster(
+        &mut self,
+        name: IdString,
+        root: Index<CellInfo<Delay>>,
+    ) -> Result<(), GenericArchError> {
+        if self.clusters.contains_key(&name) {
+            return Err(GenericArchError::DuplicateCluster(name));
+        }
+        self.check_unclustered(root)?;
+        self.clusters.insert(
+            name,
+            ClusterInfo {
+                root,
+                cells: vec![(root, Loc::origin())],
+            },
+        );
+        Ok(())
+    }
+    /// Adds `cell` to `cluster`, to be placed on the bel `offset` away from the root's.
+    pub fn add_cluster_cell(
+        &mut self,
+        cluster: IdString,
+        cell: Index<CellInfo<Delay>>,
+        offset: Loc,
+    ) -> Result<(), GenericArchError> {
+        self.check_unclustered(cell)?;
+        let info = self
+            .clusters
+            .get_mut(&cluster)
+            .ok_or(GenericArchError::UnknownCluster(cluster))?;
+        if info.cells.iter().any(|(_, taken)| *taken == offset) {
+            return Err(GenericArchError::OffsetTaken(cluster, offset));
+        }
+        info.cells.push((cell, offset));
+        Ok(())
+    }
+    fn check_unclustered(&self, cell: Index<CellInfo<Delay>>) -> Result<(), GenericArchError> {
+        let clustered = self
+            .clusters
+            .values()
+            .any(|cluster| cluster.cells.iter().any(|(member, _)| *member == cell));
+        if clustered {
+            return Err(GenericArchError::CellInCluster(cell));
+        }
+        Ok(())
+    }
+
+    // Decals
+    pub fn add_decal(&mut self, name: IdStringList) -> Result<DecalId, GenericArchError> {
+        let decal = next_id(&mut self.decal_names, &self.decals, &name, |index| {
+            DecalId::with_index(index as u64)
+        })?;
+        self.decals.push(DecalInfo {
+            name,
+            graphics: Vec::new(),
+        });
+        Ok(decal)
+    }
+    pub fn add_decal_graphic(
+        &mut self,
+        decal: DecalId,
+        graphic: GraphicElement,
+    ) -> Result<(), GenericArchError> {
+        self.decal_mut(decal)?.graphics.push(graphic);
+        Ok(())
+    }
+    pub fn set_bel_decal(&mut self, bel: BelId, decal: DecalXY) -> Result<(), GenericArchError> {
+        self.decal_mut(decal.decal())?;
+        self.bel_mut(bel)?.decal = Some(decal);
+        Ok(())
+    }
+    pub fn set_wire_decal(&mut self, wire: WireId, decal: DecalXY) -> Result<(), GenericArchError> {
+        self.decal_mut(decal.decal())?;
+        self.wire_mut(wire)?.decal = Some(decal);
+        Ok(())
+    }
+    pub fn set_pip_decal(&mut self, pip: PipId, decal: DecalXY) -> Result<(), GenericArchError> {
+        self.decal_mut(decal.decal())?;
+        self.pip_mut(pip)?.decal = Some(decal);
+        Ok(())
+    }
+    pub fn set_group_decal(
+        &mut self,
+        group: GroupId,
+        decal: DecalXY,
+    ) -> Result<(), GenericArchError> {
+        self.decal_mut(decal.decal())?;
+        self.group_mut(group)?.decal = Some(decal);
+        Ok(())
+    }
+
+    // Delays and cell timing
+    /// Routing delays are estimated as `offset` plus `scale` per tile of Manhattan distance.
+    pub fn set_delay_scaling(&mut self, scale: Delay, offset: Delay) {
+        self.delay_scale = scale;
+        self.delay_offset = offset;
+    }
+    /// A combinational arc from `from_port` to `to_port` of every cell of type `cell_type`.
+    pub fn add_cell_timing_delay(
+        &mut self,
+        cell_type: IdString,
+        from_port: IdString,
+        to_port: IdString,
+        delay: DelayQuad<Delay>,
+    ) {
+        self.cell_timing
+            .entry(cell_type)
+            .or_default()
+            .add_delay(from_port, to_port, delay);
+    }
+    pub fn add_cell_timing_clock(&mut self, cell_type: IdString, port: IdString) {
+        self.cell_timing
+            .entry(cell_type)
+            .or_default()
+            .port_classes
+            .insert(port, TimingPortClass::ClockInput);
+    }
+    /// Makes `port` a register input clocked by `clock`.
+    pub fn add_cell_timing_setup_hold(
+        &mut self,
+        cell_type: IdString,
+        port: IdString,
+        clock: IdString,
+        setup: DelayPair<Delay>,
+        hold: DelayPair<Delay>,
+    ) {
+        let info = TimingClockingInfo::with_timing(
+            clock,
+            ClockEdge::RisingEdge,
+            setup,
+            hold,
+            DelayQuad::new(),
+        );
+        self.cell_timing.entry(cell_type).or_default().add_clocking(
+            port,
+            TimingPortClass::RegisterInput,
+            info,
+        );
+    }
+    /// Makes `port` a register output clocked by `clock`.
+    pub fn add_cell_timing_clock_to_out(
+        &mut self,
+        cell_type: IdString,
+        port: IdString,
+        clock: IdString,
+        clock_to_q: DelayQuad<Delay>,
+    ) {
+        let info = TimingClockingInfo::with_timing(
+            clock,
+            ClockEdge::RisingEdge,
+            DelayPair::new(),
+            DelayPair::new(),
+            clock_to_q,
+        );
+        self.cell_timing.entry(cell_type).or_default().add_clocking(
+            port,
+            TimingPortClass::RegisterOutput,
+            info,
+        );
+    }
+
+    /// Seals the device, sizing the grid to fit every bel, wire and pip. The bel, wire and pip
+    /// names are registered in `ctx`, so placement and routing saved in the netlist can be
+    /// named and found again.
+    pub fn build<D: DelayTrait>(self, ctx: &mut BaseCtx<D>) -> GenericArch {
+        ctx.bel_names.clone_from(&self.bel_names);
+        ctx.wire_names.clone_from(&self.wire_names);
+        ctx.pip_names.clone_from(&self.pip_names);
+        let mut tile_bels: BTreeMap<(i32, i32), Vec<BelId>> = BTreeMap::new();
+        let mut tile_bel_dim_z: BTreeMap<(i32, i32), i32> = BTreeMap::new();
+        for (loc, bel) in self.bel_by_loc.iter() {
+            let tile = (loc.x(), loc.y());
+            tile_bels.entry(tile).or_default().push(*bel);
+            let dim_z = tile_bel_dim_z.entry(tile).or_default();
+            *dim_z = (*dim_z).max(loc.z() + 1);
+        }
+        let points = self
+            .bels
+            .iter()
+            .map(|bel| (bel.loc.x(), bel.loc.y()))
+            .chain(self.wires.iter().map(|wire| (wire.x, wire.y)))
+            .chain(self.pips.iter().map(|pip| (pip.loc.x(), pip.loc.y())));
+        let (width, height) = points.fold((0, 0), |(width, height), (x, y)| {
+            (width.max(x + 1), height.max(y + 1))
+        });
+        let bel_types: BTreeSet<IdString> = self.bels.iter().map(|bel| bel.bel_type).collect();
+        GenericArch {
+            chip_name: self.chip_name,
+            bels: self.bels,
+            wires: self.wires,
+            pips: self.pips,
+            groups: self.groups,
+            decals: self.decals,
+            bel_names: self.bel_names,
+            wire_names: self.wire_names,
+            pip_names: self.pip_names,
+            group_names: self.group_names,
+            bel_by_loc: self.bel_by_loc,
+            tile_bels,
+            tile_bel_dim_z,
+            bel_types: bel_types.into_iter().collect(),
+            cell_timing: self.cell_timing,
+            clusters: self.clusters,
+            width,
+            height,
+            delay_scale: self.delay_scale,
+            delay_offset: self.delay_offset,
+            base: BaseArch::new(),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use super::arch::{Delay, GenericArch};
+use crate::arch::{BelId, PipId, WireId};
+use crate::kernel::arch_api::ArchAPI;
+use crate::kernel::base_context::{BaseCtx, BaseCtxError, DesignObject};
+use crate::kernel::base_types::PlaceStrength;
+use crate::kernel::cell::CellInfo;
+use crate::kernel::check::Violation;
+use crate::kernel::id_string::IdString;
+use crate::kernel::net::NetInfo;
+use crate::kernel::port::PortRef;
+use crate::kernel::types::PipMap;
+use std::collections::{BTreeMap, VecDeque};
+use thiserror::Error;
+use thunderdome::Index;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FlowError {
+    #[error("Cell {0} has a type no bel of the device has.")]
+    NoBelType(DesignObject),
+    #[error("No free bel is left for cell {0}.")]
+    NoFreeBel(DesignObject),
+    #[error("Cluster {0} can't be placed anywhere on the device.")]
+    ClusterUnplaceable(String),
+    #[error("Net {0} can't be routed.")]
+    Unroutable(DesignObject),
+    #[error("{0}")]
+    Ctx(#[from] BaseCtxError),
+    #[error("{0}")]
+    Check(#[from] Violation),
+}
+
+/// Packs, places and routes the netlist in `ctx` onto `arch`, then checks the two agree on every
+/// binding.
+pub fn run(ctx: &mut BaseCtx<Delay>, arch: &mut GenericArch) -> Result<(), FlowError> {
+    pack(ctx, arch)?;
+    place(ctx, arch)?;
+    route(ctx, arch)?;
+    match ctx.check_arch(arch).into_iter().next() {
+        Some(violation) => Err(violation.into()),
+        None => Ok(()),
+    }
+}
+
+/// The generic arch has no packing rules of its own, so this only checks every cell already has
+/// the type of some bel.
+pub fn pack(ctx: &mut BaseCtx<Delay>, arch: &mut GenericArch) -> Result<(), FlowError> {
+    let cell_types = arch.get_cell_types();
+    for (_, cell) in ctx.cells.iter() {
+        if !cell_types.contains(&cell.cell_type()) {
+            return Err(FlowError::NoBelType(ctx.describe_cell(cell)));
+        }
+    }
+    ctx.debug_check_arch(arch, "packing");
+    Ok(())
+}
+
+/// Places every cell on the first free bel that takes it, clusters first. Cells that were
+/// already placed, by a BEL attribute say, stay where they are.
+pub fn place(ctx: &mut BaseCtx<Delay>, arch: &mut GenericArch) -> Result<(), FlowError> {
+    let placed: Vec<_> = ctx
+        .cells
+        .iter()
+        .filter(|(_, cell)| cell.bel().index().is_some())
+        .map(|(index, cell)| (index, cell.bel(), cell.bel_strength()))
+        .collect();
+    for (cell, bel, strength) in placed {
+        if arch.get_bound_bel_cell(bel) != Some(cell) {
+            ctx.bind_bel(arch, bel, cell, strength)?;
+        }
+    }
+    let clusters: Vec<IdString> = arch.clusters.keys().copied().collect();
+    for cluster in clusters {
+        place_cluster(ctx, arch, cluster)?;
+    }
+    let unplaced: Vec<_> = ctx
+        .cells
+        .iter()
+        .filter(|(_, cell)| cell.bel().index().is_none())
+        .map(|(index, _)| index)
+        .collect();
+    for cell in unplaced {
+        let cell_type = ctx.cells[cell].cell_type();
+        let bel = arch
+            .get_bels()
+            .find(|bel| {
+                arch.check_bel_avail(*bel) && arch.is_valid_bel_for_cell_type(cell_type, *bel)
+            })
+            .ok_or_else(|| FlowError::NoFreeBel(ctx.describe_cell(&ctx.cells[cell])))?;
+        ctx.bind_bel(arch, bel, cell, PlaceStrength::Placer)?;
+    }
+    ctx.debug_check_arch(arch, "placement");
+    Ok(())
+}
+
+/// Routes every driven net from its driver's bel pin to each user's, taking the shortest free
+/// path through the pips. Routing a net already has is kept, so this can run again after a
+/// partial rip-up.
+pub fn route(ctx: &mut BaseCtx<Delay>, arch: &mut GenericArch) -> Result<(), FlowError> {
+    let nets: Vec<_> = ctx.nets.iter().map(|(index, _)| index).collect();
+    for net in nets {
+        route_net(ctx, arch, net)?;
+    }
+    ctx.debug_check_arch(arch, "routing");
+    Ok(())
+}
+
+/// Logs a failed stage, for the [`ArchAPI`] flow methods that only say whether it worked.
+pub(crate) fn report(result: Result<(), FlowError>) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(error) => {
+            log::error!("{error}");
+            false
+        }
+    }
+}
+
+// Places the cells of `cluster` around the first root bel where all of them fit. A root that is
+// already placed keeps its bel.
+fn place_cluster(
+    ctx: &mut BaseCtx<Delay>,
+    arch: &mut GenericArch,
+    cluster: IdString,
+) -> Result<(), FlowError> {
+    let unplaceable = || FlowError::ClusterUnplaceable(ctx.id_name(cluster));
+    let root = arch
+        .get_cluster_root_cell(cluster)
+        .ok_or_else(unplaceable)?;
+    let root = ctx.cells.get(root).ok_or_else(unplaceable)?;
+    let root_type = root.cell_type();
+    let root_bels: Vec<BelId> = if root.bel().index().is_some() {
+        vec![root.bel()]
+    } else {
+        arch.get_bels()
+            .filter(|bel| arch.is_valid_bel_for_cell_type(root_type, *bel))
+            .collect()
+    };
+    let placement = root_bels
+        .into_iter()
+        .filter_map(|bel| arch.get_cluster_placement(cluster, bel))
+        .find(|placement| {
+            placement
+                .iter()
+                .all(|(cell, bel)| fits(ctx, arch, *cell, *bel))
+        })
+        .ok_or_else(unplaceable)?;
+    for (cell, bel) in placement {
+        if ctx.cells[cell].bel() != bel {
+            ctx.bind_bel(arch, bel, cell, PlaceStrength::Placer)?;
+        }
+    }
+    Ok(())
+}
+
+// Whether `cell` is on `bel` already, or is unplaced and can go there.
+fn fits(
+    ctx: &BaseCtx<Delay>,
+    arch: &GenericArch,
+    cell: Index<CellInfo<Delay>>,
+    bel: BelId,
+) -> bool {
+    let Some(cell) = ctx.cells.get(cell) else {
+        return false;
+    };
+    if cell.bel() == bel {
+        return true;
+    }
+    cell.bel().index().is_none()
+        && arch.check_bel_avail(bel)
+        && arch.is_valid_bel_for_cell_type(cell.cell_type(), bel)
+}
+
+fn route_net(
+    ctx: &mut BaseCtx<Delay>,
+    arch: &mut GenericArch,
+    net: Index<NetInfo<Delay>>,
+) -> Result<(), FlowError> {
+    let info = &ctx.nets[net];
+    if info.driver.cell.is_none() {
+        return Ok(());
+    }
+    let source = port_wire(ctx, arch, &info.driver);
+    let sinks: Option<Vec<_>> = info
+        .users
+        .iter()
+        .map(|(_, user)| port_wire(ctx, arch, user))
+        .collect();
+    let (Some(source), Some(sinks)) = (source, sinks) else {
+        return Err(FlowError::Unroutable(ctx.describe_net(info)));
+    };
+    // Routing the net lists but the arch lost, from a checkpoint say, is bound again first.
+    let listed: Vec<_> = info
+        .wires()
+        .iter()
+        .filter(|(wire, _)| arch.get_bound_wire_net(**wire) != Some(net))
+        .map(|(wire, pip_map)| (*wire, *pip_map))
+        .collect();
+    for (wire, pip_map) in listed {
+        match pip_map.pip() {
+            pip if pip.index().is_some() => ctx.bind_pip(arch, pip, net, pip_map.strength())?,
+            _ => ctx.bind_wire(arch, wire, net, pip_map.strength())?,
+        }
+    }
+    if arch.get_bound_wire_net(source) != Some(net) {
+        ctx.bind_wire(arch, source, net, PlaceStrength::Placer)?;
+    }
+    for sink in sinks {
+        let Some(path) = find_path(ctx, arch, net, sink) else {
+            return Err(FlowError::Unroutable(ctx.describe_net(&ctx.nets[net])));
+        };
+        for pip in path {
+            // A pip the arch already has for this net only needs listing on the net.
+            if arch.get_bound_pip_net(pip) == Some(net) {
+                let wire = arch.get_pip_dst_wire(pip);
+                ctx.nets[net].set_wire(wire, PipMap::with_pip(pip, PlaceStrength::Placer));
+            } else {
+                ctx.bind_pip(arch, pip, net, PlaceStrength::Placer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The wire a placed cell's port is on, through the first bel pin the port maps to.
+fn port_wire(ctx: &BaseCtx<Delay>, arch: &GenericArch, port: &PortRef<Delay>) -> Option<WireId> {
+    let cell = ctx.cells.get(port.cell?)?;
+    cell.bel().index()?;
+    let pin = *arch.get_bel_pins_for_cell_pin(cell, port.port).first()?;
+    arch.get_bel_pin_wire(cell.bel(), pin)
+}
+
+// A breadth first search from the wires `net` already uses to `sink`, over pips and wires that
+// are free or already bound to `net`. The pips come back in order from the net outwards.
+fn find_path(
+    ctx: &BaseCtx<Delay>,
+    arch: &GenericArch,
+    net: Index<NetInfo<Delay>>,
+    sink: WireId,
+) -> Option<Vec<PipId>> {
+    let routed = ctx.nets[net].wires();
+    if routed.contains_key(&sink) {
+        return Some(Vec::new());
+    }
+    let mut queue: VecDeque<WireId> = routed.keys().copied().collect();
+    let mut reached_by: BTreeMap<WireId, PipId> = BTreeMap::new();
+    'search: while let Some(wire) = queue.pop_front() {
+        for pip in arch.get_pips_downhill(wire) {
+            let next = arch.get_pip_dst_wire(pip);
+            if routed.contains_key(&next) || reached_by.contains_key(&next) {
+                continue;
+            }
+            let own_pip = arch.get_bound_pip_net(pip) == Some(net);
+            if !arch.check_pip_avail_for_net(pip, net) || !(arch.check_wire_avail(next) || own_pip)
+            {
+                continue;
+            }
+            reached_by.insert(next, pip);
+            if next == sink {
+                break 'search;
+            }
+            queue.push_back(next);
+        }
+    }
+    let mut path = Vec::new();
+    let mut wire = sink;
+    while let Some(pip) = reached_by.get(&wire) {
+        path.push(*pip);
+        wire = arch.get_pip_src_wire(*pip);
+    }
+    if path.is_empty() {
+        return None;
+    }
+    path.reverse();
+    Some(path)
+}
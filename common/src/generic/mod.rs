@@ -0,0 +1,7 @@
+//! An architecture described at runtime rather than by a chip database, for prototyping fabrics.
+//! Devices are built up with a [`builder::GenericArchBuilder`] and sealed into an
+//! [`arch::GenericArch`], which implements the whole [`crate::kernel::arch_api::ArchAPI`]. A netlist
+//! is then packed, placed and routed onto it with [`flow::run`].
+pub mod arch;
+pub mod builder;
+pub mod flow;
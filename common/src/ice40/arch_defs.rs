@@ -3,12 +3,18 @@ use crate::kernel::delay::DelayTrait;
 use crate::kernel::id_string::IdString;
 use crate::kernel::net::NetInfo;
 use hashers::oz::DJB2Hasher;
+use serde::{Deserialize, Serialize};
 use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
 use thunderdome::Index;
 
+/// Separates the levels of a hierarchical device object name, such as `X3/Y7/lc2`.
+pub const NAME_DELIMITER: char = '/';
+
+pub use super::constids::CONSTIDS;
+
 pub trait BelIdTrait {}
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct BelId {
     index: Option<u64>,
 }
@@ -54,6 +60,12 @@ impl BelId {
             index: None,
         }
     }
+    pub const fn with_index(index: u64) -> Self {
+        Self { index: Some(index) }
+    }
+    pub const fn index(&self) -> Option<u64> {
+        self.index
+    }
     //    pub const fn hash(&self) -> Option<u64> {
     //        self.index
     //    }
@@ -65,7 +77,7 @@ impl Default for BelId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct WireId {
     index: Option<u64>,
 }
@@ -124,6 +136,9 @@ impl WireId {
     pub const fn hash(&self) -> u64 {
         self.index.unwrap()
     }
+    pub const fn index(&self) -> Option<u64> {
+        self.index
+    }
 }
 
 impl Default for WireId {
@@ -132,7 +147,7 @@ impl Default for WireId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct PipId {
     index: Option<u64>,
 }
@@ -150,6 +165,12 @@ impl PipId {
             index: None,
         }
     }
+    pub const fn with_index(index: u64) -> Self {
+        Self { index: Some(index) }
+    }
+    pub const fn index(&self) -> Option<u64> {
+        self.index
+    }
     //    pub const fn hash(&self) -> Option<u64> {
     //        self.index
     //    }
@@ -183,7 +204,7 @@ impl const PartialOrd for PipId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub enum GroupType {
     None,
     Frame,
@@ -235,7 +256,7 @@ impl Default for GroupType {
     }
 }
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, Serialize, Deserialize)]
 pub struct GroupId {
     gtype: GroupType,
     index: Option<u64>,
@@ -323,6 +344,16 @@ impl DecalId {
             active: false,
         }
     }
+    pub const fn with_index(index: u64) -> Self {
+        Self {
+            dtype: DecalType::new(),
+            index: Some(index),
+            active: true,
+        }
+    }
+    pub const fn index(&self) -> Option<u64> {
+        self.index
+    }
 
     pub fn get_hash(&self) -> u64 {
         let mut hasher = BuildHasherDefault::<DJB2Hasher>::default().build_hasher();
@@ -356,7 +387,7 @@ impl Hash for DecalId {
     }
 }
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, Serialize, Deserialize)]
 pub struct ArchNetInfo {
     is_global: bool,
     is_reset: bool,
@@ -389,7 +420,7 @@ impl const PartialEq for ArchNetInfo {
     }
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct LcInfo<D>
 where
     D: DelayTrait,
@@ -407,7 +438,7 @@ where
     srd: Index<NetInfo<D>>,
 }
 
-#[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
 pub struct IoInfo {
     lvds: bool,
     global: bool,
@@ -416,7 +447,7 @@ pub struct IoInfo {
     // TODO: clk packing checks...
 }
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, Serialize, Deserialize)]
 pub struct GbInfo {
     for_pad_in: bool,
 }
@@ -433,7 +464,7 @@ impl const PartialEq for GbInfo {
     }
 }
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq)]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, Serialize, Deserialize)]
 pub struct LedInfo {
     led_cur_connected: bool,
 }
@@ -451,8 +482,11 @@ impl const PartialEq for LedInfo {
 }
 
 // TODO: Does this need to be a C style Union? repr(C)?
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub enum CellEnum<D> where D: DelayTrait {
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CellEnum<D>
+where
+    D: DelayTrait,
+{
     Lc(LcInfo<D>),
     Io(IoInfo),
     Gb(GbInfo),
@@ -483,13 +517,14 @@ pub enum CellEnum<D> where D: DelayTrait {
 //}
 
 // TODO: Does this need to be repr(C)?
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ArchCellInfo<D>
 where
     D: DelayTrait,
 {
     base_cluster_info: BaseClusterInfo<D>,
-    cell: CellEnum<D>,
+    // None until the packer has decided what kind of ice40 cell this is.
+    cell: Option<CellEnum<D>>,
 }
 
 impl<D> ArchCellInfo<D>
@@ -497,7 +532,10 @@ where
     D: DelayTrait,
 {
     pub const fn new() -> Self {
-        todo!()
+        Self {
+            base_cluster_info: BaseClusterInfo::new(),
+            cell: None,
+        }
     }
 }
 
@@ -0,0 +1,25 @@
+//! Well known strings of the iCE40 architecture, preloaded after the kernel's
+//! [`crate::kernel::constids`] when the kernel is built for iCE40.
+
+use crate::kernel::constids::constids;
+
+constids! {
+    arch:
+    // Port names
+    ID_CEN = "CEN",
+    ID_SR = "SR",
+    ID_CIN = "CIN",
+    ID_COUT = "COUT",
+    ID_LO = "LO",
+    ID_PACKAGE_PIN = "PACKAGE_PIN",
+    // Cell types
+    ID_SB_LUT4 = "SB_LUT4",
+    ID_SB_CARRY = "SB_CARRY",
+    ID_SB_DFF = "SB_DFF",
+    ID_SB_IO = "SB_IO",
+    ID_SB_GB = "SB_GB",
+    ID_ICESTORM_LC = "ICESTORM_LC",
+    ID_ICESTORM_RAM = "ICESTORM_RAM",
+    // Parameter keys
+    ID_LUT_INIT = "LUT_INIT",
+}
@@ -1 +1,2 @@
-pub mod arch_defs;
\ No newline at end of file
+pub mod arch_defs;
+pub mod constids;
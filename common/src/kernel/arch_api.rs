@@ -1,26 +1,84 @@
+use super::base_arch::BaseArch;
+use super::base_context::BaseCtx;
+use super::base_types::{ArcBounds, GraphicElement, Loc, PlaceStrength};
+use super::cell::CellInfo;
+use super::delay::{DelayQuad, DelayTrait};
+use super::net::NetInfo;
+use super::port::{PortRef, PortType};
+use super::timing::{TimingClockingInfo, TimingPortClass};
+use super::types::DecalXY;
 use crate::kernel::id_string::IdString;
-use std::marker::PhantomData;
+use crate::kernel::id_string_list::IdStringList;
+use core::fmt::Debug;
+use core::hash::Hash;
+use thiserror::Error;
+use thunderdome::Index;
 
-pub struct ArchArgs<R> {
-    phantom: PhantomData<R>,
-}
+/// What every bel, wire, pip, group, bucket and cluster id has to provide.
+pub trait ArchId = Copy + Eq + Ord + Hash + Debug;
 
-impl<R> ArchArgs<R> {
-    pub const fn new() -> Self {
-        Self {
-            phantom: PhantomData,
-        }
-    }
-}
+/// An arch whose bels, wires and pips are the ones the netlist records, so the context can keep
+/// both sides of a binding in step.
+pub trait KernelArch<D: DelayTrait> = ArchAPI<
+    Delay = D,
+    BelId = crate::arch::BelId,
+    WireId = crate::arch::WireId,
+    PipId = crate::arch::PipId,
+>;
 
-impl<R> const Default for ArchArgs<R> {
-    fn default() -> Self {
-        Self::new()
-    }
+#[derive(Error, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ArchError {
+    #[error("Bel is already bound.")]
+    BelUnavailable,
+    #[error("Wire is already bound.")]
+    WireUnavailable,
+    #[error("Pip is already bound.")]
+    PipUnavailable,
+    #[error("Bel, wire or pip is not bound.")]
+    NotBound,
 }
 
-// The specification of the Arch API (pure virtual)
-pub trait ArchAPI<R> {
+// The specification of the Arch API. Where nextpnr's BaseArch has a default, so does this trait,
+// with bindings kept in the arch's BaseArch.
+pub trait ArchAPI {
+    type Delay: DelayTrait;
+    type ArchArgs;
+    type BelId: ArchId;
+    type WireId: ArchId;
+    type PipId: ArchId;
+    type GroupId: ArchId;
+    type BelBucketId: ArchId;
+    type ClusterId: ArchId;
+
+    type AllBels<'a>: Iterator<Item = Self::BelId>
+    where
+        Self: 'a;
+    type TileBels<'a>: Iterator<Item = Self::BelId>
+    where
+        Self: 'a;
+    type BelPins<'a>: Iterator<Item = IdString>
+    where
+        Self: 'a;
+    type AllWires<'a>: Iterator<Item = Self::WireId>
+    where
+        Self: 'a;
+    type WireBelPins<'a>: Iterator<Item = (Self::BelId, IdString)>
+    where
+        Self: 'a;
+    type AllPips<'a>: Iterator<Item = Self::PipId>
+    where
+        Self: 'a;
+    type DownhillPips<'a>: Iterator<Item = Self::PipId>
+    where
+        Self: 'a;
+    type UphillPips<'a>: Iterator<Item = Self::PipId>
+    where
+        Self: 'a;
+
+    // Bindings for the default bind and unbind methods
+    fn base_arch(&self) -> &BaseArch<Self>;
+    fn base_arch_mut(&mut self) -> &mut BaseArch<Self>;
+
     // Basic config
     fn arch_id(&self) -> IdString {
         Default::default()
@@ -28,125 +86,305 @@ pub trait ArchAPI<R> {
     fn get_chip_name(&self) -> String {
         Default::default()
     }
-    fn arch_args(&self) -> ArchArgs<R> {
-        Default::default()
+    fn arch_args(&self) -> &Self::ArchArgs;
+    fn arch_args_to_id(&self, _args: &Self::ArchArgs) -> IdString {
+        IdString::new()
+    }
+    fn get_grid_dim_x(&self) -> i32;
+    fn get_grid_dim_y(&self) -> i32;
+    fn get_tile_bel_dim_z(&self, x: i32, y: i32) -> i32;
+    fn get_tile_pip_dim_z(&self, _x: i32, _y: i32) -> i32 {
+        1
     }
-    //    virtual IdString archArgsToId(typename R::ArchArgsT args) const = 0;
-    //    virtual int getGridDimX() const = 0;
-    //    virtual int getGridDimY() const = 0;
-    //    virtual int getTileBelDimZ(int x, int y) const = 0;
-    //    virtual int getTilePipDimZ(int x, int y) const = 0;
-    //    virtual char getNameDelimiter() const = 0;
+    fn get_name_delimiter(&self) -> char {
+        '/'
+    }
+
     // Bel methods
-    //    virtual typename R::AllBelsRangeT getBels() const = 0;
-    //    virtual IdStringList getBelName(BelId bel) const = 0;
-    //    virtual BelId getBelByName(IdStringList name) const = 0;
-    //    virtual uint32_t getBelChecksum(BelId bel) const = 0;
-    //    virtual void bindBel(BelId bel, CellInfo *cell, PlaceStrength strength) = 0;
-    //    virtual void unbindBel(BelId bel) = 0;
-    //    virtual Loc getBelLocation(BelId bel) const = 0;
-    //    virtual BelId getBelByLocation(Loc loc) const = 0;
-    //    virtual typename R::TileBelsRangeT getBelsByTile(int x, int y) const = 0;
-    //    virtual bool getBelGlobalBuf(BelId bel) const = 0;
-    //    virtual bool checkBelAvail(BelId bel) const = 0;
-    //    virtual CellInfo *getBoundBelCell(BelId bel) const = 0;
-    //    virtual CellInfo *getConflictingBelCell(BelId bel) const = 0;
-    //    virtual IdString getBelType(BelId bel) const = 0;
-    //    virtual bool getBelHidden(BelId bel) const = 0;
-    //    virtual typename R::BelAttrsRangeT getBelAttrs(BelId bel) const = 0;
-    //    virtual WireId getBelPinWire(BelId bel, IdString pin) const = 0;
-    //    virtual PortType getBelPinType(BelId bel, IdString pin) const = 0;
-    //    virtual typename R::BelPinsRangeT getBelPins(BelId bel) const = 0;
-    //    virtual typename R::CellBelPinRangeT getBelPinsForCellPin(const CellInfo *cell_info, IdString pin) const = 0;
+    fn get_bels(&self) -> Self::AllBels<'_>;
+    fn get_bel_name(&self, bel: Self::BelId) -> IdStringList;
+    fn get_bel_by_name(&self, name: &IdStringList) -> Option<Self::BelId>;
+    fn get_bel_checksum(&self, bel: Self::BelId) -> u32;
+    /// Binds `cell` to `bel` in the arch alone. [`BaseCtx::bind_bel`] also places the cell.
+    ///
+    /// [`BaseCtx::bind_bel`]: super::base_context::BaseCtx::bind_bel
+    fn bind_bel(
+        &mut self,
+        bel: Self::BelId,
+        cell: Index<CellInfo<Self::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        self.base_arch_mut().bind_bel(bel, cell, strength)
+    }
+    fn unbind_bel(&mut self, bel: Self::BelId) -> Result<(), ArchError> {
+        self.base_arch_mut().unbind_bel(bel)
+    }
+    fn get_bel_location(&self, bel: Self::BelId) -> Loc;
+    fn get_bel_by_location(&self, loc: Loc) -> Option<Self::BelId>;
+    fn get_bels_by_tile(&self, x: i32, y: i32) -> Self::TileBels<'_>;
+    fn get_bel_global_buf(&self, _bel: Self::BelId) -> bool {
+        false
+    }
+    fn check_bel_avail(&self, bel: Self::BelId) -> bool {
+        self.get_bound_bel_cell(bel).is_none()
+    }
+    fn get_bound_bel_cell(&self, bel: Self::BelId) -> Option<Index<CellInfo<Self::Delay>>> {
+        self.base_arch().bound_bel_cell(bel)
+    }
+    fn get_conflicting_bel_cell(&self, bel: Self::BelId) -> Option<Index<CellInfo<Self::Delay>>> {
+        self.get_bound_bel_cell(bel)
+    }
+    fn get_bel_type(&self, bel: Self::BelId) -> IdString;
+    fn get_bel_hidden(&self, _bel: Self::BelId) -> bool {
+        false
+    }
+    fn get_bel_attrs(&self, _bel: Self::BelId) -> Vec<(IdString, String)> {
+        Vec::new()
+    }
+    fn get_bel_pin_wire(&self, bel: Self::BelId, pin: IdString) -> Option<Self::WireId>;
+    fn get_bel_pin_type(&self, bel: Self::BelId, pin: IdString) -> PortType;
+    fn get_bel_pins(&self, bel: Self::BelId) -> Self::BelPins<'_>;
+    /// The bel pins a cell pin maps to, by default the pin of the same name.
+    fn get_bel_pins_for_cell_pin(
+        &self,
+        _cell: &CellInfo<Self::Delay>,
+        pin: IdString,
+    ) -> Vec<IdString> {
+        vec![pin]
+    }
+
     // Wire methods
-    //    virtual typename R::AllWiresRangeT getWires() const = 0;
-    //    virtual WireId getWireByName(IdStringList name) const = 0;
-    //    virtual IdStringList getWireName(WireId wire) const = 0;
-    //    virtual IdString getWireType(WireId wire) const = 0;
-    //    virtual typename R::WireAttrsRangeT getWireAttrs(WireId) const = 0;
-    //    virtual typename R::DownhillPipRangeT getPipsDownhill(WireId wire) const = 0;
-    //    virtual typename R::UphillPipRangeT getPipsUphill(WireId wire) const = 0;
-    //    virtual typename R::WireBelPinRangeT getWireBelPins(WireId wire) const = 0;
-    //    virtual uint32_t getWireChecksum(WireId wire) const = 0;
-    //    virtual void bindWire(WireId wire, NetInfo *net, PlaceStrength strength) = 0;
-    //    virtual void unbindWire(WireId wire) = 0;
-    //    virtual bool checkWireAvail(WireId wire) const = 0;
-    //    virtual NetInfo *getBoundWireNet(WireId wire) const = 0;
-    //    virtual WireId getConflictingWireWire(WireId wire) const = 0;
-    //    virtual NetInfo *getConflictingWireNet(WireId wire) const = 0;
-    //    virtual DelayQuad getWireDelay(WireId wire) const = 0;
+    fn get_wires(&self) -> Self::AllWires<'_>;
+    fn get_wire_by_name(&self, name: &IdStringList) -> Option<Self::WireId>;
+    fn get_wire_name(&self, wire: Self::WireId) -> IdStringList;
+    fn get_wire_type(&self, _wire: Self::WireId) -> IdString {
+        IdString::new()
+    }
+    fn get_wire_attrs(&self, _wire: Self::WireId) -> Vec<(IdString, String)> {
+        Vec::new()
+    }
+    fn get_pips_downhill(&self, wire: Self::WireId) -> Self::DownhillPips<'_>;
+    fn get_pips_uphill(&self, wire: Self::WireId) -> Self::UphillPips<'_>;
+    fn get_wire_bel_pins(&self, wire: Self::WireId) -> Self::WireBelPins<'_>;
+    fn get_wire_checksum(&self, wire: Self::WireId) -> u32;
+    /// Binds `net` to `wire` in the arch alone. [`BaseCtx::bind_wire`] also routes the net.
+    ///
+    /// [`BaseCtx::bind_wire`]: super::base_context::BaseCtx::bind_wire
+    fn bind_wire(
+        &mut self,
+        wire: Self::WireId,
+        net: Index<NetInfo<Self::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        self.base_arch_mut().bind_wire(wire, net, strength)
+    }
+    fn unbind_wire(&mut self, wire: Self::WireId) -> Result<(), ArchError> {
+        self.base_arch_mut().unbind_wire(wire)
+    }
+    fn check_wire_avail(&self, wire: Self::WireId) -> bool {
+        self.get_bound_wire_net(wire).is_none()
+    }
+    fn get_bound_wire_net(&self, wire: Self::WireId) -> Option<Index<NetInfo<Self::Delay>>> {
+        self.base_arch().bound_wire_net(wire)
+    }
+    fn get_bound_wire_strength(&self, wire: Self::WireId) -> Option<PlaceStrength> {
+        self.base_arch().wire_strength(wire)
+    }
+    fn get_conflicting_wire_wire(&self, wire: Self::WireId) -> Option<Self::WireId> {
+        Some(wire)
+    }
+    fn get_conflicting_wire_net(&self, wire: Self::WireId) -> Option<Index<NetInfo<Self::Delay>>> {
+        self.get_bound_wire_net(wire)
+    }
+    fn get_wire_delay(&self, wire: Self::WireId) -> DelayQuad<Self::Delay>;
+
     // Pip methods
-    //    virtual typename R::AllPipsRangeT getPips() const = 0;
-    //    virtual PipId getPipByName(IdStringList name) const = 0;
-    //    virtual IdStringList getPipName(PipId pip) const = 0;
-    //    virtual IdString getPipType(PipId pip) const = 0;
-    //    virtual typename R::PipAttrsRangeT getPipAttrs(PipId) const = 0;
-    //    virtual uint32_t getPipChecksum(PipId pip) const = 0;
-    //    virtual void bindPip(PipId pip, NetInfo *net, PlaceStrength strength) = 0;
-    //    virtual void unbindPip(PipId pip) = 0;
-    //    virtual bool checkPipAvail(PipId pip) const = 0;
-    //    virtual bool checkPipAvailForNet(PipId pip, NetInfo *net) const = 0;
-    //    virtual NetInfo *getBoundPipNet(PipId pip) const = 0;
-    //    virtual WireId getConflictingPipWire(PipId pip) const = 0;
-    //    virtual NetInfo *getConflictingPipNet(PipId pip) const = 0;
-    //    virtual WireId getPipSrcWire(PipId pip) const = 0;
-    //    virtual WireId getPipDstWire(PipId pip) const = 0;
-    //    virtual DelayQuad getPipDelay(PipId pip) const = 0;
-    //    virtual Loc getPipLocation(PipId pip) const = 0;
-    // Group methods
-    //    virtual GroupId getGroupByName(IdStringList name) const = 0;
-    //    virtual IdStringList getGroupName(GroupId group) const = 0;
-    //    virtual typename R::AllGroupsRangeT getGroups() const = 0;
-    //    virtual typename R::GroupBelsRangeT getGroupBels(GroupId group) const = 0;
-    //    virtual typename R::GroupWiresRangeT getGroupWires(GroupId group) const = 0;
-    //    virtual typename R::GroupPipsRangeT getGroupPips(GroupId group) const = 0;
-    //    virtual typename R::GroupGroupsRangeT getGroupGroups(GroupId group) const = 0;
-    // Delay Methods
-    //    virtual delay_t predictDelay(BelId src_bel, IdString src_pin, BelId dst_bel, IdString dst_pin) const = 0;
-    //    virtual delay_t getDelayEpsilon() const = 0;
-    //    virtual delay_t getRipupDelayPenalty() const = 0;
-    //    virtual float getDelayNS(delay_t v) const = 0;
-    //    virtual delay_t getDelayFromNS(float ns) const = 0;
-    //    virtual uint32_t getDelayChecksum(delay_t v) const = 0;
-    //    virtual bool getBudgetOverride(const NetInfo *net_info, const PortRef &sink, delay_t &budget) const = 0;
-    //    virtual delay_t estimateDelay(WireId src, WireId dst) const = 0;
-    //    virtual ArcBounds getRouteBoundingBox(WireId src, WireId dst) const = 0;
-    // Decal methods
-    //    virtual typename R::DecalGfxRangeT getDecalGraphics(DecalId decal) const = 0;
-    //    virtual DecalXY getBelDecal(BelId bel) const = 0;
-    //    virtual DecalXY getWireDecal(WireId wire) const = 0;
-    //    virtual DecalXY getPipDecal(PipId pip) const = 0;
-    //    virtual DecalXY getGroupDecal(GroupId group) const = 0;
+    fn get_pips(&self) -> Self::AllPips<'_>;
+    fn get_pip_by_name(&self, name: &IdStringList) -> Option<Self::PipId>;
+    fn get_pip_name(&self, pip: Self::PipId) -> IdStringList;
+    fn get_pip_type(&self, _pip: Self::PipId) -> IdString {
+        IdString::new()
+    }
+    fn get_pip_attrs(&self, _pip: Self::PipId) -> Vec<(IdString, String)> {
+        Vec::new()
+    }
+    fn get_pip_checksum(&self, pip: Self::PipId) -> u32;
+    /// Binds `net` to `pip` and the wire it drives, in the arch alone like
+    /// [`ArchAPI::bind_wire`].
+    fn bind_pip(
+        &mut self,
+        pip: Self::PipId,
+        net: Index<NetInfo<Self::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        let wire = self.get_pip_dst_wire(pip);
+        self.base_arch_mut().bind_pip(pip, wire, net, strength)
+    }
+    fn unbind_pip(&mut self, pip: Self::PipId) -> Result<(), ArchError> {
+        let wire = self.get_pip_dst_wire(pip);
+        self.base_arch_mut().unbind_pip(pip, wire)
+    }
+    fn check_pip_avail(&self, pip: Self::PipId) -> bool {
+        self.get_bound_pip_net(pip).is_none()
+    }
+    fn check_pip_avail_for_net(&self, pip: Self::PipId, net: Index<NetInfo<Self::Delay>>) -> bool {
+        self.get_bound_pip_net(pip)
+            .map_or(true, |bound| bound == net)
+    }
+    fn get_bound_pip_net(&self, pip: Self::PipId) -> Option<Index<NetInfo<Self::Delay>>> {
+        self.base_arch().bound_pip_net(pip)
+    }
+    fn get_bound_pip_strength(&self, pip: Self::PipId) -> Option<PlaceStrength> {
+        self.base_arch().pip_strength(pip)
+    }
+    fn get_conflicting_pip_wire(&self, _pip: Self::PipId) -> Option<Self::WireId> {
+        None
+    }
+    fn get_conflicting_pip_net(&self, pip: Self::PipId) -> Option<Index<NetInfo<Self::Delay>>> {
+        self.get_bound_pip_net(pip)
+    }
+    fn get_pip_src_wire(&self, pip: Self::PipId) -> Self::WireId;
+    fn get_pip_dst_wire(&self, pip: Self::PipId) -> Self::WireId;
+    fn get_pip_delay(&self, pip: Self::PipId) -> DelayQuad<Self::Delay>;
+    fn get_pip_location(&self, pip: Self::PipId) -> Loc;
+
+    // Group methods, arches without groups have none
+    fn get_group_by_name(&self, _name: &IdStringList) -> Option<Self::GroupId> {
+        None
+    }
+    fn get_group_name(&self, _group: Self::GroupId) -> IdStringList {
+        IdStringList::new()
+    }
+    fn get_groups(&self) -> Vec<Self::GroupId> {
+        Vec::new()
+    }
+    fn get_group_bels(&self, _group: Self::GroupId) -> Vec<Self::BelId> {
+        Vec::new()
+    }
+    fn get_group_wires(&self, _group: Self::GroupId) -> Vec<Self::WireId> {
+        Vec::new()
+    }
+    fn get_group_pips(&self, _group: Self::GroupId) -> Vec<Self::PipId> {
+        Vec::new()
+    }
+    fn get_group_groups(&self, _group: Self::GroupId) -> Vec<Self::GroupId> {
+        Vec::new()
+    }
+
+    // Delay methods
+    fn predict_delay(
+        &self,
+        src_bel: Self::BelId,
+        src_pin: IdString,
+        dst_bel: Self::BelId,
+        dst_pin: IdString,
+    ) -> Self::Delay;
+    fn get_delay_epsilon(&self) -> Self::Delay;
+    fn get_ripup_delay_penalty(&self) -> Self::Delay;
+    fn get_delay_ns(&self, delay: Self::Delay) -> f32;
+    fn get_delay_from_ns(&self, ns: f32) -> Self::Delay;
+    fn get_delay_checksum(&self, delay: Self::Delay) -> u32;
+    /// A budget to use for `sink` instead of the computed one.
+    fn get_budget_override(
+        &self,
+        _net: &NetInfo<Self::Delay>,
+        _sink: &PortRef<Self::Delay>,
+    ) -> Option<Self::Delay> {
+        None
+    }
+    fn estimate_delay(&self, src: Self::WireId, dst: Self::WireId) -> Self::Delay;
+    fn get_route_bounding_box(&self, src: Self::WireId, dst: Self::WireId) -> ArcBounds;
+
+    // Decal methods, arches without graphics have none
+    fn get_decal_graphics(&self, _decal: &DecalXY) -> Vec<GraphicElement> {
+        Vec::new()
+    }
+    fn get_bel_decal(&self, _bel: Self::BelId) -> Option<DecalXY> {
+        None
+    }
+    fn get_wire_decal(&self, _wire: Self::WireId) -> Option<DecalXY> {
+        None
+    }
+    fn get_pip_decal(&self, _pip: Self::PipId) -> Option<DecalXY> {
+        None
+    }
+    fn get_group_decal(&self, _group: Self::GroupId) -> Option<DecalXY> {
+        None
+    }
+
     // Cell timing methods
-    //    virtual bool getCellDelay(const CellInfo *cell, IdString fromPort, IdString toPort, DelayQuad &delay) const = 0;
-    //    virtual TimingPortClass getPortTimingClass(const CellInfo *cell, IdString port, int &clockInfoCount) const = 0;
-    //    virtual TimingClockingInfo getPortClockingInfo(const CellInfo *cell, IdString port, int index) const = 0;
+    fn get_cell_delay(
+        &self,
+        cell: &CellInfo<Self::Delay>,
+        from_port: IdString,
+        to_port: IdString,
+    ) -> Option<DelayQuad<Self::Delay>>;
+    /// The timing class of a cell port, and how many clocking infos it has.
+    fn get_port_timing_class(
+        &self,
+        cell: &CellInfo<Self::Delay>,
+        port: IdString,
+    ) -> (TimingPortClass, usize);
+    fn get_port_clocking_info(
+        &self,
+        cell: &CellInfo<Self::Delay>,
+        port: IdString,
+        index: usize,
+    ) -> TimingClockingInfo<Self::Delay>;
+
     // Placement validity checks
-    //    virtual bool isValidBelForCellType(IdString cell_type, BelId bel) const = 0;
-    //    virtual IdString getBelBucketName(BelBucketId bucket) const = 0;
-    //    virtual BelBucketId getBelBucketByName(IdString name) const = 0;
-    //    virtual BelBucketId getBelBucketForBel(BelId bel) const = 0;
-    //    virtual BelBucketId getBelBucketForCellType(IdString cell_type) const = 0;
-    //    virtual bool isBelLocationValid(BelId bel) const = 0;
-    //    virtual typename R::CellTypeRangeT getCellTypes() const = 0;
-    //    virtual typename R::BelBucketRangeT getBelBuckets() const = 0;
-    //    virtual typename R::BucketBelRangeT getBelsInBucket(BelBucketId bucket) const = 0;
-    // Cluster methods
-    //    virtual CellInfo *getClusterRootCell(ClusterId cluster) const = 0;
-    //    virtual ArcBounds getClusterBounds(ClusterId cluster) const = 0;
-    //    virtual Loc getClusterOffset(const CellInfo *cell) const = 0;
-    //    virtual bool isClusterStrict(const CellInfo *cell) const = 0;
-    //    virtual bool getClusterPlacement(ClusterId cluster, BelId root_bel,
-    //    std::vector<std::pair<CellInfo *, BelId>> &placement) const = 0;
-
-    // Flow methods
-    fn pack(&self) -> bool {
+    fn is_valid_bel_for_cell_type(&self, cell_type: IdString, bel: Self::BelId) -> bool {
+        self.get_bel_type(bel) == cell_type
+    }
+    fn get_bel_bucket_name(&self, bucket: Self::BelBucketId) -> IdString;
+    fn get_bel_bucket_by_name(&self, name: IdString) -> Option<Self::BelBucketId>;
+    fn get_bel_bucket_for_bel(&self, bel: Self::BelId) -> Self::BelBucketId;
+    fn get_bel_bucket_for_cell_type(&self, cell_type: IdString) -> Self::BelBucketId;
+    fn is_bel_location_valid(&self, _bel: Self::BelId) -> bool {
+        true
+    }
+    fn get_cell_types(&self) -> Vec<IdString>;
+    fn get_bel_buckets(&self) -> Vec<Self::BelBucketId>;
+    fn get_bels_in_bucket(&self, bucket: Self::BelBucketId) -> Vec<Self::BelId> {
+        self.get_bels()
+            .filter(|bel| self.get_bel_bucket_for_bel(*bel) == bucket)
+            .collect()
+    }
+
+    // Cluster methods, arches without clusters place every cell on its own
+    fn get_cluster_root_cell(
+        &self,
+        _cluster: Self::ClusterId,
+    ) -> Option<Index<CellInfo<Self::Delay>>> {
+        None
+    }
+    fn get_cluster_bounds(&self, _cluster: Self::ClusterId) -> ArcBounds {
+        ArcBounds::default()
+    }
+    fn get_cluster_offset(&self, _cell: &CellInfo<Self::Delay>) -> Loc {
+        Loc::origin()
+    }
+    fn is_cluster_strict(&self, _cell: &CellInfo<Self::Delay>) -> bool {
+        false
+    }
+    /// Where every cell of a cluster goes if its root is placed at `root_bel`, None if it can't
+    /// be placed there.
+    fn get_cluster_placement(
+        &self,
+        _cluster: Self::ClusterId,
+        _root_bel: Self::BelId,
+    ) -> Option<Vec<(Index<CellInfo<Self::Delay>>, Self::BelId)>> {
+        None
+    }
+
+    // Flow methods, each working on the netlist in `ctx` and false if the stage failed
+    fn pack(&mut self, _ctx: &mut BaseCtx<Self::Delay>) -> bool {
         false
     }
-    fn place(&self) -> bool {
+    fn place(&mut self, _ctx: &mut BaseCtx<Self::Delay>) -> bool {
         false
     }
-    fn route(&self) -> bool {
+    fn route(&mut self, _ctx: &mut BaseCtx<Self::Delay>) -> bool {
         false
     }
     fn assign_arch_info(&self) -> bool {
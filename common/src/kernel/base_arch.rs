@@ -0,0 +1,121 @@
+use super::arch_api::{ArchAPI, ArchError};
+use super::base_types::PlaceStrength;
+use super::cell::CellInfo;
+use super::net::NetInfo;
+use std::collections::BTreeMap;
+use thunderdome::Index;
+
+/// The bel, wire and pip bindings behind [`ArchAPI`]'s default bind and unbind methods.
+pub struct BaseArch<A: ArchAPI + ?Sized> {
+    bel_to_cell: BTreeMap<A::BelId, (Index<CellInfo<A::Delay>>, PlaceStrength)>,
+    wire_to_net: BTreeMap<A::WireId, (Index<NetInfo<A::Delay>>, PlaceStrength)>,
+    pip_to_net: BTreeMap<A::PipId, (Index<NetInfo<A::Delay>>, PlaceStrength)>,
+}
+
+impl<A> BaseArch<A>
+where
+    A: ArchAPI + ?Sized,
+{
+    pub const fn new() -> Self {
+        Self {
+            bel_to_cell: BTreeMap::new(),
+            wire_to_net: BTreeMap::new(),
+            pip_to_net: BTreeMap::new(),
+        }
+    }
+    pub fn bind_bel(
+        &mut self,
+        bel: A::BelId,
+        cell: Index<CellInfo<A::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        if self.bel_to_cell.contains_key(&bel) {
+            return Err(ArchError::BelUnavailable);
+        }
+        self.bel_to_cell.insert(bel, (cell, strength));
+        Ok(())
+    }
+    pub fn unbind_bel(&mut self, bel: A::BelId) -> Result<(), ArchError> {
+        self.bel_to_cell
+            .remove(&bel)
+            .map(|_| ())
+            .ok_or(ArchError::NotBound)
+    }
+    pub fn bound_bel_cell(&self, bel: A::BelId) -> Option<Index<CellInfo<A::Delay>>> {
+        self.bel_to_cell.get(&bel).map(|(cell, _)| *cell)
+    }
+    pub fn bel_strength(&self, bel: A::BelId) -> Option<PlaceStrength> {
+        self.bel_to_cell.get(&bel).map(|(_, strength)| *strength)
+    }
+    pub fn bind_wire(
+        &mut self,
+        wire: A::WireId,
+        net: Index<NetInfo<A::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        if self.wire_to_net.contains_key(&wire) {
+            return Err(ArchError::WireUnavailable);
+        }
+        self.wire_to_net.insert(wire, (net, strength));
+        Ok(())
+    }
+    pub fn unbind_wire(&mut self, wire: A::WireId) -> Result<(), ArchError> {
+        self.wire_to_net
+            .remove(&wire)
+            .map(|_| ())
+            .ok_or(ArchError::NotBound)
+    }
+    pub fn bound_wire_net(&self, wire: A::WireId) -> Option<Index<NetInfo<A::Delay>>> {
+        self.wire_to_net.get(&wire).map(|(net, _)| *net)
+    }
+    pub fn wire_strength(&self, wire: A::WireId) -> Option<PlaceStrength> {
+        self.wire_to_net.get(&wire).map(|(_, strength)| *strength)
+    }
+    /// Binds `pip` and `dst_wire`, the wire it drives, together.
+    pub fn bind_pip(
+        &mut self,
+        pip: A::PipId,
+        dst_wire: A::WireId,
+        net: Index<NetInfo<A::Delay>>,
+        strength: PlaceStrength,
+    ) -> Result<(), ArchError> {
+        if self.pip_to_net.contains_key(&pip) {
+            return Err(ArchError::PipUnavailable);
+        }
+        self.bind_wire(dst_wire, net, strength)?;
+        self.pip_to_net.insert(pip, (net, strength));
+        Ok(())
+    }
+    pub fn unbind_pip(&mut self, pip: A::PipId, dst_wire: A::WireId) -> Result<(), ArchError> {
+        self.pip_to_net.remove(&pip).ok_or(ArchError::NotBound)?;
+        self.unbind_wire(dst_wire)
+    }
+    pub fn bound_pip_net(&self, pip: A::PipId) -> Option<Index<NetInfo<A::Delay>>> {
+        self.pip_to_net.get(&pip).map(|(net, _)| *net)
+    }
+    pub fn pip_strength(&self, pip: A::PipId) -> Option<PlaceStrength> {
+        self.pip_to_net.get(&pip).map(|(_, strength)| *strength)
+    }
+    pub fn bound_bels(&self) -> impl Iterator<Item = (A::BelId, Index<CellInfo<A::Delay>>)> + '_ {
+        self.bel_to_cell
+            .iter()
+            .map(|(bel, (cell, _))| (*bel, *cell))
+    }
+    pub fn bound_wires(&self) -> impl Iterator<Item = (A::WireId, Index<NetInfo<A::Delay>>)> + '_ {
+        self.wire_to_net
+            .iter()
+            .map(|(wire, (net, _))| (*wire, *net))
+    }
+    pub fn bound_pips(&self) -> impl Iterator<Item = (A::PipId, Index<NetInfo<A::Delay>>)> + '_ {
+        self.pip_to_net.iter().map(|(pip, (net, _))| (*pip, *net))
+    }
+}
+
+impl<A> Default for BaseArch<A>
+where
+    A: ArchAPI + ?Sized,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,10 +2,11 @@
 //pub struct CellInfo;
 
 use super::{cell::CellInfo, delay::DelayTrait};
+use serde::{Deserialize, Serialize};
 
 // The 'legacy' cluster data, used for existing arches and to provide a basic implementation for arches without complex
 // clustering requirements
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BaseClusterInfo<D: DelayTrait> {
     constr_children: Vec<CellInfo<D>>,
     constr_x: i64,      // this.x - parent.x
@@ -14,8 +15,10 @@ pub struct BaseClusterInfo<D: DelayTrait> {
     constr_abs_z: bool, // parent.z := 0
 }
 
-
-impl<D> BaseClusterInfo<D> where D: DelayTrait {
+impl<D> BaseClusterInfo<D>
+where
+    D: DelayTrait,
+{
     pub const fn new() -> Self {
         Self {
             constr_children: Vec::new(),
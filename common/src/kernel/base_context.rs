@@ -1,70 +1,89 @@
-use super::base_types::Loc;
-use super::cell::{CellInfo, PseudoCell};
+use super::arch_api::{ArchError, KernelArch};
+use super::base_types::{Loc, PlaceStrength, SrcLocation};
+use super::cell::{CellError, CellInfo, PseudoCell};
+use super::constids::{ID_BEL, ID_BEL_STRENGTH, ID_ROUTING, ID_SRC};
 use super::context::Context;
 use super::delay::DelayTrait;
+use super::id_string_list::{IdStringList, NameTable};
+use super::journal::{Journal, JournalEntry, Journaled, JournaledArena, Transactional};
 use super::net::NetInfo;
-use super::port::{PortInfo, PortType};
+use super::port::{PortBus, PortInfo, PortType};
 use super::region::Region;
-use super::timing::TimingResult;
-use super::types::DecalXY;
+use super::segment::SegmentType;
+use super::timing::{CriticalPath, TimingResult};
+use super::types::{DecalXY, PipMap};
 use super::{cell::HierarchicalCell, id_string::IdString, property::Property};
-use crate::ice40::arch_defs::{BelId, DecalId, GroupId, PipId, WireId};
+use crate::arch::{BelId, DecalId, GroupId, PipId, WireId, NAME_DELIMITER};
 use core::hash::Hash;
 use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use thiserror::Error;
 use thunderdome::{Arena, Index};
 
-pub struct BaseCtx<D: DelayTrait> {
-    // Lock to perform mutating actions on the Context.
-    //    pub mutex: Mutex,
+/// Bumped whenever a change to the context makes older checkpoints unreadable.
+pub const CHECKPOINT_VERSION: u32 = 1;
 
-    // Lock to be taken by UI when wanting to access context - the yield()
-    // method will lock/unlock it when its' released the main mutex to make
-    // sure the UI is not starved.
-    //    pub ui_mutex: Mutex,
+#[derive(Serialize, Deserialize)]
+pub struct BaseCtx<D: DelayTrait> {
+    // Sharing between threads goes through GuardedBaseCtx, which holds the locks.
 
     // ID String database.
     pub idstring_idx_to_str: Vec<String>,
-    pub idstring_str_to_idx: BTreeMap<String, u64>,
+    pub idstring_str_to_idx: HashMap<String, u64>,
 
     // Temporary string backing store for logging
+    #[serde(skip)]
     pub log_strs: SmallVec<[String; 100]>,
     // Project settings and config switches
-    pub settings: BTreeMap<IdString, Property>,
+    pub settings: Journaled<BTreeMap<IdString, Property>>,
 
     // Placed nets and cells.
     //    pub nets: BTreeMap<IdString, Index>,
     //    pub cells: BTreeMap<IdString, Index>,
-    pub nets: Arena<NetInfo<D>, NetInfo<D>>,
-    pub cells: Arena<CellInfo<D>, CellInfo<D>>,
+    // Open transactions journal every change made through these and the tables below.
+    pub nets: JournaledArena<NetInfo<D>>,
+    pub cells: JournaledArena<CellInfo<D>>,
+    // Cell names to their place in the cell arena, nets are looked up through net_aliases.
+    pub(crate) cell_names: BTreeMap<IdString, Index<CellInfo<D>>>,
+    #[serde(skip)]
     pub(crate) pseudo_cells: Arena<Box<dyn PseudoCell<D>>>,
 
     // Hierarchical (non-leaf) cells by full path
-    pub hierarchy: BTreeMap<IdString, HierarchicalCell>,
+    pub hierarchy: Journaled<BTreeMap<IdString, HierarchicalCell>>,
     // This is the root of the above structure
     pub top_module: IdString,
 
     // Aliases for nets, which may have more than one name due to assignments and hierarchy
-    net_aliases: BTreeMap<IdString, Index<NetInfo<D>>>,
+    pub(crate) net_aliases: BTreeMap<IdString, Index<NetInfo<D>>>,
+
+    // Names of the device's bels, wires, pips and groups
+    pub bel_names: NameTable<BelId>,
+    pub wire_names: NameTable<WireId>,
+    pub pip_names: NameTable<PipId>,
+    pub group_names: NameTable<GroupId>,
+    // Pins of each bel and their directions, filled in by the arch like the names above
+    pub bel_pins: Journaled<BTreeMap<BelId, BTreeMap<IdString, PortType>>>,
 
     // Top-level ports
-    // Index is for PortInfo(s).
-    ports: BTreeMap<IdString, Index<PortInfo<D>>>,
+    pub ports: Journaled<BTreeMap<IdString, PortInfo<D>>>,
     // The Index is for CellInfo(s).
-    port_cells: BTreeMap<IdString, Index<CellInfo<D>>>,
+    pub port_cells: Journaled<BTreeMap<IdString, Index<CellInfo<D>>>>,
 
     // Floorplanning regions
     // The Index maps to Region(s), unique_ptrs in nextpnr
     //    region: BTreeMap<IdString, Index>,
-    region: Arena<Region, Region>,
+    region: JournaledArena<Region>,
 
     // Context meta data
-    attributes: BTreeMap<IdString, Property>,
+    attributes: Journaled<BTreeMap<IdString, Property>>,
 
-    // Fmax data post timing analysis
+    // Fmax data post timing analysis. Left out of transactions, it only describes the netlist
+    // as it was at the last analysis and is replaced by the next one.
     timing_result: TimingResult<D>,
 
     // The Index here has a type of of Context
@@ -73,6 +92,11 @@ pub struct BaseCtx<D: DelayTrait> {
     // Has the frontend loaded a design?
     design_loaded: bool,
 
+    // Undo information for the name maps and the arch's bindings, left out of checkpoints,
+    // hashing and comparison.
+    #[serde(skip)]
+    journal: Journal<D>,
+
     all_ui_reload: bool,
     frame_ui_reload: bool,
     bel_ui_reload: Arena<BelId, BelId>,
@@ -86,16 +110,21 @@ where
     D: DelayTrait,
 {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // idstring_str_to_idx is the inverse of idstring_idx_to_str, so hashing one covers both.
         self.idstring_idx_to_str.hash(state);
-        self.idstring_str_to_idx.hash(state);
         self.settings.hash(state);
-        self.log_strs.hash(state);
         // self.pseudo_cells.hash(state);
         self.nets.hash(state);
         self.cells.hash(state);
+        self.cell_names.hash(state);
         self.hierarchy.hash(state);
         self.top_module.hash(state);
         self.net_aliases.hash(state);
+        self.bel_names.hash(state);
+        self.wire_names.hash(state);
+        self.pip_names.hash(state);
+        self.group_names.hash(state);
+        self.bel_pins.hash(state);
         self.ports.hash(state);
         self.port_cells.hash(state);
         self.region.hash(state);
@@ -122,9 +151,15 @@ where
             && self.settings == other.settings
             && self.nets == other.nets
             && self.cells == other.cells
+            && self.cell_names == other.cell_names
             && self.hierarchy == other.hierarchy
             && self.top_module == other.top_module
             && self.net_aliases == other.net_aliases
+            && self.bel_names == other.bel_names
+            && self.wire_names == other.wire_names
+            && self.pip_names == other.pip_names
+            && self.group_names == other.group_names
+            && self.bel_pins == other.bel_pins
             && self.ports == other.ports
             && self.port_cells == other.port_cells
             && self.region == other.region
@@ -144,32 +179,41 @@ impl<D> BaseCtx<D>
 where
     D: DelayTrait,
 {
-    pub const fn new() -> Self {
-        Self {
+    pub fn new() -> Self {
+        let mut ctx = Self {
             idstring_idx_to_str: Vec::new(),
-            idstring_str_to_idx: BTreeMap::new(),
+            idstring_str_to_idx: HashMap::new(),
             log_strs: SmallVec::new_const(),
-            settings: BTreeMap::new(),
-            nets: Arena::new(),
-            cells: Arena::new(),
+            settings: Journaled::default(),
+            nets: JournaledArena::new(),
+            cells: JournaledArena::new(),
+            cell_names: BTreeMap::new(),
             pseudo_cells: Arena::new(),
-            hierarchy: BTreeMap::new(),
+            hierarchy: Journaled::default(),
             top_module: IdString::new(),
             net_aliases: BTreeMap::new(),
-            ports: BTreeMap::new(),
-            port_cells: BTreeMap::new(),
-            region: Arena::new(),
-            attributes: BTreeMap::new(),
+            bel_names: NameTable::new(),
+            wire_names: NameTable::new(),
+            pip_names: NameTable::new(),
+            group_names: NameTable::new(),
+            bel_pins: Journaled::default(),
+            ports: Journaled::default(),
+            port_cells: Journaled::default(),
+            region: JournaledArena::new(),
+            attributes: Journaled::default(),
             timing_result: TimingResult::new(),
             as_context: None,
             design_loaded: false,
+            journal: Journal::new(),
             all_ui_reload: true,
             frame_ui_reload: false,
             bel_ui_reload: Arena::new(),
             wire_ui_reload: Arena::new(),
             pip_ui_reload: Arena::new(),
             group_ui_reload: Arena::new(),
-        }
+        };
+        IdString::initialize_arch(&mut ctx);
+        ctx
     }
 
     pub fn get_context(&self) -> Result<&Context, BaseCtxError> {
@@ -187,36 +231,60 @@ where
     pub fn name_of(&self, name: IdString) -> Result<String, BaseCtxError> {
         name.to_string(self).ok().ok_or(BaseCtxError::NameNotFound)
     }
+    /// The name of `id` for messages, falling back to the raw id if it was never interned.
+    pub(crate) fn id_name(&self, id: IdString) -> String {
+        self.name_of(id).unwrap_or_else(|_| format!("{id:?}"))
+    }
 
+    pub const fn name_delimiter(&self) -> char {
+        NAME_DELIMITER
+    }
+    /// The registered name of a device object, or its bare index if the arch registered none.
+    fn device_name<T: Ord + Copy>(
+        &self,
+        names: &NameTable<T>,
+        object: &T,
+        index: Option<u64>,
+    ) -> String {
+        match names.name(object) {
+            Some(name) => name.to_string(self).unwrap_or_default(),
+            None => index.map_or(String::new(), |index| index.to_string()),
+        }
+    }
+    /// Looks `name` up among the registered names. The bare index an unnamed object prints as
+    /// isn't accepted, nothing here knows whether an object with that index exists.
+    fn device_by_name<T: Ord + Copy>(&self, names: &NameTable<T>, name: &str) -> Option<T> {
+        IdStringList::find(self, name).and_then(|list| names.get(&list))
+    }
     pub fn name_of_bel(&self, bel: BelId) -> String {
-        let context: &Context = self.get_context().unwrap();
-        // FIXME: What is the actual relation ship between the context types here.
-        //        let s = *context.log_strs;
-
-        todo!()
+        self.device_name(&self.bel_names, &bel, bel.index())
     }
     pub fn name_of_wire(&self, wire: WireId) -> String {
-        todo!()
+        self.device_name(&self.wire_names, &wire, wire.index())
     }
     pub fn name_of_pip(&self, pip: PipId) -> String {
-        todo!()
+        self.device_name(&self.pip_names, &pip, pip.index())
     }
     pub fn name_of_group(&self, group: GroupId) -> String {
-        todo!()
+        self.device_name(&self.group_names, &group, None)
     }
     // TODO: Rework as part of unified database structure rewrite after MVP.
     // Wrappers of arch functions that take a string and handle IdStringList parsing
     pub fn get_bel_by_name_str(&self, name: &str) -> BelId {
-        todo!()
+        self.device_by_name(&self.bel_names, name)
+            .unwrap_or_else(BelId::new)
     }
     pub fn get_wire_by_name_str(&self, name: &str) -> WireId {
-        todo!()
+        self.device_by_name(&self.wire_names, name)
+            .unwrap_or_else(WireId::new)
     }
     pub fn get_pip_by_name_str(&self, name: &str) -> PipId {
-        todo!()
+        self.device_by_name(&self.pip_names, name)
+            .unwrap_or_else(PipId::new)
     }
     pub fn get_group_by_name_str(&self, name: &str) -> GroupId {
-        todo!()
+        self.device_by_name(&self.group_names, name)
+            .unwrap_or_else(GroupId::new)
     }
     // --------------------------------------------------------------
 
@@ -239,6 +307,35 @@ where
         self.group_ui_reload.insert(group);
     }
     // --------------------------------------------------------------
+    pub fn get_cell_index(&self, name: IdString) -> Option<Index<CellInfo<D>>> {
+        self.cell_names.get(&name).copied()
+    }
+    pub fn get_cell(&self, name: IdString) -> Option<&CellInfo<D>> {
+        self.cells.get(self.get_cell_index(name)?)
+    }
+    pub fn get_net_index(&self, name: IdString) -> Option<Index<NetInfo<D>>> {
+        self.net_aliases.get(&name).copied()
+    }
+    pub fn get_net(&self, name: IdString) -> Option<&NetInfo<D>> {
+        self.nets.get(self.get_net_index(name)?)
+    }
+    /// Makes `alias` another name for the net at `net`.
+    pub fn add_net_alias(
+        &mut self,
+        alias: IdString,
+        net: Index<NetInfo<D>>,
+    ) -> Result<(), BaseCtxError> {
+        if self.net_aliases.contains_key(&alias) {
+            return Err(BaseCtxError::DuplicateNetName(self.id_name(alias)));
+        }
+        self.nets
+            .get_mut(net)
+            .ok_or(BaseCtxError::NetAliasNotFound)?
+            .add_alias(alias);
+        self.net_aliases.insert(alias, net);
+        self.journal.record(JournalEntry::NetAlias(alias, None));
+        Ok(())
+    }
     pub fn get_net_by_alias(&self, alias: NetAlias<D>) -> Option<&NetInfo<D>> {
         match alias {
             NetAlias::Id(id_str) => {
@@ -285,30 +382,463 @@ where
     }
 
     // Helper functions for Python bindings
-    pub fn create_net(&mut self, name: IdString) -> Option<&NetInfo<D>> {
-        todo!()
+    pub fn create_net(&mut self, name: IdString) -> Result<Index<NetInfo<D>>, BaseCtxError> {
+        if self.net_aliases.contains_key(&name) {
+            return Err(BaseCtxError::DuplicateNetName(self.id_name(name)));
+        }
+        let index = self.nets.insert(NetInfo::with_name(name));
+        self.net_aliases.insert(name, index);
+        self.journal.record(JournalEntry::NetAlias(name, None));
+        Ok(index)
     }
-    pub fn connect_port(&mut self, net: IdString, cell: IdString, port: IdString) {
-        todo!()
+    /// Connects `port` of `cell` to the net known as `net`. The cell must already have the port,
+    /// its direction decides whether it drives the net or uses it.
+    pub fn connect_port(
+        &mut self,
+        net: IdString,
+        cell: IdString,
+        port: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let net = self.net_by_name(net)?;
+        let cell = self.cell_by_name(cell)?;
+        self.cells[cell]
+            .connect_port(port, net, &mut self.nets)
+            .map_err(|e| self.cell_error(cell, e))
     }
-    pub fn disconnect_port(&mut self, cell: IdString, port: IdString) {
-        todo!()
+    pub fn disconnect_port(&mut self, cell: IdString, port: IdString) -> Result<(), BaseCtxError> {
+        let cell = self.cell_by_name(cell)?;
+        self.cells[cell]
+            .disconnect_port(port, &mut self.nets)
+            .map_err(|e| self.cell_error(cell, e))
     }
-    pub fn rip_up_net(&mut self, net: IdString) {
-        todo!()
+    /// Drops all routing of a net, unbinding its wires and pips in `arch`. Its connections are
+    /// kept.
+    pub fn rip_up_net<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        net: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let net = self.net_by_name(net)?;
+        let wires: Vec<_> = self.nets[net].wires().keys().copied().collect();
+        // Each wire leaves the net as soon as the arch lets it go, so if one fails the net still
+        // lists exactly the routing the arch has.
+        for wire in wires {
+            self.unroute_wire(arch, net, wire)?;
+        }
+        Ok(())
     }
-    pub fn lock_net_routing(&mut self, name: IdString) {
-        todo!()
+    /// Places `cell` on `bel`, binding it in `arch` as well. A cell that is already placed is
+    /// taken off its old bel first.
+    pub fn bind_bel<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        bel: BelId,
+        cell: Index<CellInfo<D>>,
+        strength: PlaceStrength,
+    ) -> Result<(), BaseCtxError> {
+        let Some((old, old_strength)) = self
+            .cells
+            .get(cell)
+            .map(|info| (info.bel(), info.bel_strength()))
+        else {
+            return Err(self.cell_error(cell, CellError::CellIndexNotFound));
+        };
+        // Checked before the old bel is let go, so a failed move leaves the cell where it was.
+        if !arch.check_bel_avail(bel) {
+            return Err(ArchError::BelUnavailable.into());
+        }
+        if old.index().is_some() && arch.get_bound_bel_cell(old) == Some(cell) {
+            arch.unbind_bel(old)?;
+            self.journal
+                .record(JournalEntry::Bel(old, Some((cell, old_strength))));
+        }
+        arch.bind_bel(bel, cell, strength)?;
+        self.journal.record(JournalEntry::Bel(bel, None));
+        self.cells[cell].set_bel(bel, strength);
+        Ok(())
     }
-    pub fn rename_net(&mut self, old_name: IdString, new_name: IdString) {
-        todo!()
+    /// Takes whatever cell is on `bel` off it, in `arch` and in the netlist.
+    pub fn unbind_bel<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        bel: BelId,
+    ) -> Result<(), BaseCtxError> {
+        let cell = arch.get_bound_bel_cell(bel);
+        arch.unbind_bel(bel)?;
+        let strength = cell
+            .and_then(|cell| self.cells.get(cell))
+            .map_or(PlaceStrength::None, |info| info.bel_strength());
+        self.journal
+            .record(JournalEntry::Bel(bel, cell.map(|cell| (cell, strength))));
+        if let Some(cell) = cell.and_then(|cell| self.cells.get_mut(cell)) {
+            cell.set_bel(BelId::new(), PlaceStrength::None);
+        }
+        Ok(())
+    }
+    /// Routes `net` through `wire` without a pip, as for the wire a net starts on.
+    pub fn bind_wire<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        wire: WireId,
+        net: Index<NetInfo<D>>,
+        strength: PlaceStrength,
+    ) -> Result<(), BaseCtxError> {
+        if self.nets.get(net).is_none() {
+            return Err(BaseCtxError::NetIndexNotFound);
+        }
+        arch.bind_wire(wire, net, strength)?;
+        self.journal.record(JournalEntry::Wire(wire, None));
+        self.nets[net].set_wire(wire, PipMap::with_pip(PipId::new(), strength));
+        Ok(())
+    }
+    /// Routes `net` through `pip` onto the wire it drives.
+    pub fn bind_pip<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        pip: PipId,
+        net: Index<NetInfo<D>>,
+        strength: PlaceStrength,
+    ) -> Result<(), BaseCtxError> {
+        if self.nets.get(net).is_none() {
+            return Err(BaseCtxError::NetIndexNotFound);
+        }
+        arch.bind_pip(pip, net, strength)?;
+        let wire = arch.get_pip_dst_wire(pip);
+        self.journal.record(JournalEntry::Wire(wire, None));
+        self.journal.record(JournalEntry::Pip(pip, None));
+        self.nets[net].set_wire(wire, PipMap::with_pip(pip, strength));
+        Ok(())
+    }
+    /// Takes `wire` out of the routing of whatever net uses it, along with the pip driving it.
+    pub fn unbind_wire<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        wire: WireId,
+    ) -> Result<(), BaseCtxError> {
+        let net = arch.get_bound_wire_net(wire).ok_or(ArchError::NotBound)?;
+        self.unroute_wire(arch, net, wire)
+    }
+    /// Takes `pip` and the wire it drives out of the routing of whatever net uses them.
+    pub fn unbind_pip<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        pip: PipId,
+    ) -> Result<(), BaseCtxError> {
+        let net = arch.get_bound_pip_net(pip);
+        arch.unbind_pip(pip)?;
+        let wire = arch.get_pip_dst_wire(pip);
+        if let Some(net) = net {
+            let strength = self.routed_wire(net, wire).strength();
+            self.journal
+                .record(JournalEntry::Wire(wire, Some((net, strength))));
+            self.journal
+                .record(JournalEntry::Pip(pip, Some((net, strength))));
+        }
+        if let Some(net) = net.and_then(|net| self.nets.get_mut(net)) {
+            net.remove_wire(wire);
+        }
+        Ok(())
+    }
+    // Unbinds `wire` of `net` and the pip driving it in `arch`, then takes it off the net.
+    fn unroute_wire<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        net: Index<NetInfo<D>>,
+        wire: WireId,
+    ) -> Result<(), BaseCtxError> {
+        let pip_map = self.routed_wire(net, wire);
+        let (pip, bound) = (pip_map.pip(), Some((net, pip_map.strength())));
+        if pip.index().is_some() {
+            arch.unbind_pip(pip)?;
+            self.journal.record(JournalEntry::Wire(wire, bound));
+            self.journal.record(JournalEntry::Pip(pip, bound));
+        } else {
+            arch.unbind_wire(wire)?;
+            self.journal.record(JournalEntry::Wire(wire, bound));
+        }
+        if let Some(net) = self.nets.get_mut(net) {
+            net.remove_wire(wire);
+        }
+        Ok(())
+    }
+    // How `net` reaches `wire`, no pip at no strength if the net doesn't list it.
+    fn routed_wire(&self, net: Index<NetInfo<D>>, wire: WireId) -> PipMap {
+        self.nets
+            .get(net)
+            .and_then(|info| info.wires().get(&wire))
+            .copied()
+            .unwrap_or_default()
+    }
+    /// Raises every wire of a net to user strength, so the router leaves it as it is. The
+    /// bindings `arch` has for the net are raised along with the netlist's.
+    pub fn lock_net_routing<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+        name: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let net = self.net_by_name(name)?;
+        let wires: Vec<_> = self.nets[net]
+            .wires()
+            .iter()
+            .filter(|(_, pip_map)| pip_map.strength() != PlaceStrength::User)
+            .map(|(wire, pip_map)| (*wire, *pip_map))
+            .collect();
+        for (wire, pip_map) in wires {
+            let (pip, bound) = (pip_map.pip(), Some((net, pip_map.strength())));
+            // The arch only takes a new strength by binding again.
+            if pip.index().is_some() && arch.get_bound_pip_net(pip) == Some(net) {
+                arch.unbind_pip(pip)?;
+                arch.bind_pip(pip, net, PlaceStrength::User)?;
+                self.journal.record(JournalEntry::Wire(wire, bound));
+                self.journal.record(JournalEntry::Pip(pip, bound));
+            } else if pip.index().is_none() && arch.get_bound_wire_net(wire) == Some(net) {
+                arch.unbind_wire(wire)?;
+                arch.bind_wire(wire, net, PlaceStrength::User)?;
+                self.journal.record(JournalEntry::Wire(wire, bound));
+            }
+            self.nets[net].set_wire(wire, PipMap::with_pip(pip, PlaceStrength::User));
+        }
+        Ok(())
+    }
+    /// Gives a net a new name in place of `old_name`, which can be its name or any of its
+    /// aliases. Its other names are kept.
+    pub fn rename_net(
+        &mut self,
+        old_name: IdString,
+        new_name: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let net = self.net_by_name(old_name)?;
+        if self.net_aliases.contains_key(&new_name) {
+            return Err(BaseCtxError::DuplicateNetName(self.id_name(new_name)));
+        }
+        let info = &mut self.nets[net];
+        if info.name() == old_name {
+            info.set_name(new_name);
+        } else {
+            info.remove_alias(old_name);
+            info.add_alias(new_name);
+        }
+        self.net_aliases.remove(&old_name);
+        self.net_aliases.insert(new_name, net);
+        self.journal
+            .record(JournalEntry::NetAlias(old_name, Some(net)));
+        self.journal.record(JournalEntry::NetAlias(new_name, None));
+        Ok(())
+    }
+    /// Joins the net known as `other` onto the one known as `name`, as when a wire passes
+    /// straight through a module. The driver, users, top ports and every name of `other` move
+    /// over and it is removed. Nets that are both driven, or routed, are left as they are.
+    pub fn merge_nets(&mut self, name: IdString, other: IdString) -> Result<(), BaseCtxError> {
+        let (net, other) = (self.net_by_name(name)?, self.net_by_name(other)?);
+        if net == other {
+            return Ok(());
+        }
+        let (kept, merged) = (&self.nets[net], &self.nets[other]);
+        let both_driven = kept.driver.cell.is_some() && merged.driver.cell.is_some();
+        if both_driven || !kept.wires().is_empty() || !merged.wires().is_empty() {
+            return Err(BaseCtxError::CannotMerge(
+                self.describe_net(kept),
+                self.describe_net(merged),
+            ));
+        }
+        let ports: Vec<_> = merged
+            .driver
+            .cell
+            .map(|cell| (cell, merged.driver.port))
+            .into_iter()
+            .chain(
+                merged
+                    .users
+                    .iter()
+                    .filter_map(|(_, user)| Some((user.cell?, user.port))),
+            )
+            .collect();
+        for (cell, port) in ports {
+            let info = &mut self.cells[cell];
+            let result = info
+                .disconnect_port(port, &mut self.nets)
+                .and_then(|_| info.connect_port(port, net, &mut self.nets));
+            result.map_err(|e| self.cell_error(cell, e))?;
+        }
+        if self.ports.values().any(|port| port.net == Some(other)) {
+            for port in self.ports.values_mut() {
+                if port.net == Some(other) {
+                    port.net = Some(net);
+                }
+            }
+        }
+        let merged = self
+            .nets
+            .remove(other)
+            .ok_or(BaseCtxError::NetIndexNotFound)?;
+        for alias in std::iter::once(merged.name()).chain(merged.aliases().iter().copied()) {
+            self.net_aliases.insert(alias, net);
+            self.journal
+                .record(JournalEntry::NetAlias(alias, Some(other)));
+            self.nets[net].add_alias(alias);
+        }
+        Ok(())
     }
 
-    pub fn create_cell(&mut self, name: IdString, cell_type: IdString) {
-        todo!()
+    pub fn create_cell(
+        &mut self,
+        name: IdString,
+        cell_type: IdString,
+    ) -> Result<Index<CellInfo<D>>, BaseCtxError> {
+        if self.cell_names.contains_key(&name) {
+            return Err(BaseCtxError::DuplicateCellName(self.id_name(name)));
+        }
+        let index = CellInfo::with_name(&mut self.cells, name, cell_type);
+        self.cell_names.insert(name, index);
+        self.journal.record(JournalEntry::CellName(name, None));
+        Ok(index)
     }
-    pub fn copy_bel_ports(&mut self, cell: IdString, bel: BelId) {
-        todo!()
+    /// Adds every pin of `bel` the cell doesn't have yet as a port of the same direction.
+    pub fn copy_bel_ports(&mut self, cell: IdString, bel: BelId) -> Result<(), BaseCtxError> {
+        let cell = self.cell_by_name(cell)?;
+        let pins = self
+            .bel_pins
+            .get(&bel)
+            .ok_or(BaseCtxError::UnknownBel(bel))?;
+        let cell = &mut self.cells[cell];
+        for (pin, port_type) in pins {
+            if !cell.ports().contains_key(pin) {
+                cell.add_port(*pin, *port_type);
+            }
+        }
+        Ok(())
+    }
+    /// Connects `other_port` of `other` to the net on `port` of `cell`. If that port isn't
+    /// connected yet, it gets a new net named `<cell>$conn$<port>`.
+    pub fn connect_ports(
+        &mut self,
+        cell: Index<CellInfo<D>>,
+        port: IdString,
+        other: Index<CellInfo<D>>,
+        other_port: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let net = match self.cells.get(cell).map(|info| info.ports().get(&port)) {
+            Some(Some(info)) => info.net,
+            Some(None) => return Err(self.cell_error(cell, CellError::PortNotFound)),
+            None => return Err(self.cell_error(cell, CellError::CellIndexNotFound)),
+        };
+        let net = match net {
+            Some(net) => net,
+            None => {
+                let base = format!(
+                    "{}$conn${}",
+                    self.name_of(self.cells[cell].name())?,
+                    self.name_of(port)?
+                );
+                let name = self.unique_net_name(&base);
+                let net = self.create_net(name)?;
+                self.cells[cell]
+                    .connect_port(port, net, &mut self.nets)
+                    .map_err(|e| self.cell_error(cell, e))?;
+                net
+            }
+        };
+        self.cells
+            .get_mut(other)
+            .ok_or(CellError::CellIndexNotFound)
+            .and_then(|info| info.connect_port(other_port, net, &mut self.nets))
+            .map_err(|e| self.cell_error(other, e))
+    }
+    /// Moves `port` of `cell` and its connection to `other_port` of `other`.
+    pub fn move_port_to(
+        &mut self,
+        cell: Index<CellInfo<D>>,
+        port: IdString,
+        other: Index<CellInfo<D>>,
+        other_port: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let result = match Self::two_cells_mut(&mut self.cells, cell, other) {
+            Ok((from, to)) => from.move_port_to(port, to, other_port, &mut self.nets),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| self.cell_error(cell, e))
+    }
+    /// Adds `other_port` to `other` with the direction of `port` on `cell`, on the same net.
+    pub fn copy_port_to(
+        &mut self,
+        cell: Index<CellInfo<D>>,
+        port: IdString,
+        other: Index<CellInfo<D>>,
+        other_port: IdString,
+    ) -> Result<(), BaseCtxError> {
+        let result = match Self::two_cells_mut(&mut self.cells, cell, other) {
+            Ok((from, to)) => from.copy_port_to(port, to, other_port, &mut self.nets),
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| self.cell_error(cell, e))
+    }
+    /// Moves `width` bits of a bus, bit `i` of `old` on `cell` going to bit `i` of `new` on
+    /// `new_cell`. The bits are moved in a transaction, so if one fails none of them move.
+    pub fn move_port_bus_to(
+        &mut self,
+        cell: Index<CellInfo<D>>,
+        old: PortBus,
+        new_cell: Index<CellInfo<D>>,
+        new: PortBus,
+        width: i32,
+    ) -> Result<(), BaseCtxError> {
+        self.transaction(|ctx| {
+            for i in 0..width {
+                let (old_port, new_port) = (old.port(ctx, i)?, new.port(ctx, i)?);
+                ctx.move_port_to(cell, old_port, new_cell, new_port)?;
+            }
+            Ok(())
+        })
+    }
+    /// Like [`BaseCtx::move_port_bus_to`], but the bits stay connected on `cell` as well. Again
+    /// either every bit is copied or none is.
+    pub fn copy_port_bus_to(
+        &mut self,
+        cell: Index<CellInfo<D>>,
+        old: PortBus,
+        new_cell: Index<CellInfo<D>>,
+        new: PortBus,
+        width: i32,
+    ) -> Result<(), BaseCtxError> {
+        self.transaction(|ctx| {
+            for i in 0..width {
+                let (old_port, new_port) = (old.port(ctx, i)?, new.port(ctx, i)?);
+                ctx.copy_port_to(cell, old_port, new_cell, new_port)?;
+            }
+            Ok(())
+        })
+    }
+    // Takes the arena rather than the context so the caller can still borrow the nets.
+    fn two_cells_mut(
+        cells: &mut JournaledArena<CellInfo<D>>,
+        first: Index<CellInfo<D>>,
+        second: Index<CellInfo<D>>,
+    ) -> Result<(&mut CellInfo<D>, &mut CellInfo<D>), CellError> {
+        if first == second {
+            return Err(CellError::SameCell);
+        }
+        match cells.get2_mut(first, second) {
+            (Some(first), Some(second)) => Ok((first, second)),
+            _ => Err(CellError::CellIndexNotFound),
+        }
+    }
+    /// `base` if no net has that name yet, otherwise `base$N` for the first free N.
+    fn unique_net_name(&mut self, base: &str) -> IdString {
+        let mut name = self.id(base);
+        let mut suffix = 1;
+        while self.net_aliases.contains_key(&name) {
+            name = self.id(&format!("{base}${suffix}"));
+            suffix += 1;
+        }
+        name
+    }
+    fn cell_by_name(&self, name: IdString) -> Result<Index<CellInfo<D>>, BaseCtxError> {
+        self.get_cell_index(name)
+            .ok_or_else(|| BaseCtxError::UnknownCell(self.id_name(name)))
+    }
+    fn net_by_name(&self, name: IdString) -> Result<Index<NetInfo<D>>, BaseCtxError> {
+        self.get_net_index(name)
+            .ok_or_else(|| BaseCtxError::UnknownNet(self.id_name(name)))
     }
 
     // Workaround for lack of wrappable constructors
@@ -317,11 +847,380 @@ where
         todo!()
     }
 
-    pub fn arch_info_to_attributes() {
-        todo!()
+    /// Stores placement and routing in the cell and net attributes, so it survives being written
+    /// out with the netlist.
+    pub fn arch_info_to_attributes(&mut self) {
+        // Names are worked out before anything changes, looking them up needs the whole context.
+        let placements: Vec<_> = self
+            .cells
+            .iter()
+            .filter(|(_, cell)| cell.bel().index().is_some())
+            .map(|(index, cell)| (index, self.name_of_bel(cell.bel())))
+            .collect();
+        let routings: Vec<_> = self
+            .nets
+            .iter()
+            .map(|(index, net)| {
+                // wire;pip;strength for every wire, the pip left empty where the net starts.
+                let routing: Vec<String> = net
+                    .wires()
+                    .iter()
+                    .filter(|(wire, _)| wire.index().is_some())
+                    .map(|(wire, pip_map)| {
+                        let pip = match pip_map.pip().index() {
+                            Some(_) => self.name_of_pip(pip_map.pip()),
+                            None => String::new(),
+                        };
+                        format!(
+                            "{};{};{}",
+                            self.name_of_wire(*wire),
+                            pip,
+                            pip_map.strength() as i64
+                        )
+                    })
+                    .collect();
+                (index, routing)
+            })
+            .collect();
+        for (index, bel) in placements {
+            let cell = &mut self.cells[index];
+            cell.set_attribute(ID_BEL, Property::with_str(&bel));
+            cell.set_attribute(
+                ID_BEL_STRENGTH,
+                Property::with_width(cell.bel_strength() as i64, 32),
+            );
+        }
+        for (index, routing) in routings {
+            let net = &mut self.nets[index];
+            if routing.is_empty() {
+                net.unset_attribute(ID_ROUTING);
+            } else {
+                net.set_attribute(ID_ROUTING, Property::with_str(&routing.join(";")));
+            }
+        }
     }
-    pub fn attributes_to_arch_info() {
-        todo!()
+    /// Restores placement and routing saved by [`BaseCtx::arch_info_to_attributes`].
+    pub fn attributes_to_arch_info(&mut self) -> Result<(), BaseCtxError> {
+        // Everything is parsed before the netlist changes, so errors can still describe the object.
+        let mut placements = Vec::new();
+        for (index, cell) in self.cells.iter() {
+            let Some(bel) = cell.attributes().get(&ID_BEL) else {
+                continue;
+            };
+            let bel = self.get_bel_by_name_str(property_str(bel).trim());
+            let strength = match cell.attributes().get(&ID_BEL_STRENGTH) {
+                Some(Property::Int(_, intval, _)) => PlaceStrength::try_from(*intval).ok(),
+                Some(Property::Str(_, strval)) => strval
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|s| PlaceStrength::try_from(s).ok()),
+                None => Some(PlaceStrength::User),
+            };
+            match (bel.index(), strength) {
+                (Some(_), Some(strength)) => placements.push((index, bel, strength)),
+                _ => return Err(BaseCtxError::InvalidPlacement(self.describe_cell(cell))),
+            }
+        }
+        let mut routings = Vec::new();
+        for (index, net) in self.nets.iter() {
+            let Some(routing) = net.attributes().get(&ID_ROUTING) else {
+                continue;
+            };
+            let routing = property_str(routing);
+            let fields: Vec<&str> = routing.trim().split(';').collect();
+            if fields.len() % 3 != 0 {
+                return Err(BaseCtxError::InvalidRouting(self.describe_net(net)));
+            }
+            let mut wires = Vec::with_capacity(fields.len() / 3);
+            for field in fields.chunks(3) {
+                let wire =
+                    Some(self.get_wire_by_name_str(field[0])).filter(|wire| wire.index().is_some());
+                let pip = match field[1] {
+                    "" => Some(PipId::new()),
+                    pip => Some(self.get_pip_by_name_str(pip)).filter(|p| p.index().is_some()),
+                };
+                let strength = field[2]
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|s| PlaceStrength::try_from(s).ok());
+                match (wire, pip, strength) {
+                    (Some(wire), Some(pip), Some(strength)) => {
+                        wires.push((wire, PipMap::with_pip(pip, strength)))
+                    }
+                    _ => return Err(BaseCtxError::InvalidRouting(self.describe_net(net))),
+                }
+            }
+            routings.push((index, wires));
+        }
+        for (index, bel, strength) in placements {
+            self.cells[index].set_bel(bel, strength);
+        }
+        for (index, wires) in routings {
+            for (wire, pip_map) in wires {
+                self.nets[index].set_wire(wire, pip_map);
+            }
+        }
+        Ok(())
+    }
+    // --------------------------------------------------------------
+    // Everything that journals itself, opened and closed with the name map journal.
+    fn journaled_parts(&mut self) -> [&mut dyn Transactional; 9] {
+        [
+            &mut self.nets,
+            &mut self.cells,
+            &mut self.settings,
+            &mut self.hierarchy,
+            &mut self.bel_pins,
+            &mut self.ports,
+            &mut self.port_cells,
+            &mut self.region,
+            &mut self.attributes,
+        ]
+    }
+    /// Opens a transaction, possibly inside another one. Until it is committed or rolled back,
+    /// every change to the netlist, the context's tables and, through the context's bind and
+    /// unbind methods, the arch's bindings is journaled so it can be undone as a unit. Timing
+    /// results are not.
+    pub fn begin_transaction(&mut self) {
+        self.journal.begin();
+        for part in self.journaled_parts() {
+            part.begin();
+        }
+    }
+    /// Keeps the changes made since the matching [`BaseCtx::begin_transaction`].
+    pub fn commit_transaction(&mut self) -> Result<(), BaseCtxError> {
+        if !self.journal.commit() {
+            return Err(BaseCtxError::NoTransaction);
+        }
+        for part in self.journaled_parts() {
+            part.commit();
+        }
+        Ok(())
+    }
+    /// Undoes every change made since the matching [`BaseCtx::begin_transaction`]. If that
+    /// bound or unbound anything in the arch, nothing is undone and the transaction stays open
+    /// for [`BaseCtx::rollback_transaction_in`].
+    pub fn rollback_transaction(&mut self) -> Result<(), BaseCtxError> {
+        if self.journal.has_bindings() {
+            return Err(BaseCtxError::RollbackNeedsArch);
+        }
+        self.undo_transaction(|_| Ok(()))
+    }
+    /// Undoes every change made since the matching [`BaseCtx::begin_transaction`], the bindings
+    /// in `arch` included.
+    pub fn rollback_transaction_in<A: KernelArch<D>>(
+        &mut self,
+        arch: &mut A,
+    ) -> Result<(), BaseCtxError> {
+        self.undo_transaction(|entry| Self::restore_binding(arch, entry))
+    }
+    fn undo_transaction(
+        &mut self,
+        mut restore_binding: impl FnMut(JournalEntry<D>) -> Result<(), ArchError>,
+    ) -> Result<(), BaseCtxError> {
+        let entries = self.journal.rollback().ok_or(BaseCtxError::NoTransaction)?;
+        // Everything is undone even if the arch refuses a binding, the first refusal is returned.
+        let mut refused = None;
+        for entry in entries {
+            match entry {
+                JournalEntry::CellName(name, Some(index)) => {
+                    self.cell_names.insert(name, index);
+                }
+                JournalEntry::CellName(name, None) => {
+                    self.cell_names.remove(&name);
+                }
+                JournalEntry::NetAlias(alias, Some(index)) => {
+                    self.net_aliases.insert(alias, index);
+                }
+                JournalEntry::NetAlias(alias, None) => {
+                    self.net_aliases.remove(&alias);
+                }
+                binding => {
+                    if let Err(e) = restore_binding(binding) {
+                        refused = refused.or(Some(e));
+                    }
+                }
+            }
+        }
+        for part in self.journaled_parts() {
+            part.rollback();
+        }
+        refused.map_or(Ok(()), |e| Err(e.into()))
+    }
+    // Binds whatever a journal entry says was bound before, after unbinding what is there now.
+    fn restore_binding<A: KernelArch<D>>(
+        arch: &mut A,
+        entry: JournalEntry<D>,
+    ) -> Result<(), ArchError> {
+        match entry {
+            JournalEntry::Bel(bel, old) => {
+                let bound = arch.get_bound_bel_cell(bel);
+                if bound == old.map(|(cell, _)| cell) {
+                    return Ok(());
+                }
+                if bound.is_some() {
+                    arch.unbind_bel(bel)?;
+                }
+                if let Some((cell, strength)) = old {
+                    arch.bind_bel(bel, cell, strength)?;
+                }
+            }
+            JournalEntry::Wire(wire, old) => {
+                let bound = arch
+                    .get_bound_wire_net(wire)
+                    .zip(arch.get_bound_wire_strength(wire));
+                if bound == old {
+                    return Ok(());
+                }
+                if bound.is_some() {
+                    arch.unbind_wire(wire)?;
+                }
+                if let Some((net, strength)) = old {
+                    arch.bind_wire(wire, net, strength)?;
+                }
+            }
+            // Pips take the wire they drive along, the wire's own entry then finds it as it was.
+            JournalEntry::Pip(pip, old) => {
+                let bound = arch
+                    .get_bound_pip_net(pip)
+                    .zip(arch.get_bound_pip_strength(pip));
+                if bound == old {
+                    return Ok(());
+                }
+                if bound.is_some() {
+                    arch.unbind_pip(pip)?;
+                }
+                if let Some((net, strength)) = old {
+                    arch.bind_pip(pip, net, strength)?;
+                }
+            }
+            JournalEntry::CellName(..) | JournalEntry::NetAlias(..) => {}
+        }
+        Ok(())
+    }
+    pub fn in_transaction(&self) -> bool {
+        self.journal.is_active()
+    }
+    /// Runs `edit` as one transaction, committed if it succeeds and rolled back if it fails.
+    /// Edits that bind anything in the arch go through [`BaseCtx::transaction_in`]. One that
+    /// binds anyway and then fails gets [`BaseCtxError::RollbackNeedsArch`] instead of its own
+    /// error, and the transaction stays open for [`BaseCtx::rollback_transaction_in`].
+    pub fn transaction<T, E: From<BaseCtxError>>(
+        &mut self,
+        edit: impl FnOnce(&mut Self) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.begin_transaction();
+        let result = edit(self);
+        // The transaction opened above is still the innermost one, so committing can't fail.
+        match result {
+            Ok(_) => {
+                let _ = self.commit_transaction();
+            }
+            Err(_) => self.rollback_transaction()?,
+        }
+        result
+    }
+    /// Like [`BaseCtx::transaction`], rolling back the bindings `edit` makes in `arch` as well.
+    pub fn transaction_in<A: KernelArch<D>, T, E>(
+        &mut self,
+        arch: &mut A,
+        edit: impl FnOnce(&mut Self, &mut A) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.begin_transaction();
+        let result = edit(self, arch);
+        // An arch refusing to take a binding back is already inconsistent, the edit's own
+        // result is still the one to report.
+        let _ = match result {
+            Ok(_) => self.commit_transaction(),
+            Err(_) => self.rollback_transaction_in(arch),
+        };
+        result
+    }
+    // --------------------------------------------------------------
+    /// Saves the whole context, so a later flow step or another process can pick it up with
+    /// [`BaseCtx::load_checkpoint`]. Pseudo cells and log scratch space are not saved.
+    pub fn save_checkpoint<W: Write>(&self, writer: W) -> Result<(), BaseCtxError> {
+        bincode::serialize_into(writer, &(CHECKPOINT_VERSION, self))
+            .map_err(|e| BaseCtxError::Checkpoint(e.to_string()))
+    }
+    pub fn load_checkpoint<R: Read>(mut reader: R) -> Result<Self, BaseCtxError> {
+        let checkpoint = |e: bincode::Error| BaseCtxError::Checkpoint(e.to_string());
+        let version: u32 = bincode::deserialize_from(&mut reader).map_err(checkpoint)?;
+        if version != CHECKPOINT_VERSION {
+            return Err(BaseCtxError::CheckpointVersion(version));
+        }
+        bincode::deserialize_from(reader).map_err(checkpoint)
+    }
+    // --------------------------------------------------------------
+    /// The HDL location in a `src` attribute, if the object has one that parses.
+    pub fn src_of(&self, attributes: &BTreeMap<IdString, Property>) -> Option<SrcLocation> {
+        match attributes.get(&ID_SRC)? {
+            Property::Str(_, strval) => SrcLocation::parse(strval),
+            _ => None,
+        }
+    }
+    pub fn describe_cell(&self, cell: &CellInfo<D>) -> DesignObject {
+        DesignObject {
+            name: self.name_of(cell.name()).unwrap_or_default(),
+            src: self.src_of(cell.attributes()),
+        }
+    }
+    pub fn describe_net(&self, net: &NetInfo<D>) -> DesignObject {
+        DesignObject {
+            name: self.name_of(net.name()).unwrap_or_default(),
+            src: self.src_of(net.attributes()),
+        }
+    }
+    /// Wraps an error about `cell` with its name and HDL location.
+    pub fn cell_error(&self, cell: Index<CellInfo<D>>, error: CellError) -> BaseCtxError {
+        let cell = match self.cells.get(cell) {
+            Some(cell) => self.describe_cell(cell),
+            None => DesignObject::default(),
+        };
+        BaseCtxError::Cell(cell, error)
+    }
+    /// Logs every segment of `path`, citing where its cells and nets were written in the HDL
+    /// unless the context asks for `disable_critical_path_source_print`.
+    pub fn log_critical_path(&self, path: &CriticalPath<D>) {
+        let print_source = self.get_context().map_or(true, |context| {
+            !context.disable_critical_path_source_print()
+        });
+        for segment in path.segments() {
+            let name = |id: IdString| self.name_of(id).unwrap_or_default();
+            let (from, to) = (segment.from(), segment.to());
+            log::info!(
+                "{:?} {:?} {}.{} -> {}.{}",
+                segment.segment_type(),
+                segment.delay(),
+                name(from.first()),
+                name(from.second()),
+                name(to.first()),
+                name(to.second()),
+            );
+            if !print_source {
+                continue;
+            }
+            if segment.segment_type() == SegmentType::Routing {
+                let net = self.get_net(segment.net());
+                if let Some(src) = net.and_then(|net| self.src_of(net.attributes())) {
+                    log::info!("    net {} defined at {}", name(segment.net()), src);
+                }
+            } else {
+                let cell = self.get_cell(to.first());
+                if let Some(src) = cell.and_then(|cell| self.src_of(cell.attributes())) {
+                    log::info!("    cell {} defined at {}", name(to.first()), src);
+                }
+            }
+        }
+    }
+}
+
+/// The text of a string attribute, or the bits of any other one.
+fn property_str(property: &Property) -> String {
+    match property {
+        Property::Str(_, strval) => strval.clone(),
+        other => other.to_string(),
     }
 }
 
@@ -334,7 +1233,23 @@ where
     Index(Index<NetInfo<D>>),
 }
 
-#[derive(Error, Debug, Eq, PartialEq, Copy, Clone)]
+/// A cell or net named in an error, with the HDL location it came from when known.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DesignObject {
+    pub name: String,
+    pub src: Option<SrcLocation>,
+}
+
+impl std::fmt::Display for DesignObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.src {
+            Some(src) => write!(f, "{} ({})", self.name, src),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Error, Debug, Eq, PartialEq, Clone)]
 pub enum BaseCtxError {
     #[error("Context is not initialized.")]
     ContextNotInitialized,
@@ -342,9 +1257,39 @@ pub enum BaseCtxError {
     NameNotFound,
     #[error("Net Alias not found.")]
     NetAliasNotFound,
+    #[error("A net named {0} already exists.")]
+    DuplicateNetName(String),
+    #[error("A cell named {0} already exists.")]
+    DuplicateCellName(String),
+    #[error("No cell is named {0}.")]
+    UnknownCell(String),
+    #[error("No net is named {0}.")]
+    UnknownNet(String),
+    #[error("No net is stored at that index.")]
+    NetIndexNotFound,
+    #[error("{0}")]
+    Arch(#[from] ArchError),
+    #[error("Bel {0:?} has no known pins.")]
+    UnknownBel(BelId),
+    #[error("Cell {0} has an invalid BEL or BEL_STRENGTH attribute.")]
+    InvalidPlacement(DesignObject),
+    #[error("Net {0} has an invalid ROUTING attribute.")]
+    InvalidRouting(DesignObject),
+    #[error("Cell {0}: {1}")]
+    Cell(DesignObject, CellError),
+    #[error("Nets {0} and {1} can't be merged, they are both driven or one is routed.")]
+    CannotMerge(DesignObject, DesignObject),
+    #[error("No transaction is open.")]
+    NoTransaction,
+    #[error("The transaction changed bindings in the arch, roll it back with the arch.")]
+    RollbackNeedsArch,
+    #[error("Checkpoint could not be written or read: {0}")]
+    Checkpoint(String),
+    #[error("Checkpoint is format version {0}, expected {CHECKPOINT_VERSION}.")]
+    CheckpointVersion(u32),
 }
 
-impl<D> const Default for BaseCtx<D>
+impl<D> Default for BaseCtx<D>
 where
     D: DelayTrait,
 {
@@ -353,6 +1298,88 @@ where
     }
 }
 
-pub struct GuardedBaseCtx<D>(Arc<Mutex<BaseCtx<D>>>)
+/// A context shared between threads. Placer and router workers share read guards or take the
+/// write guard to change the netlist, a UI or monitoring thread uses [`GuardedBaseCtx::observe`].
+#[derive(Clone)]
+pub struct GuardedBaseCtx<D>
+where
+    D: DelayTrait,
+{
+    ctx: Arc<RwLock<BaseCtx<D>>>,
+    // Taken by observers before the context, so a worker that yields its write guard can wait
+    // on it until the observer is done and the observer is never starved.
+    ui: Arc<Mutex<()>>,
+}
+
+impl<D> GuardedBaseCtx<D>
+where
+    D: DelayTrait,
+{
+    pub fn new(ctx: BaseCtx<D>) -> Self {
+        Self {
+            ctx: Arc::new(RwLock::new(ctx)),
+            ui: Arc::new(Mutex::new(())),
+        }
+    }
+    /// A read-only view, any number of threads can hold one at once.
+    pub fn read(&self) -> RwLockReadGuard<'_, BaseCtx<D>> {
+        self.ctx.read().expect("context lock poisoned")
+    }
+    pub fn write(&self) -> RwLockWriteGuard<'_, BaseCtx<D>> {
+        self.ctx.write().expect("context lock poisoned")
+    }
+    /// A read-only view for a thread watching progress, served in turn whenever a worker yields.
+    pub fn observe(&self) -> ObserverGuard<'_, D> {
+        let ui = self.ui.lock().expect("ui lock poisoned");
+        ObserverGuard {
+            ctx: self.read(),
+            _ui: ui,
+        }
+    }
+    /// Lets any waiting observer look at the context before taking the write guard back. Long
+    /// running workers should call this every so often.
+    pub fn yield_write<'a>(
+        &'a self,
+        guard: RwLockWriteGuard<'a, BaseCtx<D>>,
+    ) -> RwLockWriteGuard<'a, BaseCtx<D>> {
+        drop(guard);
+        drop(self.ui.lock().expect("ui lock poisoned"));
+        self.write()
+    }
+    /// Interns `s`, only taking the write guard if it is new. Must not be called while this
+    /// thread holds a guard.
+    pub fn id(&self, s: &str) -> IdString {
+        if let Some(id) = IdString::find(&self.read(), s) {
+            return id;
+        }
+        self.write().id(s)
+    }
+    /// The context back, if no other handle to it is left.
+    pub fn into_inner(self) -> Result<BaseCtx<D>, Self> {
+        let ui = self.ui;
+        match Arc::try_unwrap(self.ctx) {
+            Ok(ctx) => Ok(ctx.into_inner().expect("context lock poisoned")),
+            Err(ctx) => Err(Self { ctx, ui }),
+        }
+    }
+}
+
+/// Read access for an observer, holding the UI lock so workers wait for it when they yield.
+pub struct ObserverGuard<'a, D>
+where
+    D: DelayTrait,
+{
+    ctx: RwLockReadGuard<'a, BaseCtx<D>>,
+    _ui: MutexGuard<'a, ()>,
+}
+
+impl<D> Deref for ObserverGuard<'_, D>
 where
-    D: DelayTrait;
+    D: DelayTrait,
+{
+    type Target = BaseCtx<D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ctx
+    }
+}
@@ -1,5 +1,6 @@
 use hashers::oz::DJB2Hasher;
 use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
 use std::hash::{BuildHasher, BuildHasherDefault};
 use std::hash::{Hash, Hasher};
 
@@ -93,7 +94,7 @@ impl GraphicElement {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash)]
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct Loc {
     x: i32,
     y: i32,
@@ -114,6 +115,15 @@ impl Loc {
     pub const fn new(x: i32, y: i32, z: i32) -> Self {
         Self { x, y, z }
     }
+    pub const fn x(&self) -> i32 {
+        self.x
+    }
+    pub const fn y(&self) -> i32 {
+        self.y
+    }
+    pub const fn z(&self) -> i32 {
+        self.z
+    }
     pub fn get_hash(&self) -> u64 {
         let mut hasher = BuildHasherDefault::<DJB2Hasher>::default().build_hasher();
         self.hash(&mut hasher);
@@ -175,7 +185,7 @@ impl ArcBounds {
     }
 }
 
-#[derive(Clone, Copy, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub enum PlaceStrength {
     None = 0,
     Weak = 1,
@@ -203,3 +213,53 @@ impl PlaceStrength {
         Self::None
     }
 }
+
+impl TryFrom<i64> for PlaceStrength {
+    type Error = i64;
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Weak),
+            2 => Ok(Self::Strong),
+            3 => Ok(Self::Placer),
+            4 => Ok(Self::Fixed),
+            5 => Ok(Self::Locked),
+            6 => Ok(Self::User),
+            _ => Err(value),
+        }
+    }
+}
+
+/// An HDL source location from a Yosys `src` attribute such as `top.v:12.3-14.5`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SrcLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SrcLocation {
+    /// Yosys joins the locations of merged objects with `|`, the first one is kept.
+    pub fn parse(src: &str) -> Option<Self> {
+        let src = src.split('|').next()?;
+        let (file, range) = src.rsplit_once(':')?;
+        let start = range.split('-').next()?;
+        let (line, column) = match start.split_once('.') {
+            Some((line, column)) => (line.parse().ok()?, column.parse().ok()?),
+            None => (start.parse().ok()?, 0),
+        };
+        Some(Self {
+            file: file.to_string(),
+            line,
+            column,
+        })
+    }
+}
+
+impl std::fmt::Display for SrcLocation {
+    /// `file:line:column`, which editors and terminals know how to jump to.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
@@ -1,7 +1,7 @@
 use thiserror::Error;
 use thunderdome::{Arena, Index};
 
-use crate::ice40::arch_defs::{ArchCellInfo, BelId, ClusterId, WireId};
+use crate::arch::{ArchCellInfo, BelId, ClusterId, WireId};
 use crate::kernel::{
     base_types::Loc,
     delay::{DelayQuad, DelayTrait},
@@ -9,15 +9,16 @@ use crate::kernel::{
     timing::{TimingClockingInfo, TimingPortClass},
 };
 use core::marker::PhantomData;
-use std::collections::btree_map::Entry::Vacant;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 
 use super::base_types::PlaceStrength;
 use super::context::Context;
+use super::journal::JournaledArena;
 use super::net::NetInfo;
-use super::port::{HierarchicalPort, PortBus, PortInfo, PortRef, PortType};
+use super::port::{HierarchicalPort, PortInfo, PortRef, PortType};
 use super::property::Property;
 use super::region::Region;
 
@@ -25,7 +26,7 @@ use super::region::Region;
 pub trait PseudoCell<D>
 where
     D: DelayTrait,
-    Self: Debug,
+    Self: Debug + Send + Sync,
 {
     fn get_location(&self) -> Loc {
         Loc::origin()
@@ -41,7 +42,7 @@ where
     }
     fn get_port_clocking_info(&self, _port: IdString, _index: u64) -> TimingClockingInfo<D>
     where
-        D: ~const DelayTrait,
+        D: [const] DelayTrait,
     {
         TimingClockingInfo::new()
     }
@@ -91,13 +92,13 @@ impl<D: DelayTrait> PseudoCell<D> for RegionPlug {
 
     fn get_port_clocking_info(&self, _port: IdString, _index: u64) -> TimingClockingInfo<D>
     where
-        D: ~const DelayTrait,
+        D: [const] DelayTrait,
     {
         TimingClockingInfo::new()
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CellInfo<D>
 where
     D: DelayTrait,
@@ -193,15 +194,18 @@ pub enum CellError {
     NetIndexNotFound,
     #[error("Port not found.")]
     PortNotFound,
-    // Why is it invalid?
-    #[error("Moving and InOut port is invalid.")]
-    PortInOutMoveInvalid,
+    #[error("Ports of different directions can't be moved onto each other.")]
+    PortTypeMismatch,
     #[error("Tried to use a User Index that is None.")]
     UserIndexIsNone,
     #[error("User wasn't found in arena.")]
     UserNotFound,
     #[error("Tried to use a Net Index that is None.")]
     NetIndexIsNone,
+    #[error("Cell Index not found.")]
+    CellIndexNotFound,
+    #[error("Ports can't be moved or copied onto the cell they are on.")]
+    SameCell,
 }
 
 impl<D> CellInfo<D>
@@ -241,38 +245,69 @@ where
         self_arena.get_mut(index).unwrap().self_index = Some(index);
         index
     }
-    pub fn add_input(&mut self, name: IdString) {
-        self.ports.insert(
+    /// Creates a cell named `name` of type `cell_type` in the arena, with its `self_index` set.
+    pub fn with_name(
+        self_arena: &mut JournaledArena<Self>,
+        name: IdString,
+        cell_type: IdString,
+    ) -> Index<Self> {
+        let n = Self {
             name,
-            PortInfo {
-                name,
-                port_type: PortType::In,
-                net: self.ports[&name].net,
-                user_index: self.ports[&name].user_index,
-            },
-        );
+            cell_type,
+            ..Default::default()
+        };
+        let index = self_arena.insert(n);
+        self_arena.get_mut(index).unwrap().self_index = Some(index);
+        index
+    }
+    pub const fn name(&self) -> IdString {
+        self.name
+    }
+    pub const fn cell_type(&self) -> IdString {
+        self.cell_type
+    }
+    pub const fn hierarchy_path(&self) -> IdString {
+        self.hierarchy_path
+    }
+    pub fn set_hierarchy_path(&mut self, hierarchy_path: IdString) {
+        self.hierarchy_path = hierarchy_path;
+    }
+    pub const fn self_index(&self) -> Option<Index<Self>> {
+        self.self_index
+    }
+    pub const fn ports(&self) -> &BTreeMap<IdString, PortInfo<D>> {
+        &self.ports
+    }
+    pub const fn attributes(&self) -> &BTreeMap<IdString, Property> {
+        &self.attributes
+    }
+    pub const fn parameters(&self) -> &BTreeMap<IdString, Property> {
+        &self.parameters
+    }
+    pub const fn bel(&self) -> BelId {
+        self.bel
+    }
+    pub const fn bel_strength(&self) -> PlaceStrength {
+        self.bel_strength
+    }
+    pub fn set_bel(&mut self, bel: BelId, strength: PlaceStrength) {
+        self.bel = bel;
+        self.bel_strength = strength;
+    }
+    /// Adds a port with the given direction, keeping any existing connection.
+    pub fn add_port(&mut self, name: IdString, port_type: PortType) {
+        let port = self.ports.entry(name).or_default();
+        port.name = name;
+        port.port_type = port_type;
+    }
+    pub fn add_input(&mut self, name: IdString) {
+        self.add_port(name, PortType::In);
     }
     pub fn add_output(&mut self, name: IdString) {
-        self.ports.insert(
-            name,
-            PortInfo {
-                name,
-                port_type: PortType::Out,
-                net: self.ports[&name].net,
-                user_index: self.ports[&name].user_index,
-            },
-        );
+        self.add_port(name, PortType::Out);
     }
     pub fn add_in_out(&mut self, name: IdString) {
-        self.ports.insert(
-            name,
-            PortInfo {
-                name,
-                port_type: PortType::InOut,
-                net: self.ports[&name].net,
-                user_index: self.ports[&name].user_index,
-            },
-        );
+        self.add_port(name, PortType::InOut);
     }
     pub fn set_param(&mut self, name: IdString, value: Property) {
         self.parameters.insert(name, value);
@@ -331,150 +366,147 @@ where
         port_name: IdString,
         //        net: &NetInfo<DelayType, CellType>,
         net: Index<NetInfo<D>>,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
-        // Get the port from our btree mapping that matches the passed in port_name value.
-        // If there's nothing there just return the default value.
-        let port = self.ports.entry(port_name).or_default();
-        // Get the index of the net of the port we just looked up.
-        let net_index = port.net.ok_or(CellError::NetIndexNotFound)?;
-        // Lookup the existing net using this net index, and the net of the index passed in.
-        let (self_net, passed_net) = net_arena.get2_mut(net_index, net);
-        // Unwrap out net mapping to the port's net, and the net mapping to the passed in net index.
-        let self_net = self_net.ok_or(CellError::NetIndexNotFound)?;
-        let passed_net = passed_net.ok_or(CellError::NetIndexNotFound)?;
-        // Check if the net mapped to the port at the given port_name is the default value.
-        if *self_net == Default::default() {
-            // Update this cell's port's net index with the passed in net index.
-            port.net = Some(net);
-            match port.port_type {
-                PortType::Out => {
-                    // Update the passed in net now since we reassigned self's port's to it alraady.
-                    if passed_net.driver.cell.is_none() {
-                        passed_net.driver.cell = self.self_index;
-                        passed_net.driver.port = port_name;
-                        Ok(())
-                    } else {
-                        Err(CellError::DriverCellInUse)
-                    }
-                }
-                PortType::In | PortType::InOut => {
-                    let mut user: PortRef<D> = PortRef::new();
-                    user.cell = self.self_index;
-                    user.port = port_name;
-                    port.user_index = Some(passed_net.users.push_and_get_key(user));
+        // The port has to exist already, its direction decides how it joins the net.
+        let port = self
+            .ports
+            .get_mut(&port_name)
+            .ok_or(CellError::PortNotFound)?;
+        // A port can only ever be tied to a single net.
+        if port.net.is_some() {
+            return Err(CellError::PortAlreadyConnected);
+        }
+        // Lookup the net we are connecting to.
+        let passed_net = net_arena.get_mut(net).ok_or(CellError::NetIndexNotFound)?;
+        match port.port_type {
+            PortType::Out => {
+                // Check the driver before touching the port, so a failure leaves both sides untouched.
+                if passed_net.driver.cell.is_none() {
+                    passed_net.driver.cell = self.self_index;
+                    passed_net.driver.port = port_name;
+                    port.net = Some(net);
                     Ok(())
+                } else {
+                    Err(CellError::DriverCellInUse)
                 }
             }
-        } else {
-            Err(CellError::PortAlreadyConnected)
+            PortType::In | PortType::InOut => {
+                let mut user: PortRef<D> = PortRef::new();
+                user.cell = self.self_index;
+                user.port = port_name;
+                port.user_index = Some(passed_net.users.push(user));
+                port.net = Some(net);
+                Ok(())
+            }
         }
     }
 
     pub fn disconnect_port(
         &mut self,
         port_name: IdString,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
-        if !self.ports.contains_key(&port_name) {
-            let mut port = self
-                .ports
-                .get_mut(&port_name)
-                .ok_or(CellError::PortNotFound)?;
-            if let Some(net_index) = port.net {
-                let mut net = net_arena
-                    .get_mut(net_index)
-                    .ok_or(CellError::NetIndexNotFound)?;
-                if let Some(user_idx) = port.user_index {
-                    net.users.remove(user_idx);
-                }
-                if net.driver.cell == self.self_index && net.driver.port == port_name {
-                    net.driver.cell = None;
-                }
-                port.net = None;
+        // Like nextpnr, disconnecting a port the cell doesn't have is not an error.
+        let Some(port) = self.ports.get_mut(&port_name) else {
+            return Ok(());
+        };
+        if let Some(net_index) = port.net {
+            let net = net_arena
+                .get_mut(net_index)
+                .ok_or(CellError::NetIndexNotFound)?;
+            if let Some(user_idx) = port.user_index.take() {
+                net.users.remove(user_idx);
+            }
+            if net.driver.cell == self.self_index && net.driver.port == port_name {
+                net.driver.cell = None;
+                net.driver.port = IdString::new();
             }
+            port.net = None;
         }
         Ok(())
     }
 
+    /// Connects `other_port` of `other` to the net on `port`, which must already have one.
+    /// [`BaseCtx::connect_ports`] creates the net when there is none.
+    ///
+    /// [`BaseCtx::connect_ports`]: super::base_context::BaseCtx::connect_ports
     pub fn connect_ports(
         &mut self,
         port: IdString,
         other: &mut CellInfo<D>,
         other_port: IdString,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
-        let port_1 = self.ports.get_mut(&port).ok_or(CellError::PortNotFound)?;
-        if let Some(p1_net) = port_1.net {
-            other.connect_port(other_port, p1_net, net_arena)?
-        } else {
-            // No net on port1; need to create one
-            todo!()
-            //            let p1_net = self.context.create_net(self.context.id(self.name.to_string(self.context) + "$conn$" + port.to_string(self.context);
-        }
-
-        Ok(())
+        let port_1 = self.ports.get(&port).ok_or(CellError::PortNotFound)?;
+        let p1_net = port_1.net.ok_or(CellError::NetIndexIsNone)?;
+        other.connect_port(other_port, p1_net, net_arena)
     }
 
+    /// Moves `port` and its connection to `other_port` of `other`, adding that port with the
+    /// same direction if `other` doesn't have it. Whatever `other_port` was connected to is
+    /// disconnected first.
     pub fn move_port_to(
         &mut self,
         port: IdString,
         other: &mut CellInfo<D>,
         other_port: IdString,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
-        let mut old = self.ports.get_mut(&port).ok_or(CellError::PortNotFound)?;
-        // Create port on the replacement cell if it doesn't already exist
-        if let Vacant(e) = other.ports.entry(other_port) {
-            e.insert(PortInfo {
-                name: other_port,
-                port_type: old.port_type,
-                ..Default::default()
-            });
+        let old = self.ports.get(&port).ok_or(CellError::PortNotFound)?;
+        let (port_type, net, user_index) = (old.port_type, old.net, old.user_index);
+        if let Some(rep) = other.ports.get(&other_port) {
+            if rep.port_type != port_type {
+                return Err(CellError::PortTypeMismatch);
+            }
         }
-
-        let mut rep = other
-            .ports
-            .get_mut(&other_port)
-            .ok_or(CellError::PortNotFound)?;
-
-        assert!(old.port_type == rep.port_type);
-
-        rep.net = old.net;
-        rep.user_index = old.user_index;
-        old.net = None;
-        old.user_index = None;
-        if let Some(r_net_id) = rep.net {
-            let mut r_net = net_arena
-                .get_mut(r_net_id)
+        // Check the net still knows the port before changing anything. Like nextpnr, an inout
+        // port is one of the net's users.
+        if let Some(net_index) = net {
+            let net = net_arena
+                .get(net_index)
                 .ok_or(CellError::NetIndexNotFound)?;
-            match rep.port_type {
+            if port_type != PortType::Out {
+                let user = user_index.ok_or(CellError::UserIndexIsNone)?;
+                net.users.get(user).ok_or(CellError::UserNotFound)?;
+            }
+        }
+        other.disconnect_port(other_port, net_arena)?;
+
+        let rep = other.ports.entry(other_port).or_insert_with(|| PortInfo {
+            name: other_port,
+            port_type,
+            ..Default::default()
+        });
+        rep.net = net;
+        rep.user_index = user_index;
+        if let Some(old) = self.ports.get_mut(&port) {
+            old.net = None;
+            old.user_index = None;
+        }
+        if let Some(net_index) = net {
+            let net = &mut net_arena[net_index];
+            match port_type {
                 PortType::Out => {
-                    r_net.driver.cell = other.self_index;
-                    r_net.driver.port = other_port;
-                    Ok(())
+                    net.driver.cell = other.self_index;
+                    net.driver.port = other_port;
                 }
-                PortType::In => {
-                    let mut load = r_net
-                        .users
-                        .get_mut(rep.user_index.ok_or(CellError::UserIndexIsNone)?)
-                        .ok_or(CellError::UserNotFound)?;
-                    load.cell = other.self_index;
-                    load.port = other_port;
-                    Ok(())
+                PortType::In | PortType::InOut => {
+                    if let Some(user) = user_index.and_then(|user| net.users.get_mut(user)) {
+                        user.cell = other.self_index;
+                        user.port = other_port;
+                    }
                 }
-                PortType::InOut => Err(CellError::PortInOutMoveInvalid),
             }
-        } else {
-            Ok(())
         }
+        Ok(())
     }
 
     pub fn rename_port(
         &mut self,
         old_name: IdString,
         new_name: IdString,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
         let mut old = self
             .ports
@@ -504,56 +536,23 @@ where
         Ok(())
     }
 
-    pub fn move_port_bus_to(
-        &mut self,
-        old_port_bus: PortBus,
-        new_cell: &mut CellInfo<D>,
-        new_port_bus: PortBus,
-        width: i32,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
-    ) -> Result<(), CellError> {
-        for _i in 0..width {
-            // FIXME: correct this after implementing Context.
-            let old_port: IdString = IdString::new();
-            let new_port: IdString = IdString::new();
-            self.move_port_to(old_port, new_cell, new_port, net_arena)?;
-            todo!()
-        }
-        Ok(())
-    }
-
     pub fn copy_port_to(
         &mut self,
         port: IdString,
         other: &mut CellInfo<D>,
         other_port: IdString,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
+        net_arena: &mut JournaledArena<NetInfo<D>>,
     ) -> Result<(), CellError> {
         let self_port = self.ports.get(&port).ok_or(CellError::PortNotFound)?;
 
-        let mut other_p = other
-            .ports
-            .get_mut(&other_port)
-            .ok_or(CellError::PortNotFound)?;
+        // Create the port on the other cell if it doesn't already exist
+        let other_p = other.ports.entry(other_port).or_default();
         other_p.name = other_port;
         other_p.port_type = self_port.port_type;
-        other.connect_port(
-            other_port,
-            self_port.net.ok_or(CellError::NetIndexIsNone)?,
-            net_arena,
-        )?;
-        Ok(())
-    }
-
-    pub fn copy_port_bus_to(
-        &mut self,
-        old_port_bus: PortBus,
-        new_cell: &mut CellInfo<D>,
-        new_port_bus: PortBus,
-        width: i32,
-        net_arena: &mut Arena<NetInfo<D>, NetInfo<D>>,
-    ) {
-        todo!()
+        match self_port.net {
+            Some(net) => other.connect_port(other_port, net, net_arena),
+            None => Ok(()),
+        }
     }
 }
 
@@ -566,7 +565,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct HierarchicalCell {
     name: IdString,
     cell_type: IdString,
@@ -583,3 +582,69 @@ pub struct HierarchicalCell {
     // Name inside cell instance -> global name
     hierachical_cells: BTreeMap<IdString, IdString>,
 }
+
+impl HierarchicalCell {
+    pub const fn new(
+        name: IdString,
+        cell_type: IdString,
+        parent: IdString,
+        fullpath: IdString,
+    ) -> Self {
+        Self {
+            name,
+            cell_type,
+            parent,
+            fullpath,
+            leaf_cells: BTreeMap::new(),
+            nets: BTreeMap::new(),
+            leaf_cells_by_global_name: BTreeMap::new(),
+            nets_by_global_name: BTreeMap::new(),
+            ports: BTreeMap::new(),
+            hierachical_cells: BTreeMap::new(),
+        }
+    }
+    pub const fn name(&self) -> IdString {
+        self.name
+    }
+    pub const fn cell_type(&self) -> IdString {
+        self.cell_type
+    }
+    pub const fn parent(&self) -> IdString {
+        self.parent
+    }
+    pub const fn fullpath(&self) -> IdString {
+        self.fullpath
+    }
+    pub const fn leaf_cells(&self) -> &BTreeMap<IdString, IdString> {
+        &self.leaf_cells
+    }
+    pub const fn nets(&self) -> &BTreeMap<IdString, IdString> {
+        &self.nets
+    }
+    pub const fn ports(&self) -> &BTreeMap<IdString, HierarchicalPort> {
+        &self.ports
+    }
+    pub const fn hierachical_cells(&self) -> &BTreeMap<IdString, IdString> {
+        &self.hierachical_cells
+    }
+    pub fn leaf_cell_by_global_name(&self, global_name: IdString) -> Option<IdString> {
+        self.leaf_cells_by_global_name.get(&global_name).copied()
+    }
+    pub fn net_by_global_name(&self, global_name: IdString) -> Option<IdString> {
+        self.nets_by_global_name.get(&global_name).copied()
+    }
+    pub fn add_leaf_cell(&mut self, name: IdString, global_name: IdString) {
+        self.leaf_cells.insert(name, global_name);
+        self.leaf_cells_by_global_name.insert(global_name, name);
+    }
+    pub fn add_net(&mut self, name: IdString, global_name: IdString) {
+        self.nets.insert(name, global_name);
+        self.nets_by_global_name.insert(global_name, name);
+    }
+    pub fn add_port(&mut self, port: HierarchicalPort) {
+        self.ports.insert(port.name(), port);
+    }
+    pub fn add_hierarchical_cell(&mut self, name: IdString, fullpath: IdString) {
+        self.hierachical_cells.insert(name, fullpath);
+    }
+}
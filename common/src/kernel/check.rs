@@ -0,0 +1,344 @@
+use super::arch_api::KernelArch;
+use super::base_context::{BaseCtx, DesignObject};
+use super::cell::CellInfo;
+use super::delay::DelayTrait;
+use super::net::NetInfo;
+use super::port::{PortRef, PortType};
+use crate::arch::{BelId, WireId};
+use std::collections::BTreeMap;
+use thiserror::Error;
+use thunderdome::Index;
+
+/// A broken invariant found by [`BaseCtx::check`] or [`BaseCtx::check_arch`], naming the
+/// objects involved.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    #[error("Cell {0} is stored at a different index than it records.")]
+    CellSelfIndex(DesignObject),
+    #[error("Cell name {0} doesn't point at a live cell of that name.")]
+    CellName(String),
+    #[error("Port {port} of cell {cell} is stored under the name {key}.")]
+    PortName {
+        cell: DesignObject,
+        port: String,
+        key: String,
+    },
+    #[error("Port {port} of cell {cell} is connected to a net that doesn't exist.")]
+    PortNetMissing { cell: DesignObject, port: String },
+    #[error("Port {port} of cell {cell} isn't recorded as the driver or a user of net {net}.")]
+    PortNotOnNet {
+        cell: DesignObject,
+        port: String,
+        net: DesignObject,
+    },
+    #[error("Port {port} of cell {cell} is unconnected but still has a user index.")]
+    StaleUserIndex { cell: DesignObject, port: String },
+    #[error("Driver {driver} of net {net} isn't an output port connected to it.")]
+    DriverMismatch { net: DesignObject, driver: String },
+    #[error("User {user} of net {net} isn't an input port connected to it at that index.")]
+    UserMismatch { net: DesignObject, user: String },
+    #[error("Net alias {0} doesn't point at a live net known by that name.")]
+    NetAlias(String),
+    #[error("Top level port {0} has no port cell.")]
+    PortCellMissing(String),
+    #[error("Bel {bel} is used by both cell {first} and cell {second}.")]
+    BelConflict {
+        bel: String,
+        first: DesignObject,
+        second: DesignObject,
+    },
+    #[error("Wire {wire} is used by both net {first} and net {second}.")]
+    WireConflict {
+        wire: String,
+        first: DesignObject,
+        second: DesignObject,
+    },
+    #[error("Cell {cell} is placed on bel {bel}, which the arch has bound to {bound}.")]
+    BelBinding {
+        cell: DesignObject,
+        bel: String,
+        bound: String,
+    },
+    #[error("Net {net} is routed through wire {wire}, which the arch has bound to {bound}.")]
+    WireBinding {
+        net: DesignObject,
+        wire: String,
+        bound: String,
+    },
+    #[error("Net {net} is routed through pip {pip}, which the arch has bound to {bound}.")]
+    PipBinding {
+        net: DesignObject,
+        pip: String,
+        bound: String,
+    },
+    #[error("The arch has bel {bel} bound to {cell}, which isn't placed there.")]
+    StaleBelBinding { bel: String, cell: String },
+    #[error("The arch has wire {wire} bound to {net}, which isn't routed through it.")]
+    StaleWireBinding { wire: String, net: String },
+    #[error("The arch has pip {pip} bound to {net}, which isn't routed through it.")]
+    StalePipBinding { pip: String, net: String },
+}
+
+impl<D> BaseCtx<D>
+where
+    D: DelayTrait,
+{
+    /// Checks the netlist is consistent: ports and nets agree on every connection, names and
+    /// aliases point at live objects, and no bel or wire is used twice.
+    pub fn check(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check_cells(&mut violations);
+        self.check_nets(&mut violations);
+        for (name, index) in &self.cell_names {
+            if self.cells.get(*index).map(|cell| cell.name()) != Some(*name) {
+                violations.push(Violation::CellName(self.id_name(*name)));
+            }
+        }
+        for (alias, index) in &self.net_aliases {
+            let known = self.nets.get(*index).map_or(false, |net| {
+                net.name() == *alias || net.aliases().contains(alias)
+            });
+            if !known {
+                violations.push(Violation::NetAlias(self.id_name(*alias)));
+            }
+        }
+        for (port, index) in self.port_cells.iter() {
+            if self.cells.get(*index).is_none() {
+                violations.push(Violation::PortCellMissing(self.id_name(*port)));
+            }
+        }
+        violations
+    }
+    /// Checks the placement and routing of the netlist against what `arch` has bound, both
+    /// ways round.
+    pub fn check_arch<A: KernelArch<D>>(&self, arch: &A) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (index, cell) in self.cells.iter() {
+            let bel = cell.bel();
+            if bel.index().is_none() {
+                continue;
+            }
+            let bound = arch.get_bound_bel_cell(bel);
+            if bound != Some(index) {
+                violations.push(Violation::BelBinding {
+                    cell: self.describe_cell(cell),
+                    bel: self.name_of_bel(bel),
+                    bound: self.cell_name_at(bound),
+                });
+            }
+        }
+        for (index, net) in self.nets.iter() {
+            for (wire, pip_map) in net.wires() {
+                let bound = arch.get_bound_wire_net(*wire);
+                if bound != Some(index) {
+                    violations.push(Violation::WireBinding {
+                        net: self.describe_net(net),
+                        wire: self.name_of_wire(*wire),
+                        bound: self.net_name_at(bound),
+                    });
+                }
+                let pip = pip_map.pip();
+                if pip.index().is_none() {
+                    continue;
+                }
+                let bound = arch.get_bound_pip_net(pip);
+                if bound != Some(index) {
+                    violations.push(Violation::PipBinding {
+                        net: self.describe_net(net),
+                        pip: self.name_of_pip(pip),
+                        bound: self.net_name_at(bound),
+                    });
+                }
+            }
+        }
+        let base = arch.base_arch();
+        for (bel, cell) in base.bound_bels() {
+            if self.cells.get(cell).map(|cell| cell.bel()) != Some(bel) {
+                violations.push(Violation::StaleBelBinding {
+                    bel: self.name_of_bel(bel),
+                    cell: self.cell_name_at(Some(cell)),
+                });
+            }
+        }
+        for (wire, net) in base.bound_wires() {
+            let routed = self
+                .nets
+                .get(net)
+                .map_or(false, |net| net.wires().contains_key(&wire));
+            if !routed {
+                violations.push(Violation::StaleWireBinding {
+                    wire: self.name_of_wire(wire),
+                    net: self.net_name_at(Some(net)),
+                });
+            }
+        }
+        for (pip, net) in base.bound_pips() {
+            let wire = arch.get_pip_dst_wire(pip);
+            let routed = self.nets.get(net).map_or(false, |net| {
+                net.wires()
+                    .get(&wire)
+                    .map_or(false, |pip_map| pip_map.pip() == pip)
+            });
+            if !routed {
+                violations.push(Violation::StalePipBinding {
+                    pip: self.name_of_pip(pip),
+                    net: self.net_name_at(Some(net)),
+                });
+            }
+        }
+        violations
+    }
+    /// Runs [`BaseCtx::check`] in debug builds, panicking with every violation found so
+    /// corruption is caught by the flow stage that caused it.
+    pub fn debug_check(&self, stage: &str) {
+        if cfg!(debug_assertions) {
+            Self::panic_on_violations(stage, self.check());
+        }
+    }
+    /// Like [`BaseCtx::debug_check`], checking the netlist against the bindings in `arch` as
+    /// well.
+    pub fn debug_check_arch<A: KernelArch<D>>(&self, arch: &A, stage: &str) {
+        if cfg!(debug_assertions) {
+            let mut violations = self.check();
+            violations.extend(self.check_arch(arch));
+            Self::panic_on_violations(stage, violations);
+        }
+    }
+    fn panic_on_violations(stage: &str, violations: Vec<Violation>) {
+        if !violations.is_empty() {
+            for violation in &violations {
+                log::error!("{violation}");
+            }
+            panic!(
+                "netlist is inconsistent after {stage}, {} problems found",
+                violations.len()
+            );
+        }
+    }
+
+    fn check_cells(&self, violations: &mut Vec<Violation>) {
+        let mut bels: BTreeMap<BelId, &CellInfo<D>> = BTreeMap::new();
+        for (index, cell) in self.cells.iter() {
+            if cell.self_index() != Some(index) {
+                violations.push(Violation::CellSelfIndex(self.describe_cell(cell)));
+            }
+            if cell.bel().index().is_some() {
+                if let Some(first) = bels.insert(cell.bel(), cell) {
+                    violations.push(Violation::BelConflict {
+                        bel: self.name_of_bel(cell.bel()),
+                        first: self.describe_cell(first),
+                        second: self.describe_cell(cell),
+                    });
+                }
+            }
+            for (name, port) in cell.ports() {
+                if port.name != *name {
+                    violations.push(Violation::PortName {
+                        cell: self.describe_cell(cell),
+                        port: self.id_name(port.name),
+                        key: self.id_name(*name),
+                    });
+                }
+                let Some(net_index) = port.net else {
+                    if port.user_index.is_some() {
+                        violations.push(Violation::StaleUserIndex {
+                            cell: self.describe_cell(cell),
+                            port: self.id_name(*name),
+                        });
+                    }
+                    continue;
+                };
+                let Some(net) = self.nets.get(net_index) else {
+                    violations.push(Violation::PortNetMissing {
+                        cell: self.describe_cell(cell),
+                        port: self.id_name(*name),
+                    });
+                    continue;
+                };
+                let on_net = match port.port_type {
+                    PortType::Out => net.driver.cell == Some(index) && net.driver.port == *name,
+                    PortType::In | PortType::InOut => port
+                        .user_index
+                        .and_then(|user| net.users.get(user))
+                        .map_or(false, |user| user.cell == Some(index) && user.port == *name),
+                };
+                if !on_net {
+                    violations.push(Violation::PortNotOnNet {
+                        cell: self.describe_cell(cell),
+                        port: self.id_name(*name),
+                        net: self.describe_net(net),
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_nets(&self, violations: &mut Vec<Violation>) {
+        let mut wires: BTreeMap<WireId, &NetInfo<D>> = BTreeMap::new();
+        for (index, net) in self.nets.iter() {
+            if net.driver.cell.is_some() {
+                let driver = net
+                    .driver
+                    .cell
+                    .and_then(|cell| self.cells.get(cell))
+                    .and_then(|cell| cell.ports().get(&net.driver.port));
+                let valid = driver.map_or(false, |port| {
+                    port.port_type == PortType::Out && port.net == Some(index)
+                });
+                if !valid {
+                    violations.push(Violation::DriverMismatch {
+                        net: self.describe_net(net),
+                        driver: self.port_ref_name(&net.driver),
+                    });
+                }
+            }
+            for (user_id, user) in net.users.iter() {
+                let port = user
+                    .cell
+                    .and_then(|cell| self.cells.get(cell))
+                    .and_then(|cell| cell.ports().get(&user.port));
+                let valid = port.map_or(false, |port| {
+                    port.net == Some(index) && port.user_index == Some(user_id)
+                });
+                if !valid {
+                    violations.push(Violation::UserMismatch {
+                        net: self.describe_net(net),
+                        user: self.port_ref_name(user),
+                    });
+                }
+            }
+            for wire in net.wires().keys() {
+                if let Some(first) = wires.insert(*wire, net) {
+                    violations.push(Violation::WireConflict {
+                        wire: self.name_of_wire(*wire),
+                        first: self.describe_net(first),
+                        second: self.describe_net(net),
+                    });
+                }
+            }
+        }
+    }
+
+    // Names for messages, where a broken netlist may well refer to things that don't exist.
+    fn cell_name_at(&self, cell: Option<Index<CellInfo<D>>>) -> String {
+        match cell.map(|cell| self.cells.get(cell)) {
+            Some(Some(cell)) => self.describe_cell(cell).to_string(),
+            Some(None) => "a cell that doesn't exist".to_string(),
+            None => "no cell".to_string(),
+        }
+    }
+    fn net_name_at(&self, net: Option<Index<NetInfo<D>>>) -> String {
+        match net.map(|net| self.nets.get(net)) {
+            Some(Some(net)) => self.describe_net(net).to_string(),
+            Some(None) => "a net that doesn't exist".to_string(),
+            None => "no net".to_string(),
+        }
+    }
+    fn port_ref_name(&self, port: &PortRef<D>) -> String {
+        let cell = match port.cell.and_then(|cell| self.cells.get(cell)) {
+            Some(cell) => self.id_name(cell.name()),
+            None => "?".to_string(),
+        };
+        format!("{}.{}", cell, self.id_name(port.port))
+    }
+}
@@ -0,0 +1,75 @@
+//! Well known strings, preloaded by [`IdString::initialize_arch`] so they get the same index in
+//! every context and can be compared against without interning. Strings only one architecture
+//! uses live in that architecture's own table, such as [`crate::ice40::constids`].
+
+use super::id_string::IdString;
+
+macro_rules! constids {
+    ($($name:ident = $s:literal,)*) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u64)]
+        enum ConstId {
+            EMPTY,
+            $($name,)*
+        }
+        $(pub const $name: IdString = IdString::with_index(ConstId::$name as u64);)*
+        /// The preloaded strings in index order, the empty string first.
+        pub const CONSTIDS: &[&str] = &["", $($s,)*];
+    };
+    // An architecture's own table, preloaded right after the kernel's.
+    (arch: $($name:ident = $s:literal,)*) => {
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u64)]
+        enum ConstId {
+            $($name,)*
+        }
+        $(pub const $name: crate::kernel::id_string::IdString =
+            crate::kernel::id_string::IdString::with_index(
+                crate::kernel::constids::CONSTIDS.len() as u64 + ConstId::$name as u64,
+            );)*
+        /// The preloaded strings in index order, following the kernel's.
+        pub const CONSTIDS: &[&str] = &[$($s,)*];
+    };
+}
+pub(crate) use constids;
+
+constids! {
+    // Port names
+    ID_A = "A",
+    ID_B = "B",
+    ID_C = "C",
+    ID_D = "D",
+    ID_E = "E",
+    ID_I = "I",
+    ID_O = "O",
+    ID_Q = "Q",
+    ID_R = "R",
+    ID_S = "S",
+    ID_Y = "Y",
+    ID_I0 = "I0",
+    ID_I1 = "I1",
+    ID_I2 = "I2",
+    ID_I3 = "I3",
+    ID_CLK = "CLK",
+    // Cell types
+    ID_GND = "GND",
+    ID_VCC = "VCC",
+    ID_NEXTPNR_IBUF = "$nextpnr_ibuf",
+    ID_NEXTPNR_OBUF = "$nextpnr_obuf",
+    ID_NEXTPNR_IOBUF = "$nextpnr_iobuf",
+    // Attribute and parameter keys
+    ID_SRC = "src",
+    ID_TOP = "top",
+    ID_KEEP = "keep",
+    ID_INIT = "INIT",
+    ID_BEL = "BEL",
+    ID_NEXTPNR_BEL = "NEXTPNR_BEL",
+    ID_BEL_STRENGTH = "BEL_STRENGTH",
+    ID_ROUTING = "ROUTING",
+    ID_NEXTPNR_PORT = "NEXTPNR_PORT",
+    // Constant drivers
+    ID_PACKER_GND_NET = "$PACKER_GND_NET",
+    ID_PACKER_VCC_NET = "$PACKER_VCC_NET",
+    ID_PACKER_GND_DRV = "$PACKER_GND_DRV",
+    ID_PACKER_VCC_DRV = "$PACKER_VCC_DRV",
+}
@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Context {
     verbose: bool,
     debug: bool,
@@ -18,7 +19,12 @@ impl Context {
             force: false,
             disable_critical_path_source_print: false,
             detailed_timing_report: false,
-
         }
     }
-}
\ No newline at end of file
+    pub const fn disable_critical_path_source_print(&self) -> bool {
+        self.disable_critical_path_source_print
+    }
+    pub fn set_disable_critical_path_source_print(&mut self, disable: bool) {
+        self.disable_critical_path_source_print = disable;
+    }
+}
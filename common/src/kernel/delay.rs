@@ -3,13 +3,14 @@ use core::fmt::Debug;
 use core::hash::{Hash, Hasher};
 use core::marker::Destruct;
 use core::ops::{Add, Sub};
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize};
 
 /// The trait that all delays have to implement to fit within our model.
 #[const_trait]
 pub trait DelayTrait
 where
     Self: Debug + Eq + PartialEq + Ord + PartialOrd + Copy + Clone + Sub + Add + Hash + Destruct,
+    Self: Send + Sync + Serialize + for<'de> Deserialize<'de>,
 {
     fn new() -> Self {
         unimplemented!()
@@ -36,13 +37,13 @@ where
 {
     pub const fn new() -> Self
     where
-        D: DelayTrait + ~const DelayTrait,
+        D: DelayTrait + [const] DelayTrait,
     {
         Delay(D::new())
     }
     pub const fn with_delay(value: D) -> Self
     where
-        D: DelayTrait + ~const DelayTrait,
+        D: DelayTrait + [const] DelayTrait,
     {
         Delay(value)
     }
@@ -50,7 +51,7 @@ where
 
 impl<D> const Ord for Delay<D>
 where
-    D: DelayTrait + ~const Ord,
+    D: DelayTrait + [const] Ord,
 {
     fn cmp(&self, other: &Self) -> Ordering {
         self.0.cmp(&other.0)
@@ -71,7 +72,7 @@ impl const From<i64> for Delay<i64> {
 
 impl<D> const Add for Delay<D>
 where
-    D: DelayTrait + ~const Add<Output = D>,
+    D: DelayTrait + [const] Add<Output = D>,
 {
     type Output = Self;
 
@@ -82,7 +83,7 @@ where
 
 impl<D> const Sub for Delay<D>
 where
-    D: DelayTrait + ~const Sub<Output = D>,
+    D: DelayTrait + [const] Sub<Output = D>,
 {
     type Output = Self;
 
@@ -93,7 +94,7 @@ where
 
 impl<D> const PartialEq for Delay<D>
 where
-    D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.0 == other.0
@@ -102,7 +103,7 @@ where
 
 impl<D> const PartialOrd for Delay<D>
 where
-    D: DelayTrait + ~const Ord,
+    D: DelayTrait + [const] Ord,
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.0.cmp(&other.0))
@@ -111,7 +112,7 @@ where
 
 impl<D> const Default for Delay<D>
 where
-    D: DelayTrait + ~const DelayTrait,
+    D: DelayTrait + [const] DelayTrait,
 {
     fn default() -> Self {
         Self::new()
@@ -144,7 +145,7 @@ where
 {
     pub const fn new() -> Self
     where
-        D: DelayTrait + ~const DelayTrait,
+        D: DelayTrait + [const] DelayTrait,
     {
         Self {
             min_delay: Delay::new(),
@@ -187,7 +188,7 @@ where
 
 impl<D> const Add<DelayPair<D>> for DelayPair<D>
 where
-    D: DelayTrait + ~const Add<Output = D>,
+    D: DelayTrait + [const] Add<Output = D>,
 {
     type Output = DelayPair<D>;
 
@@ -201,7 +202,7 @@ where
 
 impl<D> const Sub for DelayPair<D>
 where
-    D: DelayTrait + ~const Sub<Output = D>,
+    D: DelayTrait + [const] Sub<Output = D>,
 {
     type Output = Self;
 
@@ -215,7 +216,7 @@ where
 
 impl<D> const PartialEq for DelayPair<D>
 where
-    D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.min_delay == other.min_delay && self.max_delay == other.max_delay
@@ -244,7 +245,7 @@ where
 
 impl<D> const PartialEq for DelayQuad<D>
 where
-    D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.rise == other.rise && self.fall == other.fall
@@ -253,7 +254,7 @@ where
 
 impl<D> const PartialOrd for DelayQuad<D>
 where
-    D: DelayTrait + ~const PartialOrd,
+    D: DelayTrait + [const] PartialOrd,
 {
     fn partial_cmp(&self, _other: &Self) -> Option<Ordering> {
         todo!()
@@ -266,7 +267,7 @@ where
 {
     pub const fn new() -> Self
     where
-        D: DelayTrait + ~const DelayTrait,
+        D: DelayTrait + [const] DelayTrait,
     {
         Self {
             rise: DelayPair::new(),
@@ -322,21 +323,21 @@ where
 
     pub const fn min_delay(&self) -> Delay<D>
     where
-        D: DelayTrait + ~const Ord + ~const Destruct,
+        D: DelayTrait + [const] Ord + [const] Destruct,
     {
         std::cmp::min(self.rise.min_delay(), self.fall.min_delay())
     }
 
     pub const fn max_delay(&self) -> Delay<D>
     where
-        D: DelayTrait + ~const Ord + ~const Destruct,
+        D: DelayTrait + [const] Ord + [const] Destruct,
     {
         std::cmp::max(self.rise.max_delay(), self.fall.max_delay())
     }
 
     pub const fn delay_pair(&self) -> DelayPair<D>
     where
-        D: DelayTrait + ~const Ord + ~const Destruct,
+        D: DelayTrait + [const] Ord + [const] Destruct,
     {
         DelayPair::with_min_max(self.min_delay(), self.max_delay())
     }
@@ -344,7 +345,7 @@ where
 
 impl<D> const Add<DelayQuad<D>> for DelayQuad<D>
 where
-    D: DelayTrait + ~const Add<Output = D>,
+    D: DelayTrait + [const] Add<Output = D>,
 {
     type Output = Self;
 
@@ -358,7 +359,7 @@ where
 
 impl<D> const Sub<DelayQuad<D>> for DelayQuad<D>
 where
-    D: DelayTrait + ~const Sub<Output = D>,
+    D: DelayTrait + [const] Sub<Output = D>,
 {
     type Output = Self;
 
@@ -372,7 +373,7 @@ where
 
 impl<D> const Default for DelayQuad<D>
 where
-    D: DelayTrait + ~const Destruct + ~const Ord + ~const DelayTrait,
+    D: DelayTrait + [const] Destruct + [const] Ord + [const] DelayTrait,
 {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,895 @@
+use super::base_types::Loc;
+use super::port::PortType;
+use crate::arch::{BelId, PipId, WireId};
+use memmap2::Mmap;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::Path;
+use thiserror::Error;
+
+// Device databases are a header followed by flat sections of fixed size little endian records.
+// Lists are stored as a start and count into another section, and names as indices into the
+// string table, so every lookup reads the mapped file in place.
+
+const MAGIC: &[u8; 8] = b"PNRDEVDB";
+/// Bumped whenever the layout changes, older databases have to be regenerated.
+pub const DEVICE_DB_VERSION: u32 = 1;
+/// Marks a tile wire that isn't part of any node.
+const NO_NODE: u32 = u32::MAX;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Section {
+    Strings,
+    Bytes,
+    TileTypes,
+    Bels,
+    BelPins,
+    Wires,
+    Pips,
+    Tiles,
+    NodeMap,
+    Nodes,
+    NodeWires,
+    TimingClasses,
+    Packages,
+    PackagePins,
+}
+
+const SECTIONS: usize = 14;
+// Words per record of each section, in the order above. Bytes holds single bytes instead.
+const RECORD_WORDS: [usize; SECTIONS] = [2, 0, 7, 5, 3, 3, 3, 6, 1, 2, 2, 2, 3, 3];
+// Magic, then version, width, height and name, then an offset and count for every section.
+const HEADER_BYTES: usize = MAGIC.len() + 4 * (4 + 2 * SECTIONS);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DeviceDbError {
+    #[error("Device database could not be read or written: {0}")]
+    Io(String),
+    #[error("Not a device database.")]
+    BadMagic,
+    #[error("Device database is format version {0}, expected {DEVICE_DB_VERSION}.")]
+    Version(u32),
+    #[error("Device database is truncated.")]
+    Truncated,
+    #[error("Device grid is {width}x{height} but {tiles} tiles were given.")]
+    InvalidGrid {
+        width: u32,
+        height: u32,
+        tiles: usize,
+    },
+    #[error("Device database refers to a missing {0}.")]
+    InvalidReference(&'static str),
+}
+
+impl From<std::io::Error> for DeviceDbError {
+    fn from(e: std::io::Error) -> Self {
+        DeviceDbError::Io(e.to_string())
+    }
+}
+
+/// A bel of a tile type. Its pins name wires of the same tile type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BelData {
+    pub name: String,
+    pub bel_type: String,
+    pub z: i32,
+    pub pins: Vec<BelPinData>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BelPinData {
+    pub name: String,
+    pub wire: u32,
+    pub dir: PortType,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WireData {
+    pub name: String,
+    pub wire_type: String,
+    pub timing_class: u32,
+}
+
+/// A pip between two wires of the same tile type.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipData {
+    pub src: u32,
+    pub dst: u32,
+    pub timing_class: u32,
+}
+
+/// The bels, wires and pips every tile of one type has, stored once however many tiles use it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TileTypeData {
+    pub name: String,
+    pub bels: Vec<BelData>,
+    pub wires: Vec<WireData>,
+    pub pips: Vec<PipData>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TileData {
+    pub name: String,
+    pub tile_type: u32,
+}
+
+/// Tile wires that are electrically one, as `(tile, wire)` pairs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeData {
+    pub wires: Vec<(u32, u32)>,
+}
+
+/// Delay of a wire or pip, in picoseconds.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimingClass {
+    pub min_delay: i32,
+    pub max_delay: i32,
+}
+
+/// Package pins and the `(tile, bel)` each is bonded to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PackageData {
+    pub name: String,
+    pub pins: Vec<(String, u32, u32)>,
+}
+
+/// Everything a device database holds, for arches and generators to fill in and write out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceDbWriter {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub tile_types: Vec<TileTypeData>,
+    /// Row major, `width * height` tiles.
+    pub tiles: Vec<TileData>,
+    pub nodes: Vec<NodeData>,
+    pub timing_classes: Vec<TimingClass>,
+    pub packages: Vec<PackageData>,
+}
+
+#[derive(Default)]
+struct StringTable {
+    ids: HashMap<String, u32>,
+    records: Vec<u32>,
+    bytes: Vec<u8>,
+}
+
+impl StringTable {
+    fn id(&mut self, s: &str) -> u32 {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+        let id = self.ids.len() as u32;
+        self.records
+            .extend([self.bytes.len() as u32, s.len() as u32]);
+        self.bytes.extend_from_slice(s.as_bytes());
+        self.ids.insert(s.to_string(), id);
+        id
+    }
+}
+
+impl DeviceDbWriter {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DeviceDbError> {
+        if self.tiles.len() != (self.width * self.height) as usize {
+            return Err(DeviceDbError::InvalidGrid {
+                width: self.width,
+                height: self.height,
+                tiles: self.tiles.len(),
+            });
+        }
+        let mut strings = StringTable::default();
+        let mut sections: [Vec<u32>; SECTIONS] = Default::default();
+        let records = |sections: &[Vec<u32>; SECTIONS], section: Section| {
+            (sections[section as usize].len() / RECORD_WORDS[section as usize]) as u32
+        };
+
+        for tile_type in &self.tile_types {
+            let (bel_start, wire_start, pip_start) = (
+                records(&sections, Section::Bels),
+                records(&sections, Section::Wires),
+                records(&sections, Section::Pips),
+            );
+            for bel in &tile_type.bels {
+                let pin_start = records(&sections, Section::BelPins);
+                for pin in &bel.pins {
+                    if pin.wire as usize >= tile_type.wires.len() {
+                        return Err(DeviceDbError::InvalidReference("bel pin wire"));
+                    }
+                    let record = [strings.id(&pin.name), pin.wire, pin.dir as u32];
+                    sections[Section::BelPins as usize].extend(record);
+                }
+                let record = [
+                    strings.id(&bel.name),
+                    strings.id(&bel.bel_type),
+                    bel.z as u32,
+                    pin_start,
+                    bel.pins.len() as u32,
+                ];
+                sections[Section::Bels as usize].extend(record);
+            }
+            for wire in &tile_type.wires {
+                if wire.timing_class as usize >= self.timing_classes.len() {
+                    return Err(DeviceDbError::InvalidReference("wire timing class"));
+                }
+                let record = [
+                    strings.id(&wire.name),
+                    strings.id(&wire.wire_type),
+                    wire.timing_class,
+                ];
+                sections[Section::Wires as usize].extend(record);
+            }
+            for pip in &tile_type.pips {
+                let wires = tile_type.wires.len() as u32;
+                if pip.src >= wires || pip.dst >= wires {
+                    return Err(DeviceDbError::InvalidReference("pip wire"));
+                }
+                if pip.timing_class as usize >= self.timing_classes.len() {
+                    return Err(DeviceDbError::InvalidReference("pip timing class"));
+                }
+                sections[Section::Pips as usize].extend([pip.src, pip.dst, pip.timing_class]);
+            }
+            let record = [
+                strings.id(&tile_type.name),
+                bel_start,
+                tile_type.bels.len() as u32,
+                wire_start,
+                tile_type.wires.len() as u32,
+                pip_start,
+                tile_type.pips.len() as u32,
+            ];
+            sections[Section::TileTypes as usize].extend(record);
+        }
+
+        let (mut bel_base, mut wire_base, mut pip_base) = (0, 0, 0);
+        let mut node_map_starts = Vec::with_capacity(self.tiles.len());
+        for tile in &self.tiles {
+            let tile_type = self
+                .tile_types
+                .get(tile.tile_type as usize)
+                .ok_or(DeviceDbError::InvalidReference("tile type"))?;
+            let node_map_start = records(&sections, Section::NodeMap);
+            node_map_starts.push(node_map_start);
+            let record = [
+                tile.tile_type,
+                strings.id(&tile.name),
+                bel_base,
+                wire_base,
+                pip_base,
+                node_map_start,
+            ];
+            sections[Section::Tiles as usize].extend(record);
+            sections[Section::NodeMap as usize]
+                .extend(std::iter::repeat(NO_NODE).take(tile_type.wires.len()));
+            bel_base += tile_type.bels.len() as u32;
+            wire_base += tile_type.wires.len() as u32;
+            pip_base += tile_type.pips.len() as u32;
+        }
+
+        for (node, data) in self.nodes.iter().enumerate() {
+            let wire_start = records(&sections, Section::NodeWires);
+            for &(tile, wire) in &data.wires {
+                let tile_type = self
+                    .tiles
+                    .get(tile as usize)
+                    .map(|tile| &self.tile_types[tile.tile_type as usize])
+                    .ok_or(DeviceDbError::InvalidReference("node tile"))?;
+                if wire as usize >= tile_type.wires.len() {
+                    return Err(DeviceDbError::InvalidReference("node wire"));
+                }
+                let at = (node_map_starts[tile as usize] + wire) as usize;
+                // A wire can only be in one node.
+                if sections[Section::NodeMap as usize][at] != NO_NODE {
+                    return Err(DeviceDbError::InvalidReference("node wire"));
+                }
+                sections[Section::NodeMap as usize][at] = node as u32;
+                sections[Section::NodeWires as usize].extend([tile, wire]);
+            }
+            sections[Section::Nodes as usize].extend([wire_start, data.wires.len() as u32]);
+        }
+
+        for class in &self.timing_classes {
+            let record = [class.min_delay as u32, class.max_delay as u32];
+            sections[Section::TimingClasses as usize].extend(record);
+        }
+
+        for package in &self.packages {
+            let pin_start = records(&sections, Section::PackagePins);
+            for (pin, tile, bel) in &package.pins {
+                let bels = self
+                    .tiles
+                    .get(*tile as usize)
+                    .map(|tile| self.tile_types[tile.tile_type as usize].bels.len());
+                if bels.map_or(true, |bels| *bel as usize >= bels) {
+                    return Err(DeviceDbError::InvalidReference("package pin bel"));
+                }
+                sections[Section::PackagePins as usize].extend([strings.id(pin), *tile, *bel]);
+            }
+            let record = [
+                strings.id(&package.name),
+                pin_start,
+                package.pins.len() as u32,
+            ];
+            sections[Section::Packages as usize].extend(record);
+        }
+
+        let name = strings.id(&self.name);
+        sections[Section::Strings as usize] = strings.records;
+        let mut out = Vec::with_capacity(HEADER_BYTES);
+        out.extend_from_slice(MAGIC);
+        for word in [DEVICE_DB_VERSION, self.width, self.height, name] {
+            out.extend(word.to_le_bytes());
+        }
+        // Sections follow the header in order, each starting on a word boundary.
+        let mut offset = HEADER_BYTES;
+        let mut directory = Vec::with_capacity(2 * SECTIONS);
+        for (index, words) in sections.iter().enumerate() {
+            if index == Section::Bytes as usize {
+                directory.extend([offset as u32, strings.bytes.len() as u32]);
+                offset += (strings.bytes.len() + 3) & !3;
+            } else {
+                directory.extend([offset as u32, (words.len() / RECORD_WORDS[index]) as u32]);
+                offset += words.len() * 4;
+            }
+        }
+        for word in directory {
+            out.extend(word.to_le_bytes());
+        }
+        for (index, words) in sections.iter().enumerate() {
+            if index == Section::Bytes as usize {
+                out.extend_from_slice(&strings.bytes);
+                out.resize((out.len() + 3) & !3, 0);
+            } else {
+                for word in words {
+                    out.extend(word.to_le_bytes());
+                }
+            }
+        }
+        Ok(out)
+    }
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), DeviceDbError> {
+        writer.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+}
+
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(bytes) => bytes,
+            Storage::Mapped(map) => map,
+        }
+    }
+}
+
+/// A loaded device database. Bels, wires and pips are numbered across the whole device, tile by
+/// tile, and looked up straight from the underlying bytes. Every reference in the database is
+/// checked when it is loaded. Tile wires joined into a node act as one wire.
+pub struct DeviceDb {
+    data: Storage,
+    sections: [(usize, u32); SECTIONS],
+}
+
+impl DeviceDb {
+    /// Maps a database file into memory.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DeviceDbError> {
+        let file = File::open(path)?;
+        // Safety: the database is only ever read, and is expected not to change on disk while
+        // it is open, as with any mapped file.
+        let map = unsafe { Mmap::map(&file)? };
+        Self::with_storage(Storage::Mapped(map))
+    }
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, DeviceDbError> {
+        Self::with_storage(Storage::Owned(bytes))
+    }
+    fn with_storage(data: Storage) -> Result<Self, DeviceDbError> {
+        if data.len() < HEADER_BYTES {
+            return Err(DeviceDbError::Truncated);
+        }
+        if &data[..MAGIC.len()] != MAGIC {
+            return Err(DeviceDbError::BadMagic);
+        }
+        let mut db = Self {
+            data,
+            sections: [(0, 0); SECTIONS],
+        };
+        let version = db.word(MAGIC.len());
+        if version != DEVICE_DB_VERSION {
+            return Err(DeviceDbError::Version(version));
+        }
+        for index in 0..SECTIONS {
+            let at = MAGIC.len() + 4 * (4 + 2 * index);
+            let (offset, count) = (db.word(at) as usize, db.word(at + 4));
+            let bytes = match RECORD_WORDS[index] {
+                0 => count as usize,
+                words => count as usize * words * 4,
+            };
+            if offset % 4 != 0 || offset + bytes > db.data.len() {
+                return Err(DeviceDbError::Truncated);
+            }
+            db.sections[index] = (offset, count);
+        }
+        db.validate()?;
+        Ok(db)
+    }
+
+    /// Checks every reference from one record to another once, so lookups can follow them
+    /// without checking again.
+    fn validate(&self) -> Result<(), DeviceDbError> {
+        let check = |valid: bool, what| match valid {
+            true => Ok(()),
+            false => Err(DeviceDbError::InvalidReference(what)),
+        };
+        // `count` records from `start` all lie within `section`.
+        let range = |start: u32, count: u32, section, what| {
+            check(
+                start
+                    .checked_add(count)
+                    .map_or(false, |end| end <= self.count(section)),
+                what,
+            )
+        };
+        let string = |id, what| check(id < self.count(Section::Strings), what);
+        let timing = |class, what| check(class < self.count(Section::TimingClasses), what);
+
+        let (bytes_offset, bytes) = self.sections[Section::Bytes as usize];
+        for id in 0..self.count(Section::Strings) {
+            let start = self.field(Section::Strings, id, 0);
+            let valid = start
+                .checked_add(self.field(Section::Strings, id, 1))
+                .filter(|end| *end <= bytes)
+                .map_or(false, |end| {
+                    let (start, end) = (bytes_offset + start as usize, bytes_offset + end as usize);
+                    std::str::from_utf8(&self.data[start..end]).is_ok()
+                });
+            check(valid, "string")?;
+        }
+        string(self.word(MAGIC.len() + 12), "device name")?;
+
+        for tile_type in 0..self.count(Section::TileTypes) {
+            let field = |field| self.field(Section::TileTypes, tile_type, field);
+            string(field(0), "tile type name")?;
+            range(field(1), field(2), Section::Bels, "tile type bels")?;
+            range(field(3), field(4), Section::Wires, "tile type wires")?;
+            range(field(5), field(6), Section::Pips, "tile type pips")?;
+            let wires = field(4);
+            for bel in field(1)..field(1) + field(2) {
+                string(self.field(Section::Bels, bel, 0), "bel name")?;
+                string(self.field(Section::Bels, bel, 1), "bel type")?;
+                let (pins, count) = (
+                    self.field(Section::Bels, bel, 3),
+                    self.field(Section::Bels, bel, 4),
+                );
+                range(pins, count, Section::BelPins, "bel pins")?;
+                for pin in pins..pins + count {
+                    string(self.field(Section::BelPins, pin, 0), "bel pin name")?;
+                    check(self.field(Section::BelPins, pin, 1) < wires, "bel pin wire")?;
+                    check(
+                        self.field(Section::BelPins, pin, 2) <= 2,
+                        "bel pin direction",
+                    )?;
+                }
+            }
+            for wire in field(3)..field(3) + wires {
+                string(self.field(Section::Wires, wire, 0), "wire name")?;
+                string(self.field(Section::Wires, wire, 1), "wire type")?;
+                timing(self.field(Section::Wires, wire, 2), "wire timing class")?;
+            }
+            for pip in field(5)..field(5) + field(6) {
+                let (src, dst) = (
+                    self.field(Section::Pips, pip, 0),
+                    self.field(Section::Pips, pip, 1),
+                );
+                check(src < wires && dst < wires, "pip wire")?;
+                timing(self.field(Section::Pips, pip, 2), "pip timing class")?;
+            }
+        }
+
+        let tiles = self.count(Section::Tiles);
+        if u64::from(tiles) != u64::from(self.width()) * u64::from(self.height()) {
+            return Err(DeviceDbError::InvalidGrid {
+                width: self.width(),
+                height: self.height(),
+                tiles: tiles as usize,
+            });
+        }
+        // Each tile's first bel, wire and pip follow on from the tile before.
+        let mut bases = [0u32; 3];
+        for tile in 0..tiles {
+            let tile_type = self.field(Section::Tiles, tile, 0);
+            check(tile_type < self.count(Section::TileTypes), "tile type")?;
+            string(self.field(Section::Tiles, tile, 1), "tile name")?;
+            for (base, field) in bases.iter_mut().zip(1..) {
+                check(
+                    self.field(Section::Tiles, tile, field + 1) == *base,
+                    "tile object base",
+                )?;
+                *base = base
+                    .checked_add(self.field(Section::TileTypes, tile_type, 2 * field))
+                    .ok_or(DeviceDbError::InvalidReference("tile object base"))?;
+            }
+            let wires = self.field(Section::TileTypes, tile_type, 4);
+            range(
+                self.field(Section::Tiles, tile, 5),
+                wires,
+                Section::NodeMap,
+                "tile node map",
+            )?;
+        }
+
+        // How many wires the node map puts in each node.
+        let mut mapped = vec![0u32; self.count(Section::Nodes) as usize];
+        for entry in 0..self.count(Section::NodeMap) {
+            let node = self.field(Section::NodeMap, entry, 0);
+            check(node == NO_NODE || node < self.count(Section::Nodes), "node")?;
+            if node != NO_NODE {
+                mapped[node as usize] += 1;
+            }
+        }
+        for node in 0..self.count(Section::Nodes) {
+            let (start, count) = (
+                self.field(Section::Nodes, node, 0),
+                self.field(Section::Nodes, node, 1),
+            );
+            range(start, count, Section::NodeWires, "node wires")?;
+            // Every wire the map puts in the node is one it lists, or it would be folded into a
+            // node that doesn't know about it.
+            check(mapped[node as usize] == count, "node wires")?;
+            for at in start..start + count {
+                let tile = self.field(Section::NodeWires, at, 0);
+                let wire = self.field(Section::NodeWires, at, 1);
+                check(
+                    tile < tiles && wire < self.tile_objects(tile, 3).1,
+                    "node wire",
+                )?;
+                // The node map has to agree, or a wire would lead to a node without it.
+                let entry = self.field(Section::Tiles, tile, 5) + wire;
+                check(self.field(Section::NodeMap, entry, 0) == node, "node wire")?;
+            }
+        }
+
+        for package in 0..self.count(Section::Packages) {
+            string(self.field(Section::Packages, package, 0), "package name")?;
+            let start = self.field(Section::Packages, package, 1);
+            let count = self.field(Section::Packages, package, 2);
+            range(start, count, Section::PackagePins, "package pins")?;
+            for pin in start..start + count {
+                string(self.field(Section::PackagePins, pin, 0), "package pin name")?;
+                let tile = self.field(Section::PackagePins, pin, 1);
+                let bel = self.field(Section::PackagePins, pin, 2);
+                check(
+                    tile < tiles && bel < self.tile_objects(tile, 1).1,
+                    "package pin bel",
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn word(&self, at: usize) -> u32 {
+        u32::from_le_bytes(self.data[at..at + 4].try_into().unwrap())
+    }
+    fn count(&self, section: Section) -> u32 {
+        self.sections[section as usize].1
+    }
+    // Records are only read at indices checked against their section's count, either here or
+    // once by `validate`.
+    fn field(&self, section: Section, record: u32, field: usize) -> u32 {
+        let offset = self.sections[section as usize].0;
+        self.word(offset + (record as usize * RECORD_WORDS[section as usize] + field) * 4)
+    }
+    fn string(&self, id: u32) -> &str {
+        let (start, len) = (
+            self.field(Section::Strings, id, 0) as usize,
+            self.field(Section::Strings, id, 1) as usize,
+        );
+        let offset = self.sections[Section::Bytes as usize].0;
+        std::str::from_utf8(&self.data[offset + start..offset + start + len]).unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &str {
+        self.string(self.word(MAGIC.len() + 12))
+    }
+    pub fn width(&self) -> u32 {
+        self.word(MAGIC.len() + 4)
+    }
+    pub fn height(&self) -> u32 {
+        self.word(MAGIC.len() + 8)
+    }
+
+    // Tiles
+    pub fn tile_count(&self) -> u32 {
+        self.count(Section::Tiles)
+    }
+    pub fn tile_at(&self, x: u32, y: u32) -> Option<u32> {
+        (x < self.width() && y < self.height()).then(|| y * self.width() + x)
+    }
+    pub fn tile_location(&self, tile: u32) -> (u32, u32) {
+        (tile % self.width(), tile / self.width())
+    }
+    pub fn tile_name(&self, tile: u32) -> Option<&str> {
+        (tile < self.tile_count()).then(|| self.string(self.field(Section::Tiles, tile, 1)))
+    }
+    pub fn tile_type_name(&self, tile: u32) -> Option<&str> {
+        (tile < self.tile_count())
+            .then(|| self.string(self.field(Section::TileTypes, self.tile_type(tile), 0)))
+    }
+    fn tile_type(&self, tile: u32) -> u32 {
+        self.field(Section::Tiles, tile, 0)
+    }
+    // Field 1, 3 or 5 of a tile type for bels, wires or pips, its count follows it.
+    fn tile_objects(&self, tile: u32, field: usize) -> (u32, u32) {
+        let tile_type = self.tile_type(tile);
+        (
+            self.field(Section::TileTypes, tile_type, field),
+            self.field(Section::TileTypes, tile_type, field + 1),
+        )
+    }
+    /// The tile a device wide index falls in and its index within the tile. `base` is the
+    /// tile field holding the first index of each tile.
+    fn locate(&self, index: Option<u64>, base: usize, field: usize) -> Option<(u32, u32)> {
+        let index = u32::try_from(index?).ok()?;
+        let (mut low, mut high) = (0, self.tile_count());
+        // The first tile starting after `index`, the one before it holds `index`.
+        while low < high {
+            let mid = (low + high) / 2;
+            if self.field(Section::Tiles, mid, base) <= index {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        // Tiles without any objects share their base with the next tile, so step back over
+        // them to the one that might hold `index`.
+        let tile = (0..low)
+            .rev()
+            .find(|tile| self.tile_objects(*tile, field).1 > 0)?;
+        let local = index - self.field(Section::Tiles, tile, base);
+        (local < self.tile_objects(tile, field).1).then_some((tile, local))
+    }
+
+    // Bels
+    pub fn bel_count(&self) -> u32 {
+        self.total(2, 1)
+    }
+    fn total(&self, base: usize, field: usize) -> u32 {
+        match self.tile_count() {
+            0 => 0,
+            tiles => {
+                self.field(Section::Tiles, tiles - 1, base) + self.tile_objects(tiles - 1, field).1
+            }
+        }
+    }
+    pub fn bels(&self) -> impl Iterator<Item = BelId> {
+        (0..self.bel_count() as u64).map(BelId::with_index)
+    }
+    pub fn bel(&self, tile: u32, local: u32) -> Option<BelId> {
+        (tile < self.tile_count() && local < self.tile_objects(tile, 1).1)
+            .then(|| BelId::with_index((self.field(Section::Tiles, tile, 2) + local) as u64))
+    }
+    pub fn bel_tile(&self, bel: BelId) -> Option<(u32, u32)> {
+        self.locate(bel.index(), 2, 1)
+    }
+    fn bel_record(&self, bel: BelId) -> Option<(u32, u32)> {
+        let (tile, local) = self.bel_tile(bel)?;
+        Some((tile, self.tile_objects(tile, 1).0 + local))
+    }
+    /// The name of the bel's tile and of the bel within it.
+    pub fn bel_name(&self, bel: BelId) -> Option<(&str, &str)> {
+        let (tile, record) = self.bel_record(bel)?;
+        Some((
+            self.string(self.field(Section::Tiles, tile, 1)),
+            self.string(self.field(Section::Bels, record, 0)),
+        ))
+    }
+    pub fn bel_type(&self, bel: BelId) -> Option<&str> {
+        let (_, record) = self.bel_record(bel)?;
+        Some(self.string(self.field(Section::Bels, record, 1)))
+    }
+    pub fn bel_location(&self, bel: BelId) -> Option<Loc> {
+        let (tile, record) = self.bel_record(bel)?;
+        let (x, y) = self.tile_location(tile);
+        let z = self.field(Section::Bels, record, 2) as i32;
+        Some(Loc::new(x as i32, y as i32, z))
+    }
+    /// The pins of a bel, with the wire each one is on, as [`DeviceDb::canonical_wire`], and its
+    /// direction.
+    pub fn bel_pins(&self, bel: BelId) -> impl Iterator<Item = (&str, WireId, PortType)> + '_ {
+        let pins = self.bel_record(bel).map(|(tile, record)| {
+            let start = self.field(Section::Bels, record, 3);
+            let count = self.field(Section::Bels, record, 4);
+            (tile, start..start + count)
+        });
+        pins.into_iter().flat_map(move |(tile, pins)| {
+            pins.map(move |pin| {
+                let dir = match self.field(Section::BelPins, pin, 2) {
+                    1 => PortType::Out,
+                    2 => PortType::InOut,
+                    _ => PortType::In,
+                };
+                (
+                    self.string(self.field(Section::BelPins, pin, 0)),
+                    self.node_wire(tile, self.field(Section::BelPins, pin, 1)),
+                    dir,
+                )
+            })
+        })
+    }
+
+    // Wires
+    /// Tile wires, counting every tile wire of a node.
+    pub fn wire_count(&self) -> u32 {
+        self.total(3, 3)
+    }
+    /// One wire per node, its [`DeviceDb::canonical_wire`], and every tile wire outside a node.
+    pub fn wires(&self) -> impl Iterator<Item = WireId> + '_ {
+        (0..self.wire_count() as u64)
+            .map(WireId::with_index)
+            .filter(|wire| self.canonical_wire(*wire) == *wire)
+    }
+    pub fn wire(&self, tile: u32, local: u32) -> Option<WireId> {
+        (tile < self.tile_count() && local < self.tile_objects(tile, 3).1)
+            .then(|| self.tile_wire(tile, local))
+    }
+    fn tile_wire(&self, tile: u32, local: u32) -> WireId {
+        WireId::with_index((self.field(Section::Tiles, tile, 3) + local) as u64)
+    }
+    fn node_wire(&self, tile: u32, local: u32) -> WireId {
+        self.canonical_wire(self.tile_wire(tile, local))
+    }
+    /// The wire that stands for the node `wire` is in, the node's first tile wire, so that all of
+    /// a node's tile wires are one wire to bind and route. Wires outside any node stand for
+    /// themselves.
+    pub fn canonical_wire(&self, wire: WireId) -> WireId {
+        self.wire_node(wire)
+            .and_then(|node| self.node_wires(node).next())
+            .unwrap_or(wire)
+    }
+    pub fn wire_tile(&self, wire: WireId) -> Option<(u32, u32)> {
+        self.locate(wire.index(), 3, 3)
+    }
+    fn wire_record(&self, wire: WireId) -> Option<(u32, u32)> {
+        let (tile, local) = self.wire_tile(wire)?;
+        Some((tile, self.tile_objects(tile, 3).0 + local))
+    }
+    pub fn wire_name(&self, wire: WireId) -> Option<(&str, &str)> {
+        let (tile, record) = self.wire_record(wire)?;
+        Some((
+            self.string(self.field(Section::Tiles, tile, 1)),
+            self.string(self.field(Section::Wires, record, 0)),
+        ))
+    }
+    pub fn wire_type(&self, wire: WireId) -> Option<&str> {
+        let (_, record) = self.wire_record(wire)?;
+        Some(self.string(self.field(Section::Wires, record, 1)))
+    }
+    pub fn wire_timing(&self, wire: WireId) -> Option<TimingClass> {
+        let (_, record) = self.wire_record(wire)?;
+        self.timing_class(self.field(Section::Wires, record, 2))
+    }
+    /// The node a wire belongs to, if it is joined to wires of other tiles.
+    pub fn wire_node(&self, wire: WireId) -> Option<u32> {
+        let (tile, local) = self.wire_tile(wire)?;
+        let node = self.field(
+            Section::NodeMap,
+            self.field(Section::Tiles, tile, 5) + local,
+            0,
+        );
+        (node != NO_NODE).then_some(node)
+    }
+    pub fn node_count(&self) -> u32 {
+        self.count(Section::Nodes)
+    }
+    pub fn node_wires(&self, node: u32) -> impl Iterator<Item = WireId> + '_ {
+        let (start, count) = match node < self.node_count() {
+            true => (
+                self.field(Section::Nodes, node, 0),
+                self.field(Section::Nodes, node, 1),
+            ),
+            false => (0, 0),
+        };
+        (start..start + count).map(move |at| {
+            self.tile_wire(
+                self.field(Section::NodeWires, at, 0),
+                self.field(Section::NodeWires, at, 1),
+            )
+        })
+    }
+
+    // Pips
+    pub fn pip_count(&self) -> u32 {
+        self.total(4, 5)
+    }
+    pub fn pips(&self) -> impl Iterator<Item = PipId> {
+        (0..self.pip_count() as u64).map(PipId::with_index)
+    }
+    pub fn pip_tile(&self, pip: PipId) -> Option<(u32, u32)> {
+        self.locate(pip.index(), 4, 5)
+    }
+    fn pip_record(&self, pip: PipId) -> Option<(u32, u32)> {
+        let (tile, local) = self.pip_tile(pip)?;
+        Some((tile, self.tile_objects(tile, 5).0 + local))
+    }
+    /// The wire a pip is driven by, as [`DeviceDb::canonical_wire`].
+    pub fn pip_src_wire(&self, pip: PipId) -> Option<WireId> {
+        let (tile, record) = self.pip_record(pip)?;
+        Some(self.node_wire(tile, self.field(Section::Pips, record, 0)))
+    }
+    /// The wire a pip drives, as [`DeviceDb::canonical_wire`].
+    pub fn pip_dst_wire(&self, pip: PipId) -> Option<WireId> {
+        let (tile, record) = self.pip_record(pip)?;
+        Some(self.node_wire(tile, self.field(Section::Pips, record, 1)))
+    }
+    pub fn pip_timing(&self, pip: PipId) -> Option<TimingClass> {
+        let (_, record) = self.pip_record(pip)?;
+        self.timing_class(self.field(Section::Pips, record, 2))
+    }
+    /// Pips in the wire's own tile that it drives (`field` 0) or is driven by (`field` 1).
+    fn tile_pips(&self, wire: WireId, field: usize) -> impl Iterator<Item = PipId> + '_ {
+        let found = self.wire_tile(wire).map(|(tile, local)| {
+            let (start, count) = self.tile_objects(tile, 5);
+            let base = self.field(Section::Tiles, tile, 4);
+            (local, start, base, 0..count)
+        });
+        found
+            .into_iter()
+            .flat_map(move |(local, start, base, pips)| {
+                pips.filter(move |pip| self.field(Section::Pips, start + pip, field) == local)
+                    .map(move |pip| PipId::with_index((base + pip) as u64))
+            })
+    }
+    /// Pips of every tile wire in the node of `wire`, so a node is reached and left through
+    /// all its tiles.
+    fn node_pips(&self, wire: WireId, field: usize) -> impl Iterator<Item = PipId> + '_ {
+        let wires: Vec<WireId> = match self.wire_node(wire) {
+            Some(node) => self.node_wires(node).collect(),
+            None => vec![wire],
+        };
+        wires
+            .into_iter()
+            .flat_map(move |wire| self.tile_pips(wire, field))
+    }
+    pub fn pips_downhill(&self, wire: WireId) -> impl Iterator<Item = PipId> + '_ {
+        self.node_pips(wire, 0)
+    }
+    pub fn pips_uphill(&self, wire: WireId) -> impl Iterator<Item = PipId> + '_ {
+        self.node_pips(wire, 1)
+    }
+
+    pub fn timing_class(&self, class: u32) -> Option<TimingClass> {
+        (class < self.count(Section::TimingClasses)).then(|| TimingClass {
+            min_delay: self.field(Section::TimingClasses, class, 0) as i32,
+            max_delay: self.field(Section::TimingClasses, class, 1) as i32,
+        })
+    }
+
+    // Packages
+    pub fn packages(&self) -> impl Iterator<Item = &str> + '_ {
+        (0..self.count(Section::Packages))
+            .map(|package| self.string(self.field(Section::Packages, package, 0)))
+    }
+    /// The pins of a package and the bel each is bonded to.
+    pub fn package_pins(&self, name: &str) -> Option<impl Iterator<Item = (&str, BelId)> + '_> {
+        let package = (0..self.count(Section::Packages))
+            .find(|package| self.string(self.field(Section::Packages, *package, 0)) == name)?;
+        let start = self.field(Section::Packages, package, 1);
+        let count = self.field(Section::Packages, package, 2);
+        Some((start..start + count).map(move |pin| {
+            let tile = self.field(Section::PackagePins, pin, 1);
+            let bel =
+                self.field(Section::Tiles, tile, 2) + self.field(Section::PackagePins, pin, 2);
+            (
+                self.string(self.field(Section::PackagePins, pin, 0)),
+                BelId::with_index(bel as u64),
+            )
+        }))
+    }
+}
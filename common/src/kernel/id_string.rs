@@ -1,3 +1,4 @@
+use super::constids;
 use super::delay::DelayTrait;
 use crate::kernel::base_context::BaseCtx;
 use core::hash::Hash;
@@ -40,11 +41,16 @@ impl Hash for IdString {
 }
 
 impl IdString {
-    pub fn initialize_arch<D: DelayTrait>(_ctx: &BaseCtx<D>) {
-        todo!()
+    /// Preloads the [`constids`] table and then the arch's, so their constants name the same
+    /// strings in this context.
+    pub fn initialize_arch<D: DelayTrait>(ctx: &mut BaseCtx<D>) {
+        let arch_constids = crate::arch::CONSTIDS.iter();
+        for (idx, s) in constids::CONSTIDS.iter().chain(arch_constids).enumerate() {
+            Self::initialize_add(ctx, s, idx as u64);
+        }
     }
     pub fn initialize_add<D: DelayTrait>(ctx: &mut BaseCtx<D>, s: &str, idx: u64) {
-        assert!(ctx.idstring_str_to_idx.is_empty());
+        assert!(!ctx.idstring_str_to_idx.contains_key(s));
         assert!(ctx.idstring_idx_to_str.len() == idx as usize);
         ctx.idstring_str_to_idx.insert(s.to_string(), idx);
         ctx.idstring_idx_to_str.push(s.to_string());
@@ -57,16 +63,25 @@ impl IdString {
         x.index = index;
         x
     }
+    pub const fn index(&self) -> u64 {
+        self.index
+    }
     pub fn set<D: DelayTrait>(&mut self, ctx: &mut BaseCtx<D>, s: &str) {
-        let it = ctx.idstring_str_to_idx.iter().position(|x| x.0 == s);
-        if let Some(found) = it {
-            self.index = found as u64;
+        if let Some(&found) = ctx.idstring_str_to_idx.get(s) {
+            self.index = found;
         } else {
             self.index = ctx.idstring_idx_to_str.len() as u64;
             ctx.idstring_str_to_idx.insert(s.to_string(), self.index);
             ctx.idstring_idx_to_str.push(s.to_string());
         }
     }
+    /// The id of `s` if it has already been interned, without adding it.
+    pub fn find<D: DelayTrait>(ctx: &BaseCtx<D>, s: &str) -> Option<Self> {
+        ctx.idstring_str_to_idx
+            .get(s)
+            .copied()
+            .map(Self::with_index)
+    }
 
     pub fn with_ctx_str<D: DelayTrait>(ctx: &mut BaseCtx<D>, s: &str) -> Self {
         let mut x = Self::new();
@@ -122,6 +137,18 @@ impl const PartialEq for IdPair {
     }
 }
 
+impl IdPair {
+    pub const fn new(first: IdString, second: IdString) -> Self {
+        Self(first, second)
+    }
+    pub const fn first(&self) -> IdString {
+        self.0
+    }
+    pub const fn second(&self) -> IdString {
+        self.1
+    }
+}
+
 impl Hash for IdPair {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.0.hash(state);
@@ -0,0 +1,112 @@
+use super::delay::DelayTrait;
+use super::id_string::{IdString, IdStringError};
+use crate::kernel::base_context::BaseCtx;
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::BTreeMap;
+
+/// A hierarchical name such as `X3/Y7/lc2`, one IdString per level, joined by the arch's
+/// name delimiter when printed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct IdStringList {
+    ids: SmallVec<[IdString; 4]>,
+}
+
+impl IdStringList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_ids(ids: &[IdString]) -> Self {
+        Self {
+            ids: SmallVec::from_slice(ids),
+        }
+    }
+    /// Splits `s` on the name delimiter, interning every level.
+    pub fn parse<D: DelayTrait>(ctx: &mut BaseCtx<D>, s: &str) -> Self {
+        let delimiter = ctx.name_delimiter();
+        Self {
+            ids: s.split(delimiter).map(|level| ctx.id(level)).collect(),
+        }
+    }
+    /// Like [`IdStringList::parse`], but only if every level has already been interned, so a
+    /// lookup by name never changes the context.
+    pub fn find<D: DelayTrait>(ctx: &BaseCtx<D>, s: &str) -> Option<Self> {
+        Some(Self {
+            ids: s
+                .split(ctx.name_delimiter())
+                .map(|level| IdString::find(ctx, level))
+                .collect::<Option<_>>()?,
+        })
+    }
+    pub fn to_string<D: DelayTrait>(&self, ctx: &BaseCtx<D>) -> Result<String, IdStringError> {
+        let levels = self
+            .ids
+            .iter()
+            .map(|id| id.to_string(ctx))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(levels.join(&ctx.name_delimiter().to_string()))
+    }
+    pub fn ids(&self) -> &[IdString] {
+        &self.ids
+    }
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+    pub fn push(&mut self, id: IdString) {
+        self.ids.push(id);
+    }
+}
+
+/// Device objects and their names, both ways round. Filled in by the arch as it loads its
+/// database.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NameTable<T: Ord> {
+    by_name: BTreeMap<IdStringList, T>,
+    names: BTreeMap<T, IdStringList>,
+}
+
+impl<T> NameTable<T>
+where
+    T: Ord + Copy,
+{
+    pub const fn new() -> Self {
+        Self {
+            by_name: BTreeMap::new(),
+            names: BTreeMap::new(),
+        }
+    }
+    pub fn insert(&mut self, object: T, name: IdStringList) {
+        if let Some(old) = self.names.insert(object, name.clone()) {
+            self.by_name.remove(&old);
+        }
+        if let Some(previous) = self.by_name.insert(name, object) {
+            if previous != object {
+                self.names.remove(&previous);
+            }
+        }
+    }
+    pub fn name(&self, object: &T) -> Option<&IdStringList> {
+        self.names.get(object)
+    }
+    pub fn get(&self, name: &IdStringList) -> Option<T> {
+        self.by_name.get(name).copied()
+    }
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl<T> Default for NameTable<T>
+where
+    T: Ord + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
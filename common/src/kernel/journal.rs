@@ -0,0 +1,341 @@
+use super::base_types::PlaceStrength;
+use super::cell::CellInfo;
+use super::delay::DelayTrait;
+use super::id_string::IdString;
+use super::net::NetInfo;
+use crate::arch::{BelId, PipId, WireId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::ops::{self, Deref, DerefMut};
+use thunderdome::{Arena, Index};
+
+/// Something a transaction can undo. Every open transaction has a savepoint in each part of the
+/// context implementing this, all opened and closed together.
+pub(crate) trait Transactional {
+    fn begin(&mut self);
+    /// Folds the innermost savepoint into the one around it.
+    fn commit(&mut self);
+    /// Restores the innermost savepoint.
+    fn rollback(&mut self);
+}
+
+/// An arena that keeps every element as it was before its first change in each open
+/// transaction, so whatever changes it is undone on rollback. Reading goes through `Deref`,
+/// changing only through the methods here, which do the journaling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JournaledArena<T> {
+    arena: Arena<T, T>,
+    #[serde(skip)]
+    savepoints: Vec<ArenaSavepoint<T>>,
+}
+
+#[derive(Debug, Clone)]
+struct ArenaSavepoint<T> {
+    // The element before its first change, None if the transaction inserted it.
+    entries: Vec<(Index<T>, Option<T>)>,
+    saved: HashSet<Index<T>>,
+}
+
+impl<T> JournaledArena<T>
+where
+    T: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            arena: Arena::new(),
+            savepoints: Vec::new(),
+        }
+    }
+    fn save(&mut self, index: Index<T>) {
+        let Some(savepoint) = self.savepoints.last_mut() else {
+            return;
+        };
+        if let Some(value) = self.arena.get(index) {
+            if savepoint.saved.insert(index) {
+                savepoint.entries.push((index, Some(value.clone())));
+            }
+        }
+    }
+    pub fn insert(&mut self, value: T) -> Index<T> {
+        let index = self.arena.insert(value);
+        if let Some(savepoint) = self.savepoints.last_mut() {
+            savepoint.saved.insert(index);
+            savepoint.entries.push((index, None));
+        }
+        index
+    }
+    pub fn remove(&mut self, index: Index<T>) -> Option<T> {
+        self.save(index);
+        self.arena.remove(index)
+    }
+    pub fn get_mut(&mut self, index: Index<T>) -> Option<&mut T> {
+        self.save(index);
+        self.arena.get_mut(index)
+    }
+    pub fn get2_mut(
+        &mut self,
+        first: Index<T>,
+        second: Index<T>,
+    ) -> (Option<&mut T>, Option<&mut T>) {
+        self.save(first);
+        self.save(second);
+        self.arena.get2_mut(first, second)
+    }
+}
+
+impl<T> Transactional for JournaledArena<T> {
+    fn begin(&mut self) {
+        self.savepoints.push(ArenaSavepoint {
+            entries: Vec::new(),
+            saved: HashSet::new(),
+        });
+    }
+    fn commit(&mut self) {
+        let Some(savepoint) = self.savepoints.pop() else {
+            return;
+        };
+        if let Some(outer) = self.savepoints.last_mut() {
+            // Undoing the inner entries first and the outer ones after still ends at the outer
+            // savepoint, so they can simply be appended.
+            outer.entries.extend(savepoint.entries);
+            outer.saved.extend(savepoint.saved);
+        }
+    }
+    fn rollback(&mut self) {
+        let Some(savepoint) = self.savepoints.pop() else {
+            return;
+        };
+        for (index, value) in savepoint.entries.into_iter().rev() {
+            match value {
+                Some(value) => {
+                    self.arena.insert_at(index, value);
+                }
+                None => {
+                    self.arena.remove(index);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Deref for JournaledArena<T> {
+    type Target = Arena<T, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl<T> ops::Index<Index<T>> for JournaledArena<T> {
+    type Output = T;
+
+    fn index(&self, index: Index<T>) -> &T {
+        &self.arena[index]
+    }
+}
+
+impl<T> ops::IndexMut<Index<T>> for JournaledArena<T>
+where
+    T: Clone,
+{
+    fn index_mut(&mut self, index: Index<T>) -> &mut T {
+        self.save(index);
+        &mut self.arena[index]
+    }
+}
+
+impl<T> PartialEq for JournaledArena<T>
+where
+    Arena<T, T>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.arena == other.arena
+    }
+}
+
+impl<T> Hash for JournaledArena<T>
+where
+    Arena<T, T>: Hash,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.arena.hash(state);
+    }
+}
+
+impl<T> Default for JournaledArena<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A value saved whole before its first change in each open transaction, for the context's
+/// tables that change rarely enough that copying one beats tracking each entry. Any mutable
+/// access counts as a change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Journaled<T> {
+    value: T,
+    // One slot per open transaction, holding the value as it was when the transaction began
+    // once it has been changed.
+    #[serde(skip)]
+    savepoints: Vec<Option<T>>,
+}
+
+impl<T> Journaled<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            savepoints: Vec::new(),
+        }
+    }
+}
+
+impl<T> Transactional for Journaled<T> {
+    fn begin(&mut self) {
+        self.savepoints.push(None);
+    }
+    fn commit(&mut self) {
+        let Some(saved) = self.savepoints.pop() else {
+            return;
+        };
+        // Unchanged in the outer transaction before the inner one began, so what the inner one
+        // saved is also how the outer one began.
+        if let Some(outer @ None) = self.savepoints.last_mut() {
+            *outer = saved;
+        }
+    }
+    fn rollback(&mut self) {
+        if let Some(Some(saved)) = self.savepoints.pop() {
+            self.value = saved;
+        }
+    }
+}
+
+impl<T> Deref for Journaled<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Journaled<T>
+where
+    T: Clone,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        if let Some(slot @ None) = self.savepoints.last_mut() {
+            *slot = Some(self.value.clone());
+        }
+        &mut self.value
+    }
+}
+
+impl<T> PartialEq for Journaled<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Hash for Journaled<T>
+where
+    T: Hash,
+{
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+/// What a transaction needs to undo a change to the context's name maps or to the arch's
+/// bindings, which only the context's own methods change. Cells, nets and the other tables
+/// journal themselves.
+#[derive(Debug, Clone)]
+pub(crate) enum JournalEntry<D: DelayTrait> {
+    /// The cell a name pointed to before.
+    CellName(IdString, Option<Index<CellInfo<D>>>),
+    /// The net an alias pointed to before.
+    NetAlias(IdString, Option<Index<NetInfo<D>>>),
+    /// The cell a bel was bound to in the arch before, None if it was free.
+    Bel(BelId, Option<(Index<CellInfo<D>>, PlaceStrength)>),
+    /// The net a wire was bound to in the arch before.
+    Wire(WireId, Option<(Index<NetInfo<D>>, PlaceStrength)>),
+    /// The net a pip was bound to in the arch before.
+    Pip(PipId, Option<(Index<NetInfo<D>>, PlaceStrength)>),
+}
+
+impl<D> JournalEntry<D>
+where
+    D: DelayTrait,
+{
+    /// Whether undoing this needs the arch.
+    pub(crate) const fn is_binding(&self) -> bool {
+        matches!(self, Self::Bel(..) | Self::Wire(..) | Self::Pip(..))
+    }
+}
+
+/// The name map and binding changes of the open transactions, innermost last.
+#[derive(Debug, Clone)]
+pub(crate) struct Journal<D: DelayTrait> {
+    savepoints: Vec<Vec<JournalEntry<D>>>,
+}
+
+impl<D> Journal<D>
+where
+    D: DelayTrait,
+{
+    pub(crate) const fn new() -> Self {
+        Self {
+            savepoints: Vec::new(),
+        }
+    }
+    pub(crate) fn is_active(&self) -> bool {
+        !self.savepoints.is_empty()
+    }
+    pub(crate) fn record(&mut self, entry: JournalEntry<D>) {
+        if let Some(savepoint) = self.savepoints.last_mut() {
+            savepoint.push(entry);
+        }
+    }
+    /// Whether the innermost transaction changed any bindings in the arch.
+    pub(crate) fn has_bindings(&self) -> bool {
+        self.savepoints.last().map_or(false, |savepoint| {
+            savepoint.iter().any(JournalEntry::is_binding)
+        })
+    }
+    pub(crate) fn begin(&mut self) {
+        self.savepoints.push(Vec::new());
+    }
+    /// Closes the innermost transaction, folding its changes into the one around it. Returns
+    /// false if there was no transaction.
+    pub(crate) fn commit(&mut self) -> bool {
+        let Some(savepoint) = self.savepoints.pop() else {
+            return false;
+        };
+        if let Some(outer) = self.savepoints.last_mut() {
+            outer.extend(savepoint);
+        }
+        true
+    }
+    /// Closes the innermost transaction, returning its changes newest first for the context to
+    /// undo.
+    pub(crate) fn rollback(&mut self) -> Option<impl Iterator<Item = JournalEntry<D>>> {
+        Some(self.savepoints.pop()?.into_iter().rev())
+    }
+}
+
+impl<D> Default for Journal<D>
+where
+    D: DelayTrait,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
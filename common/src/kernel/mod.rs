@@ -4,16 +4,22 @@ pub mod base_clusterinfo;
 pub mod base_context;
 pub mod base_types;
 pub mod cell;
+pub mod check;
+pub mod constids;
 pub mod context;
 pub mod delay;
+pub mod device_db;
 pub mod id_string;
+pub mod id_string_list;
+pub mod journal;
+pub mod net;
 pub mod port;
 pub mod property;
 pub mod region;
-pub mod types;
-pub mod timing;
-pub mod net;
 pub mod segment;
+pub mod settings;
+pub mod timing;
+pub mod types;
 
 #[cfg(test)]
 mod tests;
@@ -1,19 +1,21 @@
 use crate::{
-    ice40::arch_defs::{ArchNetInfo, WireId},
+    arch::{ArchNetInfo, WireId},
     kernel::{
         delay::DelayTrait, id_string::IdString, port::PortRef, property::Property, region::Region,
         timing::ClockConstraint, types::PipMap,
     },
 };
 use derive_more::{From, Into};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use thunderdome::{Arena, Index};
-use typed_index_collections::TiVec;
 
-#[derive(Clone, Copy, Debug, From, Into, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(
+    Clone, Copy, Debug, From, Into, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize,
+)]
 pub struct UserId(usize);
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NetInfo<D>
 where
     D: DelayTrait,
@@ -24,8 +26,7 @@ where
     udata: i32,
 
     pub driver: PortRef<D>,
-    // TODO: Measure performance/check implementation of TiVEc, see if its better than O(n) on entry deletion.
-    pub users: TiVec<UserId, PortRef<D>>,
+    pub users: UserList<D>,
     attrs: BTreeMap<IdString, Property>,
 
     // wire -> uphill_pip
@@ -48,8 +49,7 @@ where
             hierarchy_path: IdString::new(),
             udata: 0,
             driver: PortRef::new(),
-            // TODO: Go upstream and get this made const.
-            users: TiVec::new(),
+            users: UserList::new(),
             attrs: BTreeMap::new(),
             wires: BTreeMap::new(),
             aliases: Vec::new(),
@@ -73,6 +73,56 @@ where
             ..Default::default()
         }
     }
+    pub const fn name(&self) -> IdString {
+        self.name
+    }
+    // The context owns the name's net_aliases entry, so renaming goes through it.
+    pub(crate) fn set_name(&mut self, name: IdString) {
+        self.name = name;
+    }
+    pub const fn hierarchy_path(&self) -> IdString {
+        self.hierarchy_path
+    }
+    pub fn set_hierarchy_path(&mut self, hierarchy_path: IdString) {
+        self.hierarchy_path = hierarchy_path;
+    }
+    pub const fn attributes(&self) -> &BTreeMap<IdString, Property> {
+        &self.attrs
+    }
+    pub fn set_attribute(&mut self, name: IdString, value: Property) {
+        self.attrs.insert(name, value);
+    }
+    pub fn unset_attribute(&mut self, name: IdString) {
+        self.attrs.remove(&name);
+    }
+    pub const fn wires(&self) -> &BTreeMap<WireId, PipMap> {
+        &self.wires
+    }
+    /// Records `wire` as part of this net's routing, reached through `pip_map`.
+    pub fn set_wire(&mut self, wire: WireId, pip_map: PipMap) {
+        self.wires.insert(wire, pip_map);
+    }
+    /// Takes `wire` out of this net's routing, returning how it was reached.
+    pub fn remove_wire(&mut self, wire: WireId) -> Option<PipMap> {
+        self.wires.remove(&wire)
+    }
+    /// Forgets all of this net's routing.
+    pub fn clear_wires(&mut self) {
+        self.wires.clear();
+    }
+    pub fn aliases(&self) -> &[IdString] {
+        &self.aliases
+    }
+    /// Records another name this net is known by, the `net_aliases` entry is owned by the context.
+    pub fn add_alias(&mut self, alias: IdString) {
+        if !self.aliases.contains(&alias) {
+            self.aliases.push(alias);
+        }
+    }
+    // Like set_name, the context drops the alias's net_aliases entry along with it.
+    pub(crate) fn remove_alias(&mut self, alias: IdString) {
+        self.aliases.retain(|other| *other != alias);
+    }
 }
 
 impl<D> Default for NetInfo<D>
@@ -83,3 +133,76 @@ where
         Self::new()
     }
 }
+
+/// The users of a net. A user keeps its UserId until it is removed, and removal is O(1), so
+/// disconnecting one port never invalidates the `user_index` of another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserList<D>
+where
+    D: DelayTrait,
+{
+    slots: Vec<Option<PortRef<D>>>,
+    // Emptied slots, reused by the next push.
+    free: Vec<UserId>,
+}
+
+impl<D> UserList<D>
+where
+    D: DelayTrait,
+{
+    pub const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+    pub fn push(&mut self, user: PortRef<D>) -> UserId {
+        match self.free.pop() {
+            Some(id) => {
+                self.slots[id.0] = Some(user);
+                id
+            }
+            None => {
+                self.slots.push(Some(user));
+                UserId(self.slots.len() - 1)
+            }
+        }
+    }
+    pub fn remove(&mut self, id: UserId) -> Option<PortRef<D>> {
+        let user = self.slots.get_mut(id.0)?.take()?;
+        self.free.push(id);
+        Some(user)
+    }
+    pub fn get(&self, id: UserId) -> Option<&PortRef<D>> {
+        self.slots.get(id.0)?.as_ref()
+    }
+    pub fn get_mut(&mut self, id: UserId) -> Option<&mut PortRef<D>> {
+        self.slots.get_mut(id.0)?.as_mut()
+    }
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Removes every user, UserIds handed out before are no longer valid.
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (UserId, &PortRef<D>)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, user)| Some((UserId(i), user.as_ref()?)))
+    }
+}
+
+impl<D> Default for UserList<D>
+where
+    D: DelayTrait,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
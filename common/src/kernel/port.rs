@@ -1,13 +1,14 @@
+use super::base_context::{BaseCtx, BaseCtxError};
 use super::cell::CellInfo;
 use super::net::UserId;
 use crate::kernel::delay::{Delay, DelayTrait};
 use crate::kernel::id_string::IdString;
 use crate::kernel::net::NetInfo;
+use serde::{Deserialize, Serialize};
 use std::hash::Hash;
 use thunderdome::{Arena, Index};
-use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
 pub struct PortRef<D>
 where
     D: DelayTrait,
@@ -18,7 +19,10 @@ where
     pub budget: Delay<D>,
 }
 
-impl<D> Hash for PortRef<D> where D:DelayTrait {
+impl<D> Hash for PortRef<D>
+where
+    D: DelayTrait,
+{
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.cell.hash(state);
         self.port.hash(state);
@@ -48,7 +52,7 @@ where
 {
     pub const fn new() -> Self
     where
-        D: ~const DelayTrait,
+        D: [const] DelayTrait,
     {
         Self {
             cell: None,
@@ -86,7 +90,13 @@ impl const PartialEq for PortType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+impl Default for PortType {
+    fn default() -> Self {
+        Self::In
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PortInfo<D>
 where
     D: DelayTrait,
@@ -102,7 +112,7 @@ where
 impl<D> PortInfo<D>
 where
     D: DelayTrait,
-//    CellType: CellTrait<DelayType>,
+    //    CellType: CellTrait<DelayType>,
 {
     pub const fn new() -> Self {
         Self {
@@ -165,9 +175,42 @@ impl const PartialEq for PortBus {
     }
 }
 
+impl PortBus {
+    pub const fn new(name: IdString, offset: i32, brackets: bool) -> Self {
+        Self {
+            name,
+            offset,
+            brackets,
+        }
+    }
+    pub const fn name(&self) -> IdString {
+        self.name
+    }
+    pub const fn offset(&self) -> i32 {
+        self.offset
+    }
+    pub const fn brackets(&self) -> bool {
+        self.brackets
+    }
+    /// The port for bit `i` of the bus, `A[3]` with brackets or `A3` without.
+    pub fn port<D: DelayTrait>(
+        &self,
+        ctx: &mut BaseCtx<D>,
+        i: i32,
+    ) -> Result<IdString, BaseCtxError> {
+        let name = ctx.name_of(self.name)?;
+        let bit = i + self.offset;
+        if self.brackets {
+            Ok(ctx.id(&format!("{name}[{bit}]")))
+        } else {
+            Ok(ctx.id(&format!("{name}{bit}")))
+        }
+    }
+}
+
 /// Represents the contents of a non-leaf cell in a design
 /// with hierarchy
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct HierarchicalPort {
     name: IdString,
     dir: PortType,
@@ -175,3 +218,36 @@ pub struct HierarchicalPort {
     offset: i32,
     up_to: bool,
 }
+
+impl HierarchicalPort {
+    pub const fn new(
+        name: IdString,
+        dir: PortType,
+        nets: Vec<IdString>,
+        offset: i32,
+        up_to: bool,
+    ) -> Self {
+        Self {
+            name,
+            dir,
+            nets,
+            offset,
+            up_to,
+        }
+    }
+    pub const fn name(&self) -> IdString {
+        self.name
+    }
+    pub const fn dir(&self) -> PortType {
+        self.dir
+    }
+    pub fn nets(&self) -> &[IdString] {
+        &self.nets
+    }
+    pub const fn offset(&self) -> i32 {
+        self.offset
+    }
+    pub const fn up_to(&self) -> bool {
+        self.up_to
+    }
+}
@@ -1,8 +1,11 @@
 use bitvec::vec::BitVec;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::fmt::Display;
+use std::str::FromStr;
 
 /// https://web.archive.org/web/20220122134908/https://www.chipverify.com/verilog/verilog-data-types
-#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash)]
+#[derive(Copy, Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum State {
     /// 0    represents a logic zero, or a false condition
@@ -84,7 +87,7 @@ impl State {
     }
 }
 
-#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash)]
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum Property {
     Str(State, String),
     Int(State, i64, String),
@@ -182,18 +185,24 @@ impl Property {
         Self::Int(State::S0, 0, String::new())
     }
 
+    /// The low `width` bits of `intval`, sign extended past 64 bits. The integer is kept as it
+    /// is given, so a negative value stays negative however few bits it is stored in.
     pub fn with_width(intval: i64, width: usize) -> Self {
-        Self::Int(State::S0, intval, {
-            let mut strval = String::with_capacity(width);
-            for i in 0..width {
-                strval.push(if intval & (1 << i) != 0 {
-                    State::S1.into()
+        let strval: String = (0..width)
+            .map(|i| {
+                if (intval >> i.min(63)) & 1 != 0 {
+                    State::S1.to_char()
                 } else {
-                    State::S0.into()
-                })
-            }
-            strval
-        })
+                    State::S0.to_char()
+                }
+            })
+            .collect();
+        let state = if strval.contains(State::S1.to_char()) {
+            State::S1
+        } else {
+            State::S0
+        };
+        Self::Int(state, intval, strval)
     }
 
     pub fn with_str(strval: &str) -> Self {
@@ -220,22 +229,24 @@ impl Property {
 
     pub fn update_intval(&mut self) {
         match self {
-            Self::Str(_state, _strval) => {
-                todo!()
-            }
-            Self::Int(_state, intval, strval) => {
+            // A string has no integer value to bring up to date.
+            Self::Str(_state, _strval) => {}
+            Self::Int(state, intval, strval) => {
                 *intval = 0;
-                for i in 0..strval.len() {
-                    // TODO: This is not a good way to do this conversion, implement a conversion to character.
-                    assert!(
-                        strval.chars().nth(i).unwrap() == State::S0.into()
-                            || strval.chars().nth(i).unwrap() == State::S1.into()
-                            || strval.chars().nth(i).unwrap() == State::Sx.into()
-                            || strval.chars().nth(i).unwrap() == State::Sz.into()
-                    );
-                    if strval.chars().nth(i).unwrap() == State::S1.into() && i < 64 {
+                // Undefined if any bit is, else whether any bit is set.
+                *state = State::S0;
+                for (i, c) in strval.chars().enumerate() {
+                    let bit = State::try_from(c).unwrap();
+                    // Wider values are only kept as the bit string.
+                    if bit == State::S1 && i < 64 {
                         *intval |= 1 << i;
                     }
+                    *state = match (*state, bit) {
+                        (State::Sx, _) | (_, State::Sx) => State::Sx,
+                        (State::Sz, _) | (_, State::Sz) => State::Sz,
+                        (State::S1, _) | (_, State::S1) => State::S1,
+                        _ => State::S0,
+                    };
                 }
             }
         }
@@ -243,59 +254,56 @@ impl Property {
 
     pub fn is_fully_def(&self) -> bool {
         match self {
-            Self::Str(_state, _strval) => {
-                todo!()
-            }
-            Self::Int(_state, _intval, strval) => {
-                //                strval.chars().find(|&x| x == State::Sx.into() || x == State::Sz.into()).is_none()
-                !strval
-                    .chars()
-                    .any(|x| x == State::Sx.into() || x == State::Sz.into())
-            }
+            Self::Str(_state, _strval) => false,
+            Self::Int(_state, _intval, strval) => !strval
+                .chars()
+                .any(|x| x == State::Sx.into() || x == State::Sz.into()),
         }
     }
+    /// `len` bits from `offset` on, padded past the end. A string is read as its bytes, the last
+    /// character being the least significant as in Verilog.
     pub fn extract(&self, offset: usize, len: usize, padding: State) -> Self {
-        let mut ret = Property::default();
-        match &mut ret {
-            Property::Int(_state, _intval, strval) => {
-                *strval = String::with_capacity(len);
-                for i in offset..offset + len {
-                    strval.push(if i < strval.len() {
-                        strval.chars().nth(i).unwrap()
-                    } else {
-                        padding.to_char()
-                    });
-                }
-            }
-            _ => {
-                todo!()
-            }
+        let bits: Vec<char> = match self {
+            Property::Int(_, _, bits) => bits.chars().collect(),
+            Property::Str(_, strval) => strval
+                .bytes()
+                .rev()
+                .flat_map(|byte| (0..8).map(move |i| char::from(b'0' + (byte >> i & 1))))
+                .collect(),
+        };
+        let padding = padding.to_char();
+        let mut strval = String::with_capacity(len);
+        for i in offset..offset + len {
+            strval.push(bits.get(i).copied().unwrap_or(padding));
         }
+        let mut ret = Property::Int(State::S0, 0, strval);
         ret.update_intval();
-
         ret
     }
 
     /// Convert a string of four-value binary [01xz], or a literal string escaped according to the above rule
     /// to a Property
     pub fn from_string(s: &str) -> Self {
-        let mut p = Property::default();
-        let cursor = s.find(|c: char| {
-            c != State::S0.to_char()
-                && c != State::S1.to_char()
-                && c != State::Sx.to_char()
-                && c != State::Sz.to_char()
-        });
-        if cursor.is_none() {
-        } else if s.find(|c: char| c != ' ').is_none() {
-            if let Property::Int(_state, _intval, strval) = &mut p {
-                *strval = s.chars().rev().collect()
+        match s.find(|c: char| State::try_from(c).is_err()) {
+            None => {
+                let mut p = Property::Int(State::S0, 0, s.chars().rev().collect());
+                p.update_intval();
+                p
             }
-            p.update_intval()
-        } else {
-            p = Self::with_str(s);
+            // Only strings that would otherwise read as bits have the space appended by Display.
+            Some(cursor) if s[cursor..].chars().all(|c| c == ' ') => {
+                Self::with_str(&s[..s.len() - 1])
+            }
+            Some(_) => Self::with_str(s),
         }
+    }
+}
+
+impl FromStr for Property {
+    type Err = Infallible;
 
-        p
+    /// The inverse of [`Property`]'s `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_string(s))
     }
 }
@@ -1,8 +1,9 @@
-use crate::ice40::arch_defs::{BelId, WireId};
+use crate::arch::{BelId, WireId};
 use crate::kernel::base_types::Loc;
 use crate::kernel::id_string::IdString;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Region {
     pub name: IdString,
 
@@ -1,8 +1,12 @@
-use super::{delay::{DelayTrait, Delay}, id_string::{IdPair, IdString}};
+use super::{
+    delay::{Delay, DelayTrait},
+    id_string::{IdPair, IdString},
+};
 use core::hash::Hash;
+use serde::{Deserialize, Serialize};
 
 /// Segment type
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub enum SegmentType {
     ClkToQ,  // Clock-to-Q delay
     Source,  // Delayless source
@@ -23,7 +27,7 @@ impl const PartialEq for SegmentType {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct Segment<D: DelayTrait> {
     // Type
     segment_type: SegmentType,
@@ -39,7 +43,31 @@ pub struct Segment<D: DelayTrait> {
     budget: Delay<D>,
 }
 
-impl<D> Hash for Segment<D> where D: DelayTrait {
+impl<D> Segment<D>
+where
+    D: DelayTrait,
+{
+    pub const fn segment_type(&self) -> SegmentType {
+        self.segment_type
+    }
+    pub const fn net(&self) -> IdString {
+        self.net
+    }
+    pub const fn from(&self) -> IdPair {
+        self.from
+    }
+    pub const fn to(&self) -> IdPair {
+        self.to
+    }
+    pub const fn delay(&self) -> Delay<D> {
+        self.delay
+    }
+}
+
+impl<D> Hash for Segment<D>
+where
+    D: DelayTrait,
+{
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.segment_type.hash(state);
         self.net.hash(state);
@@ -52,14 +80,14 @@ impl<D> Hash for Segment<D> where D: DelayTrait {
 
 impl<D> const PartialEq for Segment<D>
 where
-D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.segment_type == other.segment_type
-    && self.net == other.net
-    && self.from == other.from
-    && self.to == other.to
-    && self.delay == other.delay
-    && self.budget == other.budget
+            && self.net == other.net
+            && self.from == other.from
+            && self.to == other.to
+            && self.delay == other.delay
+            && self.budget == other.budget
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,332 @@
+use super::base_context::BaseCtx;
+use super::delay::DelayTrait;
+use super::id_string::IdString;
+use super::property::Property;
+use std::fmt::Display;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+pub enum SettingsError {
+    #[error("Unknown setting {0:?}.")]
+    UnknownKey(String),
+    #[error("Setting {key:?} can't be {value:?}, expected {expected}.")]
+    InvalidValue {
+        key: String,
+        value: String,
+        expected: String,
+    },
+    #[error("Setting override {0:?} is not of the form key=value.")]
+    MalformedOverride(String),
+    #[error("Settings file could not be parsed: {0}")]
+    Toml(String),
+}
+
+/// What a setting holds, and the values it accepts.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SettingKind {
+    Bool,
+    Int { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Str,
+    Enum(&'static [&'static str]),
+}
+
+impl Display for SettingKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingKind::Bool => write!(f, "true or false"),
+            SettingKind::Int { min, max } => write!(f, "an integer from {min} to {max}"),
+            SettingKind::Float { min, max } => write!(f, "a number from {min} to {max}"),
+            SettingKind::Str => write!(f, "a string"),
+            SettingKind::Enum(options) => write!(f, "one of {}", options.join(", ")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl SettingValue {
+    fn to_property(&self) -> Property {
+        match self {
+            SettingValue::Bool(b) => Property::with_width(*b as i64, 1),
+            SettingValue::Int(i) => Property::with_width(*i, 64),
+            // Like nextpnr, floats are kept as their text.
+            SettingValue::Float(x) => Property::with_str(&x.to_string()),
+            SettingValue::Str(s) => Property::with_str(s),
+        }
+    }
+}
+
+impl Display for SettingValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingValue::Bool(b) => write!(f, "{b}"),
+            SettingValue::Int(i) => write!(f, "{i}"),
+            SettingValue::Float(x) => write!(f, "{x}"),
+            SettingValue::Str(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+/// A known setting. Defaults are written as they would be on the command line.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub default: &'static str,
+    pub help: &'static str,
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "seed",
+        kind: SettingKind::Int {
+            min: 0,
+            max: i64::MAX,
+        },
+        default: "1",
+        help: "Random seed for the placer and router",
+    },
+    Setting {
+        key: "frontend/top",
+        kind: SettingKind::Str,
+        default: "",
+        help: "Top module, found from the netlist when empty",
+    },
+    Setting {
+        key: "frontend/x_tieoff",
+        kind: SettingKind::Enum(&["none", "0", "1"]),
+        default: "none",
+        help: "Constant to drive undefined bits with, or none to leave them unconnected",
+    },
+    Setting {
+        key: "place/algorithm",
+        kind: SettingKind::Enum(&["sa", "heap"]),
+        default: "heap",
+        help: "Placer to run",
+    },
+    Setting {
+        key: "place/timing_driven",
+        kind: SettingKind::Bool,
+        default: "true",
+        help: "Weigh critical connections when placing",
+    },
+    Setting {
+        key: "place/timing_weight",
+        kind: SettingKind::Float { min: 0.0, max: 1.0 },
+        default: "0.5",
+        help: "Share of the placer cost given to timing over wirelength",
+    },
+    Setting {
+        key: "route/algorithm",
+        kind: SettingKind::Enum(&["router1", "router2"]),
+        default: "router1",
+        help: "Router to run",
+    },
+    Setting {
+        key: "route/max_iterations",
+        kind: SettingKind::Int {
+            min: 1,
+            max: 100_000,
+        },
+        default: "500",
+        help: "Rip-up and reroute passes before routing gives up",
+    },
+    Setting {
+        key: "timing/target_freq",
+        kind: SettingKind::Float {
+            min: 0.001,
+            max: 10_000.0,
+        },
+        default: "12",
+        help: "Target frequency in MHz for clocks without a constraint",
+    },
+    Setting {
+        key: "timing/allow_unconstrained",
+        kind: SettingKind::Bool,
+        default: "false",
+        help: "Don't fail timing on clocks without a constraint",
+    },
+    Setting {
+        key: "timing/ignore_loops",
+        kind: SettingKind::Bool,
+        default: "false",
+        help: "Break combinational loops instead of reporting them",
+    },
+    Setting {
+        key: "timing/slack_redist_iter",
+        kind: SettingKind::Int { min: 0, max: 1_000 },
+        default: "0",
+        help: "Slack redistribution passes between placement and routing",
+    },
+];
+
+impl Setting {
+    pub fn find(key: &str) -> Result<&'static Setting, SettingsError> {
+        SETTINGS
+            .iter()
+            .find(|setting| setting.key == key)
+            .ok_or_else(|| SettingsError::UnknownKey(key.to_string()))
+    }
+    /// Reads a value from its text, as given on the command line or stored in the context.
+    pub fn parse(&self, text: &str) -> Result<SettingValue, SettingsError> {
+        let value = match self.kind {
+            SettingKind::Bool => match text {
+                "true" | "1" | "yes" | "on" => Some(SettingValue::Bool(true)),
+                "false" | "0" | "no" | "off" => Some(SettingValue::Bool(false)),
+                _ => None,
+            },
+            SettingKind::Int { .. } => text.parse().ok().map(SettingValue::Int),
+            SettingKind::Float { .. } => text.parse().ok().map(SettingValue::Float),
+            SettingKind::Str | SettingKind::Enum(_) => Some(SettingValue::Str(text.to_string())),
+        };
+        self.check(value.ok_or_else(|| self.invalid(text))?)
+    }
+    /// Makes sure `value` fits this setting, integers are accepted for floats.
+    pub fn check(&self, value: SettingValue) -> Result<SettingValue, SettingsError> {
+        let valid = match (&self.kind, &value) {
+            (SettingKind::Bool, SettingValue::Bool(_)) => true,
+            (SettingKind::Int { min, max }, SettingValue::Int(i)) => (min..=max).contains(&i),
+            (SettingKind::Float { .. }, SettingValue::Int(i)) => {
+                return self.check(SettingValue::Float(*i as f64));
+            }
+            (SettingKind::Float { min, max }, SettingValue::Float(x)) => {
+                x.is_finite() && (min..=max).contains(&x)
+            }
+            (SettingKind::Str, SettingValue::Str(_)) => true,
+            (SettingKind::Enum(options), SettingValue::Str(s)) => options.contains(&s.as_str()),
+            _ => false,
+        };
+        if valid {
+            Ok(value)
+        } else {
+            Err(self.invalid(&value.to_string()))
+        }
+    }
+    fn invalid(&self, value: &str) -> SettingsError {
+        SettingsError::InvalidValue {
+            key: self.key.to_string(),
+            value: value.to_string(),
+            expected: self.kind.to_string(),
+        }
+    }
+}
+
+impl<D> BaseCtx<D>
+where
+    D: DelayTrait,
+{
+    /// The value of a known setting, or its default if it hasn't been set.
+    pub fn setting(&self, key: &str) -> Result<SettingValue, SettingsError> {
+        let setting = Setting::find(key)?;
+        let stored = IdString::find(self, key).and_then(|id| self.settings.get(&id));
+        match stored {
+            Some(Property::Str(_, strval)) => setting.parse(strval),
+            Some(Property::Int(_, intval, _)) => setting.parse(&intval.to_string()),
+            None => setting.parse(setting.default),
+        }
+    }
+    pub fn set_setting(&mut self, key: &str, value: SettingValue) -> Result<(), SettingsError> {
+        let value = Setting::find(key)?.check(value)?;
+        let id = self.id(key);
+        self.settings.insert(id, value.to_property());
+        Ok(())
+    }
+    pub fn get_bool(&self, key: &str) -> Result<bool, SettingsError> {
+        match self.setting(key)? {
+            SettingValue::Bool(b) => Ok(b),
+            other => Err(Setting::find(key)?.invalid(&other.to_string())),
+        }
+    }
+    pub fn get_int(&self, key: &str) -> Result<i64, SettingsError> {
+        match self.setting(key)? {
+            SettingValue::Int(i) => Ok(i),
+            other => Err(Setting::find(key)?.invalid(&other.to_string())),
+        }
+    }
+    pub fn get_float(&self, key: &str) -> Result<f64, SettingsError> {
+        match self.setting(key)? {
+            SettingValue::Float(x) => Ok(x),
+            other => Err(Setting::find(key)?.invalid(&other.to_string())),
+        }
+    }
+    /// The value of a string or enum setting.
+    pub fn get_str(&self, key: &str) -> Result<String, SettingsError> {
+        match self.setting(key)? {
+            SettingValue::Str(s) => Ok(s),
+            other => Err(Setting::find(key)?.invalid(&other.to_string())),
+        }
+    }
+    pub fn set_bool(&mut self, key: &str, value: bool) -> Result<(), SettingsError> {
+        self.set_setting(key, SettingValue::Bool(value))
+    }
+    pub fn set_int(&mut self, key: &str, value: i64) -> Result<(), SettingsError> {
+        self.set_setting(key, SettingValue::Int(value))
+    }
+    pub fn set_float(&mut self, key: &str, value: f64) -> Result<(), SettingsError> {
+        self.set_setting(key, SettingValue::Float(value))
+    }
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<(), SettingsError> {
+        self.set_setting(key, SettingValue::Str(value.to_string()))
+    }
+    /// Applies a `key=value` override from the command line.
+    pub fn apply_setting_override(&mut self, arg: &str) -> Result<(), SettingsError> {
+        let (key, text) = arg
+            .split_once('=')
+            .ok_or_else(|| SettingsError::MalformedOverride(arg.to_string()))?;
+        let (key, text) = (key.trim(), text.trim());
+        let value = Setting::find(key)?.parse(text)?;
+        self.set_setting(key, value)
+    }
+    /// Loads a TOML project file. Tables name the part of the flow, so `[place]` then
+    /// `algorithm = "sa"` sets `place/algorithm`.
+    pub fn load_settings_toml(&mut self, text: &str) -> Result<(), SettingsError> {
+        let table: toml::value::Table =
+            toml::from_str(text).map_err(|e| SettingsError::Toml(e.to_string()))?;
+        let mut values = Vec::new();
+        flatten_toml("", &table, &mut values)?;
+        for (key, value) in values {
+            self.set_setting(&key, value)?;
+        }
+        Ok(())
+    }
+    /// Checks every stored setting is known and valid, including any set on the raw map.
+    pub fn check_settings(&self) -> Result<(), SettingsError> {
+        for key in self.settings.keys() {
+            let key = key.to_string(self).unwrap_or_default();
+            self.setting(&key)?;
+        }
+        Ok(())
+    }
+}
+
+fn flatten_toml(
+    prefix: &str,
+    table: &toml::value::Table,
+    values: &mut Vec<(String, SettingValue)>,
+) -> Result<(), SettingsError> {
+    for (name, value) in table {
+        let key = format!("{prefix}{name}");
+        let value = match value {
+            toml::Value::Table(inner) => {
+                flatten_toml(&format!("{key}/"), inner, values)?;
+                continue;
+            }
+            toml::Value::Boolean(b) => SettingValue::Bool(*b),
+            toml::Value::Integer(i) => SettingValue::Int(*i),
+            toml::Value::Float(x) => SettingValue::Float(*x),
+            toml::Value::String(s) => Setting::find(&key)?.parse(s)?,
+            other => {
+                return Err(Setting::find(&key)?.invalid(&other.to_string()));
+            }
+        };
+        values.push((key, value));
+    }
+    Ok(())
+}
@@ -68,3 +68,1176 @@ fn delay_pair_sub() {
     let result = lhs - rhs;
     assert_eq!(result, DelayPair::with_min_max(3.into(), 12.into()));
 }
+
+#[test]
+fn property_round_trip() {
+    use property::{Property, State};
+
+    let wide = format!("x1z{}", "0".repeat(100));
+    let values = [
+        Property::with_width(-5, 32),
+        Property::with_width(1, 70),
+        Property::from_string(&wide),
+        Property::with_width(0, 0),
+        Property::with_state(State::S0),
+        Property::with_state(State::S1),
+        Property::with_state(State::Sx),
+        Property::with_state(State::Sz),
+        Property::from_string("z01"),
+        Property::with_str("hello world"),
+        Property::with_str("0101"),
+        Property::with_str("10  "),
+        Property::with_str(""),
+    ];
+    // Bits carry no sign, so a round trip is compared on them rather than on the integer.
+    for value in values {
+        let read_back = Property::from_string(&value.to_string());
+        assert_eq!(read_back.to_string(), value.to_string());
+        assert_eq!(
+            matches!(read_back, Property::Str(..)),
+            matches!(value, Property::Str(..))
+        );
+    }
+    assert_eq!(i64::try_from(Property::with_width(-5, 32)), Ok(-5));
+    assert_eq!(i64::try_from(Property::with_width(-5, 4)), Ok(-5));
+    assert_eq!(Property::with_width(-5, 4).to_string(), "1011");
+
+    // Strings have no integer to update, and are left as they are.
+    let mut string = Property::with_str("top");
+    string.update_intval();
+    assert_eq!(string, Property::with_str("top"));
+}
+
+#[test]
+fn property_from_string() {
+    use property::{Property, State};
+
+    let bits = Property::from_string("1x0");
+    assert_eq!(bits, Property::Int(State::Sx, 4, "0x1".to_string()));
+    assert!(!bits.is_fully_def());
+    assert_eq!(Property::from_string("0101 "), Property::with_str("0101"));
+    assert_eq!(Property::from_string("top"), Property::with_str("top"));
+    assert_eq!(i64::try_from(Property::from_string("1010")), Ok(10));
+    // "A" is 0x41, the low nibble of the last character comes first.
+    let low = Property::with_str("BA").extract(0, 4, State::S0);
+    assert_eq!(low, Property::with_width(1, 4));
+}
+
+#[test]
+fn src_location_parse() {
+    use base_types::SrcLocation;
+
+    let location = SrcLocation::parse("rtl/top.v:12.3-14.5|rtl/other.v:1.1-1.9").unwrap();
+    assert_eq!(location.file, "rtl/top.v");
+    assert_eq!((location.line, location.column), (12, 3));
+    assert_eq!(location.to_string(), "rtl/top.v:12:3");
+    assert_eq!(
+        SrcLocation::parse("c:/top.v:7").unwrap().to_string(),
+        "c:/top.v:7:0"
+    );
+    assert_eq!(SrcLocation::parse("top.v"), None);
+}
+
+#[test]
+fn error_cites_src() {
+    use base_context::BaseCtx;
+    use property::Property;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let name = ctx.id("u_lut");
+    let cell_type = ctx.id("SB_LUT4");
+    let src = ctx.id("src");
+    let bel = ctx.id("BEL");
+    let index = ctx.create_cell(name, cell_type).unwrap();
+    ctx.cells[index].set_attribute(src, Property::with_str("top.v:12.3-12.20"));
+    ctx.cells[index].set_attribute(bel, Property::with_str("not a bel"));
+    let error = ctx.attributes_to_arch_info().unwrap_err();
+    assert!(error.to_string().contains("u_lut (top.v:12:3)"));
+}
+
+#[test]
+fn id_string_interning() {
+    use base_context::BaseCtx;
+    use constids::{CONSTIDS, ID_GND, ID_INIT, ID_SRC};
+    use id_string::IdString;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    assert!(IdString::new().is_empty());
+    assert_eq!(ctx.id("GND"), ID_GND);
+    assert_eq!(ctx.name_of(ID_INIT).unwrap(), "INIT");
+    assert_eq!(IdString::find(&ctx, "src"), Some(ID_SRC));
+    assert_eq!(IdString::find(&ctx, "net_0"), None);
+
+    // Indices are handed out in order and never move, whatever the strings sort as.
+    let preloaded = CONSTIDS.len() + crate::arch::CONSTIDS.len();
+    let ids: Vec<IdString> = (0..1000)
+        .rev()
+        .map(|i| ctx.id(&format!("net_{i}")))
+        .collect();
+    for (i, id) in ids.iter().enumerate() {
+        assert_eq!(id.index(), (preloaded + i) as u64);
+        assert_eq!(ctx.name_of(*id).unwrap(), format!("net_{}", 999 - i));
+        assert_eq!(ctx.id(&format!("net_{}", 999 - i)), *id);
+    }
+}
+
+#[cfg(feature = "ice40")]
+#[test]
+fn ice40_constids() {
+    use crate::ice40::constids::{ID_LUT_INIT, ID_SB_LUT4};
+    use base_context::BaseCtx;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    assert_eq!(ctx.id("SB_LUT4"), ID_SB_LUT4);
+    assert_eq!(ctx.name_of(ID_LUT_INIT).unwrap(), "LUT_INIT");
+}
+
+#[cfg(feature = "testarch")]
+#[test]
+fn testarch_constids() {
+    use base_context::BaseCtx;
+    use constids::CONSTIDS;
+
+    // The test arch adds no ids of its own, so the first new string comes straight after the
+    // kernel's.
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    assert!(crate::arch::CONSTIDS.is_empty());
+    assert_eq!(ctx.id("SB_LUT4").index(), CONSTIDS.len() as u64);
+    assert_eq!(crate::arch::NAME_DELIMITER, '/');
+}
+
+#[test]
+fn device_names() {
+    use crate::arch::{BelId, WireId};
+    use base_context::BaseCtx;
+    use id_string_list::IdStringList;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let name = IdStringList::parse(&mut ctx, "X3/Y7/lc2");
+    assert_eq!(name.len(), 3);
+    assert_eq!(name.to_string(&ctx).unwrap(), "X3/Y7/lc2");
+    assert_eq!(IdStringList::find(&ctx, "X3/Y7/lc9"), None);
+
+    let bel = BelId::with_index(42);
+    ctx.bel_names.insert(bel, name);
+    assert_eq!(ctx.name_of_bel(bel), "X3/Y7/lc2");
+    assert_eq!(ctx.get_bel_by_name_str("X3/Y7/lc2"), bel);
+    assert_eq!(ctx.get_bel_by_name_str("X3/Y7/lc9"), BelId::new());
+
+    // Objects the arch hasn't named print as their index, which doesn't look anything up.
+    assert_eq!(ctx.name_of_wire(WireId::with_index(9)), "9");
+    assert_eq!(ctx.get_wire_by_name_str("9"), WireId::new());
+    assert_eq!(ctx.get_bel_by_name_str("42"), BelId::new());
+}
+
+#[test]
+fn guarded_context_threads() {
+    use base_context::{BaseCtx, GuardedBaseCtx};
+    use id_string::IdString;
+    use std::thread;
+
+    let guarded = GuardedBaseCtx::new(BaseCtx::<i64>::new());
+    let workers: Vec<_> = (0..4)
+        .map(|t| {
+            let guarded = guarded.clone();
+            thread::spawn(move || {
+                (0..100)
+                    .map(|i| guarded.id(&format!("net_{}", (i * 7 + t) % 50)))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let observer = {
+        let guarded = guarded.clone();
+        thread::spawn(move || guarded.observe().cells.len())
+    };
+    {
+        let mut ctx = guarded.write();
+        let name = ctx.id("cell");
+        let cell_type = ctx.id("SB_LUT4");
+        ctx.create_cell(name, cell_type).unwrap();
+        let _ctx = guarded.yield_write(ctx);
+    }
+    let ids: Vec<_> = workers
+        .into_iter()
+        .flat_map(|w| w.join().unwrap())
+        .collect();
+    assert!(observer.join().unwrap() <= 1);
+
+    // Every thread agrees on the index of every string.
+    let ctx = guarded.into_inner().ok().unwrap();
+    for id in ids {
+        assert_eq!(IdString::find(&ctx, &ctx.name_of(id).unwrap()), Some(id));
+    }
+}
+
+#[test]
+fn guarded_context_observer_not_starved() {
+    use base_context::{BaseCtx, GuardedBaseCtx};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    let guarded = GuardedBaseCtx::new(BaseCtx::<i64>::new());
+    let observed = Arc::new(AtomicBool::new(false));
+    let observer = {
+        let (guarded, observed) = (guarded.clone(), observed.clone());
+        thread::spawn(move || {
+            // Parked until the worker below yields, as the write guard is already taken.
+            let _ctx = guarded.observe();
+            observed.store(true, Ordering::SeqCst);
+        })
+    };
+    // A worker that never lets go of the write guard other than by yielding.
+    let mut ctx = guarded.write();
+    let mut yields = 0;
+    while !observed.load(Ordering::SeqCst) && yields < 1_000_000 {
+        ctx = guarded.yield_write(ctx);
+        yields += 1;
+    }
+    drop(ctx);
+    observer.join().unwrap();
+    assert!(yields < 1_000_000);
+}
+
+#[test]
+fn checkpoint_round_trip() {
+    use base_context::{BaseCtx, BaseCtxError};
+    use property::Property;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (name, cell_type, net_name) = (ctx.id("lut"), ctx.id("SB_LUT4"), ctx.id("n"));
+    let (output, init, seed) = (ctx.id("O"), ctx.id("LUT_INIT"), ctx.id("place/seed"));
+    let cell = ctx.create_cell(name, cell_type).unwrap();
+    let net = ctx.create_net(net_name).unwrap();
+    ctx.cells[cell].add_output(output);
+    ctx.cells[cell]
+        .connect_port(output, net, &mut ctx.nets)
+        .unwrap();
+    ctx.cells[cell].set_param(init, Property::with_width(0x8000, 16));
+    ctx.settings.insert(seed, Property::with_width(7, 32));
+
+    let mut checkpoint = Vec::new();
+    ctx.save_checkpoint(&mut checkpoint).unwrap();
+    let loaded = BaseCtx::<i64>::load_checkpoint(checkpoint.as_slice()).unwrap();
+    assert!(loaded == ctx);
+    let hash = |ctx: &BaseCtx<i64>| {
+        let mut hasher = DefaultHasher::new();
+        ctx.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&loaded), hash(&ctx));
+    assert_eq!(loaded.get_cell(name).unwrap().ports().len(), 1);
+
+    checkpoint[0] = 99;
+    assert!(matches!(
+        BaseCtx::<i64>::load_checkpoint(checkpoint.as_slice()),
+        Err(BaseCtxError::CheckpointVersion(99))
+    ));
+}
+
+#[test]
+fn typed_settings() {
+    use base_context::BaseCtx;
+    use property::Property;
+    use settings::SettingsError;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    assert_eq!(ctx.get_int("seed"), Ok(1));
+    assert_eq!(ctx.get_str("place/algorithm").unwrap(), "heap");
+    ctx.set_int("seed", 42).unwrap();
+    ctx.set_float("timing/target_freq", 48.0).unwrap();
+    assert_eq!(ctx.get_int("seed"), Ok(42));
+    assert_eq!(ctx.get_float("timing/target_freq"), Ok(48.0));
+    assert!(matches!(
+        ctx.set_float("place/timing_weight", 1.5),
+        Err(SettingsError::InvalidValue { .. })
+    ));
+    assert!(ctx.set_str("route/algorithm", "maze").is_err());
+    assert_eq!(
+        ctx.get_bool("place/timing_drivn"),
+        Err(SettingsError::UnknownKey("place/timing_drivn".to_string()))
+    );
+
+    ctx.apply_setting_override("place/timing_driven=off")
+        .unwrap();
+    ctx.apply_setting_override(" route/algorithm = router2 ")
+        .unwrap();
+    assert_eq!(ctx.get_bool("place/timing_driven"), Ok(false));
+    assert_eq!(ctx.get_str("route/algorithm").unwrap(), "router2");
+    assert!(matches!(
+        ctx.apply_setting_override("seed"),
+        Err(SettingsError::MalformedOverride(_))
+    ));
+
+    ctx.load_settings_toml("seed = 3\n[timing]\ntarget_freq = 100\nignore_loops = true\n")
+        .unwrap();
+    assert_eq!(ctx.get_int("seed"), Ok(3));
+    assert_eq!(ctx.get_float("timing/target_freq"), Ok(100.0));
+    assert_eq!(ctx.get_bool("timing/ignore_loops"), Ok(true));
+    assert!(matches!(
+        ctx.load_settings_toml("[place]\nalgorithim = \"sa\"\n"),
+        Err(SettingsError::UnknownKey(_))
+    ));
+
+    ctx.check_settings().unwrap();
+    let stray = ctx.id("place/stray");
+    ctx.settings.insert(stray, Property::with_str("1"));
+    assert!(ctx.check_settings().is_err());
+}
+
+#[test]
+fn transaction_rollback() {
+    use base_context::{BaseCtx, BaseCtxError};
+    use cell::CellError;
+    use property::Property;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (a, b, lut) = (ctx.id("a"), ctx.id("b"), ctx.id("SB_LUT4"));
+    let (n, out) = (ctx.id("n"), ctx.id("O"));
+    let cell_a = ctx.create_cell(a, lut).unwrap();
+    let net = ctx.create_net(n).unwrap();
+    ctx.cells[cell_a].add_output(out);
+    ctx.cells[cell_a]
+        .connect_port(out, net, &mut ctx.nets)
+        .unwrap();
+    let before_net = ctx.nets[net].clone();
+
+    // A second driver fails halfway through, everything the move did is undone.
+    let result = ctx.transaction(|ctx| {
+        let cell_b = ctx.create_cell(b, lut)?;
+        ctx.cells[cell_b].add_output(out);
+        ctx.cells[cell_b]
+            .connect_port(out, net, &mut ctx.nets)
+            .map_err(|e| ctx.cell_error(cell_b, e))
+    });
+    assert!(matches!(
+        result,
+        Err(BaseCtxError::Cell(_, CellError::DriverCellInUse))
+    ));
+    assert!(ctx.get_cell(b).is_none());
+    assert_eq!(ctx.cells.len(), 1);
+    assert_eq!(ctx.nets[net], before_net);
+    assert!(!ctx.in_transaction());
+
+    // Nested transactions fold into the outer one when committed.
+    let alias = ctx.id("n_alias");
+    ctx.begin_transaction();
+    ctx.begin_transaction();
+    ctx.add_net_alias(alias, net).unwrap();
+    ctx.commit_transaction().unwrap();
+    assert!(ctx.get_net(alias).is_some());
+    ctx.rollback_transaction().unwrap();
+    assert!(ctx.get_net(alias).is_none());
+    assert_eq!(ctx.nets[net], before_net);
+    assert_eq!(ctx.commit_transaction(), Err(BaseCtxError::NoTransaction));
+
+    // The tables beside the netlist are restored as well.
+    let seed = ctx.id("place/seed");
+    ctx.begin_transaction();
+    ctx.settings.insert(seed, Property::with_width(1, 32));
+    ctx.port_cells.insert(a, cell_a);
+    ctx.rollback_transaction().unwrap();
+    assert!(ctx.settings.is_empty());
+    assert!(ctx.port_cells.is_empty());
+}
+
+#[test]
+fn netlist_check() {
+    use crate::arch::BelId;
+    use base_context::BaseCtx;
+    use base_types::PlaceStrength;
+    use check::Violation;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (a, b, lut) = (ctx.id("a"), ctx.id("b"), ctx.id("SB_LUT4"));
+    let (n, out, input) = (ctx.id("n"), ctx.id("O"), ctx.id("I0"));
+    let cell_a = ctx.create_cell(a, lut).unwrap();
+    let cell_b = ctx.create_cell(b, lut).unwrap();
+    let net = ctx.create_net(n).unwrap();
+    ctx.cells[cell_a].add_output(out);
+    ctx.cells[cell_a]
+        .connect_port(out, net, &mut ctx.nets)
+        .unwrap();
+    ctx.cells[cell_b].add_input(input);
+    ctx.cells[cell_b]
+        .connect_port(input, net, &mut ctx.nets)
+        .unwrap();
+    assert_eq!(ctx.check(), vec![]);
+
+    let bel = BelId::with_index(3);
+    ctx.cells[cell_a].set_bel(bel, PlaceStrength::Placer);
+    ctx.cells[cell_b].set_bel(bel, PlaceStrength::Placer);
+    ctx.nets[net].users.clear();
+    let first = ctx.describe_cell(&ctx.cells[cell_a]);
+    let second = ctx.describe_cell(&ctx.cells[cell_b]);
+    assert_eq!(
+        ctx.check(),
+        vec![
+            Violation::BelConflict {
+                bel: ctx.name_of_bel(bel),
+                first,
+                second: second.clone(),
+            },
+            Violation::PortNotOnNet {
+                cell: second,
+                port: "I0".to_string(),
+                net: ctx.describe_net(&ctx.nets[net]),
+            },
+        ]
+    );
+    assert_eq!(
+        ctx.check()[1].to_string(),
+        "Port I0 of cell b isn't recorded as the driver or a user of net n."
+    );
+}
+
+#[test]
+fn netlist_editing() {
+    use crate::arch::{BelId, PipId, WireId};
+    use crate::generic::builder::GenericArchBuilder;
+    use base_context::{BaseCtx, BaseCtxError};
+    use base_types::PlaceStrength;
+    use cell::CellError;
+    use port::PortType;
+    use std::collections::BTreeMap;
+    use types::PipMap;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (a, b, lut) = (ctx.id("a"), ctx.id("b"), ctx.id("SB_LUT4"));
+    let (n, renamed, out, input) = (ctx.id("n"), ctx.id("m"), ctx.id("O"), ctx.id("I0"));
+    let cell_a = ctx.create_cell(a, lut).unwrap();
+    let cell_b = ctx.create_cell(b, lut).unwrap();
+    let net = ctx.create_net(n).unwrap();
+    ctx.cells[cell_a].add_output(out);
+    ctx.connect_port(n, a, out).unwrap();
+    // Ports aren't made up on the fly, the cell has to have them.
+    assert!(matches!(
+        ctx.connect_port(n, b, input),
+        Err(BaseCtxError::Cell(_, CellError::PortNotFound))
+    ));
+    ctx.cells[cell_b].add_input(input);
+    ctx.connect_port(n, b, input).unwrap();
+    assert_eq!(ctx.nets[net].users.len(), 1);
+    let unknown = ctx.connect_port(renamed, b, input);
+    assert_eq!(unknown, Err(BaseCtxError::UnknownNet("m".to_string())));
+    assert_eq!(unknown.unwrap_err().to_string(), "No net is named m.");
+
+    ctx.rename_net(n, renamed).unwrap();
+    assert!(ctx.get_net(n).is_none());
+    assert_eq!(ctx.get_net_index(renamed), Some(net));
+    assert_eq!(
+        ctx.rename_net(renamed, renamed),
+        Err(BaseCtxError::DuplicateNetName("m".to_string()))
+    );
+    // Renaming through an alias replaces that alias and leaves the name alone.
+    let (alias, realias) = (ctx.id("m_alias"), ctx.id("m_other"));
+    ctx.add_net_alias(alias, net).unwrap();
+    ctx.rename_net(alias, realias).unwrap();
+    assert!(ctx.get_net(alias).is_none());
+    assert_eq!(ctx.get_net_index(realias), Some(net));
+    assert_eq!(ctx.nets[net].name(), renamed);
+    assert_eq!(ctx.nets[net].aliases(), &[realias]);
+
+    // Nothing is bound in this arch, only the netlist's routing is raised.
+    let mut arch = GenericArchBuilder::new("empty").build(&mut ctx);
+    let wire = WireId::with_index(1);
+    ctx.nets[net].set_wire(
+        wire,
+        PipMap::with_pip(PipId::with_index(2), PlaceStrength::Placer),
+    );
+    ctx.lock_net_routing(&mut arch, renamed).unwrap();
+    assert_eq!(ctx.nets[net].wires()[&wire].strength(), PlaceStrength::User);
+
+    let bel = BelId::with_index(4);
+    let pins = BTreeMap::from([(input, PortType::In), (ctx.id("I1"), PortType::In)]);
+    ctx.bel_pins.insert(bel, pins);
+    ctx.copy_bel_ports(b, bel).unwrap();
+    assert_eq!(ctx.get_cell(b).unwrap().ports().len(), 2);
+    assert_eq!(
+        ctx.get_cell_index(b)
+            .and_then(|cell| ctx.cells[cell].get_port(input)),
+        Some(net)
+    );
+    assert_eq!(ctx.check(), vec![]);
+}
+
+#[test]
+fn connect_ports_and_bus_moves() {
+    use base_context::{BaseCtx, BaseCtxError};
+    use cell::CellError;
+    use port::PortBus;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (a, b, lut) = (ctx.id("a"), ctx.id("b"), ctx.id("SB_LUT4"));
+    let (out, input) = (ctx.id("O"), ctx.id("I0"));
+    let cell_a = ctx.create_cell(a, lut).unwrap();
+    let cell_b = ctx.create_cell(b, lut).unwrap();
+    ctx.cells[cell_a].add_output(out);
+    ctx.cells[cell_b].add_input(input);
+    // The first name is taken, so the new net gets a suffix.
+    let (taken, conn) = (ctx.id("a$conn$O"), ctx.id("a$conn$O$1"));
+    ctx.create_net(taken).unwrap();
+    ctx.connect_ports(cell_a, out, cell_b, input).unwrap();
+    let net = ctx.get_net_index(conn).unwrap();
+    assert_eq!(ctx.cells[cell_a].get_port(out), Some(net));
+    assert_eq!(ctx.cells[cell_b].get_port(input), Some(net));
+
+    let (from, to, ram) = (ctx.id("from"), ctx.id("to"), ctx.id("SB_RAM40_4K"));
+    let from_cell = ctx.create_cell(from, ram).unwrap();
+    let to_cell = ctx.create_cell(to, ram).unwrap();
+    let raddr = PortBus::new(ctx.id("RADDR"), 0, false);
+    let addr = PortBus::new(ctx.id("ADDR"), 4, true);
+    for i in 0..3 {
+        let (port, net) = (raddr.port(&mut ctx, i).unwrap(), ctx.id(&format!("n{i}")));
+        ctx.create_net(net).unwrap();
+        ctx.cells[from_cell].add_input(port);
+        ctx.connect_port(net, from, port).unwrap();
+    }
+    ctx.move_port_bus_to(from_cell, raddr, to_cell, addr, 3)
+        .unwrap();
+    let (n1, raddr1, addr5) = (ctx.id("n1"), ctx.id("RADDR1"), ctx.id("ADDR[5]"));
+    let n1 = ctx.get_net_index(n1).unwrap();
+    assert_eq!(ctx.cells[to_cell].get_port(addr5), Some(n1));
+    assert_eq!(ctx.cells[from_cell].get_port(raddr1), None);
+    ctx.copy_port_bus_to(to_cell, addr, from_cell, raddr, 3)
+        .unwrap();
+    assert_eq!(ctx.cells[from_cell].get_port(raddr1), Some(n1));
+    assert_eq!(ctx.nets[n1].users.len(), 2);
+    assert_eq!(ctx.check(), vec![]);
+
+    // The last bit clashes with an output, so none of the bits move.
+    let x = PortBus::new(ctx.id("X"), 0, false);
+    let (x0, x2, raddr0) = (ctx.id("X0"), ctx.id("X2"), ctx.id("RADDR0"));
+    ctx.cells[to_cell].add_output(x2);
+    assert!(matches!(
+        ctx.move_port_bus_to(from_cell, raddr, to_cell, x, 3),
+        Err(BaseCtxError::Cell(_, CellError::PortTypeMismatch))
+    ));
+    assert!(!ctx.cells[to_cell].ports().contains_key(&x0));
+    let n0 = ctx.cells[from_cell].get_port(raddr0).unwrap();
+    // Moving onto a connected port takes it off its old net first.
+    ctx.move_port_to(from_cell, raddr0, to_cell, addr5).unwrap();
+    assert_eq!(ctx.cells[to_cell].get_port(addr5), Some(n0));
+    assert_eq!(ctx.nets[n1].users.len(), 1);
+    assert_eq!(ctx.check(), vec![]);
+}
+
+#[test]
+fn disconnect_keeps_user_ids() {
+    use base_context::BaseCtx;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (n, lut, out, input) = (ctx.id("n"), ctx.id("SB_LUT4"), ctx.id("O"), ctx.id("I0"));
+    let net = ctx.create_net(n).unwrap();
+    let names: Vec<_> = (0..4).map(|i| ctx.id(&format!("c{i}"))).collect();
+    for name in &names {
+        ctx.create_cell(*name, lut).unwrap();
+    }
+    let driver = ctx.get_cell_index(names[0]).unwrap();
+    ctx.cells[driver].add_output(out);
+    ctx.connect_port(n, names[0], out).unwrap();
+    for name in &names[1..] {
+        let cell = ctx.get_cell_index(*name).unwrap();
+        ctx.cells[cell].add_input(input);
+        ctx.connect_port(n, *name, input).unwrap();
+    }
+    let user_of = |ctx: &BaseCtx<i64>, name| ctx.get_cell(name).unwrap().ports()[&input].user_index;
+    let last = user_of(&ctx, names[3]);
+
+    ctx.disconnect_port(names[1], input).unwrap();
+    ctx.disconnect_port(names[0], out).unwrap();
+    assert_eq!(ctx.nets[net].users.len(), 2);
+    assert!(ctx.nets[net].driver.cell.is_none());
+    assert_eq!(user_of(&ctx, names[1]), None);
+    assert_eq!(user_of(&ctx, names[3]), last);
+    // Ports that don't exist are already disconnected.
+    ctx.disconnect_port(names[2], out).unwrap();
+    assert_eq!(ctx.check(), vec![]);
+
+    // A freed slot is reused without disturbing anyone else.
+    ctx.connect_port(n, names[1], input).unwrap();
+    assert_eq!(ctx.nets[net].users.len(), 3);
+    assert_eq!(user_of(&ctx, names[3]), last);
+    assert_eq!(ctx.check(), vec![]);
+}
+
+#[test]
+fn device_db_round_trip() {
+    use crate::arch::{BelId, WireId};
+    use crate::generic::builder::GenericArchBuilder;
+    use arch_api::ArchAPI;
+    use base_context::BaseCtx;
+    use device_db::*;
+    use id_string_list::IdStringList;
+    use port::PortType;
+
+    // Two logic tiles either side of an empty one, their O and I wires joined into one node.
+    let logic = TileTypeData {
+        name: "LOGIC".to_string(),
+        bels: vec![BelData {
+            name: "lc0".to_string(),
+            bel_type: "LUT4".to_string(),
+            z: 0,
+            pins: vec![
+                BelPinData {
+                    name: "I0".to_string(),
+                    wire: 0,
+                    dir: PortType::In,
+                },
+                BelPinData {
+                    name: "O".to_string(),
+                    wire: 1,
+                    dir: PortType::Out,
+                },
+            ],
+        }],
+        wires: vec![
+            WireData {
+                name: "I".to_string(),
+                wire_type: "LOCAL".to_string(),
+                timing_class: 0,
+            },
+            WireData {
+                name: "O".to_string(),
+                wire_type: "LOCAL".to_string(),
+                timing_class: 0,
+            },
+        ],
+        pips: vec![PipData {
+            src: 1,
+            dst: 0,
+            timing_class: 1,
+        }],
+    };
+    let empty = TileTypeData {
+        name: "NULL".to_string(),
+        ..Default::default()
+    };
+    let tile = |name: &str, tile_type| TileData {
+        name: name.to_string(),
+        tile_type,
+    };
+    let writer = DeviceDbWriter {
+        name: "tiny".to_string(),
+        width: 3,
+        height: 1,
+        tile_types: vec![logic, empty],
+        tiles: vec![tile("X0Y0", 0), tile("X1Y0", 1), tile("X2Y0", 0)],
+        nodes: vec![NodeData {
+            wires: vec![(0, 1), (2, 0)],
+        }],
+        timing_classes: vec![
+            TimingClass {
+                min_delay: 10,
+                max_delay: 20
+            };
+            2
+        ],
+        packages: vec![PackageData {
+            name: "pkg".to_string(),
+            pins: vec![("A1".to_string(), 2, 0)],
+        }],
+    };
+
+    let path = std::env::temp_dir().join(format!("device_db_{}.bin", std::process::id()));
+    writer.write(std::fs::File::create(&path).unwrap()).unwrap();
+    let db = DeviceDb::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((db.name(), db.width(), db.height()), ("tiny", 3, 1));
+    assert_eq!((db.bel_count(), db.wire_count(), db.pip_count()), (2, 4, 2));
+
+    let bel = BelId::with_index(1);
+    assert_eq!(db.bel_tile(bel), Some((2, 0)));
+    assert_eq!(db.bel_name(bel), Some(("X2Y0", "lc0")));
+    assert_eq!(db.bel_type(bel), Some("LUT4"));
+    assert_eq!(db.bel_location(bel).unwrap(), base_types::Loc::new(2, 0, 0));
+    let pins: Vec<_> = db.bel_pins(bel).collect();
+    assert_eq!(pins[1], ("O", WireId::with_index(3), PortType::Out));
+    assert_eq!(db.bel_tile(BelId::with_index(2)), None);
+
+    let (out, input) = (WireId::with_index(1), WireId::with_index(2));
+    assert_eq!(db.wire_node(out), db.wire_node(input));
+    assert_eq!(db.wire_node(WireId::with_index(0)), None);
+    assert_eq!(db.node_wires(0).collect::<Vec<_>>(), vec![out, input]);
+    // A node is one wire, reached and left through the pips of all its tiles.
+    assert_eq!(db.canonical_wire(input), out);
+    assert_eq!(db.wires().count(), 3);
+    let pip = db.pips_downhill(input).next().unwrap();
+    assert_eq!(db.pip_dst_wire(pip), Some(WireId::with_index(0)));
+    assert_eq!(db.pip_timing(pip).unwrap().max_delay, 20);
+    let uphill: Vec<_> = db.pips_uphill(out).collect();
+    assert_eq!(uphill.len(), 1);
+    assert_eq!(db.pip_dst_wire(uphill[0]), Some(out));
+    assert_eq!(
+        db.package_pins("pkg").unwrap().collect::<Vec<_>>(),
+        vec![("A1", bel)]
+    );
+
+    // The generic arch loads the database, a node becoming a single wire.
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let arch = GenericArchBuilder::from_device_db(&mut ctx, &db)
+        .unwrap()
+        .build(&mut ctx);
+    let (o, i0) = (ctx.id("O"), ctx.id("I0"));
+    let near = arch
+        .get_bel_by_name(&IdStringList::parse(&mut ctx, "X0Y0/lc0"))
+        .unwrap();
+    let far = arch
+        .get_bel_by_name(&IdStringList::parse(&mut ctx, "X2Y0/lc0"))
+        .unwrap();
+    assert_eq!(arch.get_wires().count(), 3);
+    assert_eq!(
+        arch.get_bel_pin_wire(near, o),
+        arch.get_bel_pin_wire(far, i0)
+    );
+    let joined = arch.get_bel_pin_wire(near, o).unwrap();
+    assert_eq!(arch.get_pips_downhill(joined).count(), 1);
+    assert_eq!(arch.get_pips_uphill(joined).count(), 1);
+
+    let mut bytes = writer.to_bytes().unwrap();
+    bytes[8] = 9;
+    assert!(matches!(
+        DeviceDb::from_bytes(bytes),
+        Err(DeviceDbError::Version(9))
+    ));
+    assert!(matches!(
+        DeviceDb::from_bytes(vec![0; 4]),
+        Err(DeviceDbError::Truncated)
+    ));
+    // References are checked once when loading. Bytes 80 to 84 of the header say where the
+    // tiles section starts, and its first word is the first tile's type.
+    let mut bytes = writer.to_bytes().unwrap();
+    let tiles = u32::from_le_bytes(bytes[80..84].try_into().unwrap()) as usize;
+    bytes[tiles] = 99;
+    let error = DeviceDb::from_bytes(bytes).err();
+    assert_eq!(error, Some(DeviceDbError::InvalidReference("tile type")));
+    // The node map, found through bytes 88 to 92, may only put a wire in a node that lists it.
+    // The first tile's I wire is in no node, pointing it at the one node is caught.
+    let mut bytes = writer.to_bytes().unwrap();
+    let node_map = u32::from_le_bytes(bytes[88..92].try_into().unwrap()) as usize;
+    bytes[node_map..node_map + 4].copy_from_slice(&0u32.to_le_bytes());
+    let error = DeviceDb::from_bytes(bytes).err();
+    assert_eq!(error, Some(DeviceDbError::InvalidReference("node wires")));
+}
+
+#[test]
+fn generic_arch_builder() {
+    use crate::arch::{BelId, PipId};
+    use crate::generic::builder::{GenericArchBuilder, GenericArchError};
+    use arch_api::{ArchAPI, ArchError};
+    use base_context::{BaseCtx, BaseCtxError};
+    use base_types::{Loc, PlaceStrength};
+    use delay::{Delay, DelayQuad};
+    use id_string_list::IdStringList;
+    use port::PortType;
+    use thunderdome::Arena;
+    use types::PipMap;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (lut4, dff, local) = (ctx.id("LUT4"), ctx.id("DFF"), ctx.id("LOCAL"));
+    let (i0, o, d) = (ctx.id("I0"), ctx.id("O"), ctx.id("D"));
+    let names: Vec<_> = ["X0/Y0/O", "X1/Y0/D", "X1/Y0/O->D", "X0/Y0/lut", "X1/Y0/ff"]
+        .iter()
+        .map(|name| IdStringList::parse(&mut ctx, name))
+        .collect();
+
+    // A LUT driving a flip-flop one tile over through a single pip.
+    let mut builder = GenericArchBuilder::new("tiny");
+    let out = builder.add_wire(names[0].clone(), local, 0, 0).unwrap();
+    let data = builder.add_wire(names[1].clone(), local, 1, 0).unwrap();
+    let hop = DelayQuad::with_delay(Delay::with_delay(50));
+    let pip = builder
+        .add_pip(names[2].clone(), local, out, data, hop, Loc::new(1, 0, 0))
+        .unwrap();
+    let lut = builder
+        .add_bel(names[3].clone(), lut4, Loc::new(0, 0, 0), false, false)
+        .unwrap();
+    let ff = builder
+        .add_bel(names[4].clone(), dff, Loc::new(1, 0, 0), false, false)
+        .unwrap();
+    builder.add_bel_output(lut, o, out).unwrap();
+    builder.add_bel_input(ff, d, data).unwrap();
+    builder.add_cell_timing_delay(lut4, i0, o, DelayQuad::with_delay(Delay::with_delay(120)));
+    assert_eq!(
+        builder.add_wire(names[0].clone(), local, 0, 0),
+        Err(GenericArchError::DuplicateName(names[0].clone()))
+    );
+    assert_eq!(
+        builder.add_bel(names[2].clone(), dff, Loc::new(1, 0, 0), false, false),
+        Err(GenericArchError::LocationTaken(Loc::new(1, 0, 0)))
+    );
+    assert_eq!(
+        builder.add_bel_input(ff, d, data),
+        Err(GenericArchError::DuplicatePin(ff, d))
+    );
+
+    // Groups nest, but never into themselves.
+    let outer = builder.add_group(names[0].clone()).unwrap();
+    let inner = builder.add_group(names[1].clone()).unwrap();
+    builder.add_group_group(outer, inner).unwrap();
+    assert_eq!(
+        builder.add_group_group(inner, outer),
+        Err(GenericArchError::GroupCycle(inner, outer))
+    );
+    assert_eq!(
+        builder.add_group_group(inner, inner),
+        Err(GenericArchError::GroupCycle(inner, inner))
+    );
+
+    let mut arch = builder.build(&mut ctx);
+    assert_eq!((arch.get_grid_dim_x(), arch.get_grid_dim_y()), (2, 1));
+    // The context knows the device's names too.
+    assert_eq!(ctx.get_bel_by_name_str("X1/Y0/ff"), ff);
+    assert_eq!(ctx.name_of_pip(pip), "X1/Y0/O->D");
+    assert_eq!(arch.get_bels().collect::<Vec<_>>(), vec![lut, ff]);
+    assert_eq!(arch.get_bel_by_name(&names[4]), Some(ff));
+    assert_eq!(arch.get_bel_by_location(Loc::new(1, 0, 0)), Some(ff));
+    assert_eq!(arch.get_bels_by_tile(0, 0).collect::<Vec<_>>(), vec![lut]);
+    assert_eq!(arch.get_bel_pin_wire(ff, d), Some(data));
+    assert_eq!(arch.get_bel_pin_type(lut, o), PortType::Out);
+    assert_eq!(arch.get_bel_pin_type(lut, d), PortType::In);
+    assert_eq!(arch.get_pips_downhill(out).collect::<Vec<_>>(), vec![pip]);
+    assert_eq!(arch.get_pips_uphill(data).collect::<Vec<_>>(), vec![pip]);
+    assert_eq!(
+        arch.get_wire_bel_pins(data).collect::<Vec<_>>(),
+        vec![(ff, d)]
+    );
+    assert_eq!(arch.get_pip_delay(pip), hop);
+    assert_eq!(arch.estimate_delay(out, data), 100);
+    assert_eq!(arch.get_bel_buckets().len(), 2);
+    assert_eq!(arch.get_bel_bucket_for_bel(ff), dff);
+
+    let cell = ctx.id("u_lut");
+    let lut_cell = ctx.create_cell(cell, lut4).unwrap();
+    assert!(arch.get_cell_delay(&ctx.cells[lut_cell], i0, o).is_some());
+    assert_eq!(
+        arch.get_port_timing_class(&ctx.cells[lut_cell], o).0,
+        timing::TimingPortClass::CombOutput
+    );
+
+    // Binding a pip takes the wire it drives along with it.
+    let mut nets = Arena::new();
+    let net = nets.insert(net::NetInfo::new());
+    arch.bind_pip(pip, net, PlaceStrength::Strong).unwrap();
+    assert_eq!(arch.get_bound_wire_net(data), Some(net));
+    assert!(!arch.check_pip_avail(pip));
+    arch.unbind_pip(pip).unwrap();
+    assert!(arch.check_wire_avail(data));
+
+    // A pip the arch has bound is stale unless the net reaches the wire it drives through it.
+    let stray_name = ctx.id("stray");
+    let stray = ctx.create_net(stray_name).unwrap();
+    arch.bind_pip(pip, stray, PlaceStrength::Strong).unwrap();
+    let source = PipMap::with_pip(PipId::new(), PlaceStrength::Strong);
+    ctx.nets[stray].set_wire(data, source);
+    assert_eq!(
+        ctx.check_arch(&arch),
+        vec![check::Violation::StalePipBinding {
+            pip: ctx.name_of_pip(pip),
+            net: "stray".to_string(),
+        }]
+    );
+    ctx.nets[stray].set_wire(data, PipMap::with_pip(pip, PlaceStrength::Strong));
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+    ctx.unbind_pip(&mut arch, pip).unwrap();
+    assert!(ctx.nets[stray].wires().is_empty());
+
+    // The netlist and the arch's bindings are checked against each other both ways round.
+    arch.bind_bel(lut, lut_cell, PlaceStrength::Strong).unwrap();
+    assert_eq!(
+        ctx.check_arch(&arch),
+        vec![check::Violation::StaleBelBinding {
+            bel: ctx.name_of_bel(lut),
+            cell: "u_lut".to_string(),
+        }]
+    );
+    ctx.cells[lut_cell].set_bel(lut, PlaceStrength::Strong);
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+    arch.unbind_bel(lut).unwrap();
+    assert_eq!(ctx.check_arch(&arch).len(), 1);
+
+    // Binding through the context keeps the netlist in step, and ripping a net up unbinds it.
+    ctx.bind_bel(&mut arch, lut, lut_cell, PlaceStrength::Strong)
+        .unwrap();
+    // Moving a placed cell lets go of the bel it was on.
+    ctx.bind_bel(&mut arch, ff, lut_cell, PlaceStrength::Strong)
+        .unwrap();
+    assert!(arch.check_bel_avail(lut));
+    assert_eq!(arch.get_bound_bel_cell(ff), Some(lut_cell));
+    assert_eq!(
+        ctx.bind_bel(&mut arch, ff, lut_cell, PlaceStrength::Strong),
+        Err(BaseCtxError::Arch(ArchError::BelUnavailable))
+    );
+    assert_eq!(ctx.cells[lut_cell].bel(), ff);
+    ctx.unbind_bel(&mut arch, ff).unwrap();
+    assert_eq!(ctx.cells[lut_cell].bel(), BelId::new());
+    let name = ctx.id("n");
+    let routed = ctx.create_net(name).unwrap();
+    ctx.bind_wire(&mut arch, out, routed, PlaceStrength::Strong)
+        .unwrap();
+    ctx.bind_pip(&mut arch, pip, routed, PlaceStrength::Strong)
+        .unwrap();
+    assert_eq!(ctx.nets[routed].wires()[&data].pip(), pip);
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+    ctx.rip_up_net(&mut arch, name).unwrap();
+    assert!(arch.check_wire_avail(out) && arch.check_pip_avail(pip));
+    assert!(ctx.nets[routed].wires().is_empty());
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+
+    // Rolling a transaction back takes back what it bound in the arch.
+    ctx.begin_transaction();
+    ctx.bind_bel(&mut arch, lut, lut_cell, PlaceStrength::Placer)
+        .unwrap();
+    ctx.bind_wire(&mut arch, out, routed, PlaceStrength::Placer)
+        .unwrap();
+    ctx.bind_pip(&mut arch, pip, routed, PlaceStrength::Placer)
+        .unwrap();
+    assert_eq!(
+        ctx.rollback_transaction(),
+        Err(BaseCtxError::RollbackNeedsArch)
+    );
+    assert!(ctx.in_transaction());
+    ctx.rollback_transaction_in(&mut arch).unwrap();
+    assert_eq!(arch.get_bound_bel_cell(lut), None);
+    assert!(arch.check_wire_avail(out) && arch.check_wire_avail(data));
+    assert!(arch.check_pip_avail(pip));
+    assert_eq!(ctx.cells[lut_cell].bel(), BelId::new());
+    assert!(ctx.nets[routed].wires().is_empty());
+
+    // And binds again what it unbound, here a placer move and a rip-up that fail late.
+    ctx.bind_bel(&mut arch, lut, lut_cell, PlaceStrength::Strong)
+        .unwrap();
+    ctx.bind_wire(&mut arch, out, routed, PlaceStrength::Strong)
+        .unwrap();
+    ctx.bind_pip(&mut arch, pip, routed, PlaceStrength::Strong)
+        .unwrap();
+    let before = (ctx.cells[lut_cell].clone(), ctx.nets[routed].clone());
+    let result = ctx.transaction_in(&mut arch, |ctx, arch| {
+        ctx.bind_bel(arch, ff, lut_cell, PlaceStrength::Placer)?;
+        ctx.rip_up_net(arch, name)?;
+        ctx.unbind_pip(arch, pip)
+    });
+    assert_eq!(result, Err(BaseCtxError::Arch(ArchError::NotBound)));
+    assert_eq!(arch.get_bound_bel_cell(lut), Some(lut_cell));
+    assert!(arch.check_bel_avail(ff));
+    assert_eq!(arch.get_bound_wire_net(out), Some(routed));
+    assert_eq!(arch.get_bound_pip_net(pip), Some(routed));
+    assert_eq!(
+        (ctx.cells[lut_cell].clone(), ctx.nets[routed].clone()),
+        before
+    );
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+
+    // A plain transaction that binds anyway reports it and leaves the rollback to the caller.
+    let result = ctx.transaction(|ctx| {
+        ctx.bind_bel(&mut arch, ff, lut_cell, PlaceStrength::Placer)?;
+        ctx.unbind_bel(&mut arch, lut)
+    });
+    assert_eq!(result, Err(BaseCtxError::RollbackNeedsArch));
+    assert!(ctx.in_transaction());
+    ctx.rollback_transaction_in(&mut arch).unwrap();
+    assert_eq!(arch.get_bound_bel_cell(lut), Some(lut_cell));
+    assert!(arch.check_bel_avail(ff));
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+
+    // Locking a net raises the arch's strengths along with the netlist's, a rollback lowers both.
+    ctx.begin_transaction();
+    ctx.lock_net_routing(&mut arch, name).unwrap();
+    assert_eq!(arch.get_bound_wire_strength(out), Some(PlaceStrength::User));
+    assert_eq!(
+        arch.get_bound_wire_strength(data),
+        Some(PlaceStrength::User)
+    );
+    assert_eq!(arch.get_bound_pip_strength(pip), Some(PlaceStrength::User));
+    assert_eq!(
+        ctx.nets[routed].wires()[&data].strength(),
+        PlaceStrength::User
+    );
+    ctx.rollback_transaction_in(&mut arch).unwrap();
+    assert_eq!(
+        arch.get_bound_wire_strength(out),
+        Some(PlaceStrength::Strong)
+    );
+    assert_eq!(
+        arch.get_bound_pip_strength(pip),
+        Some(PlaceStrength::Strong)
+    );
+    assert_eq!(
+        ctx.nets[routed].wires()[&data].strength(),
+        PlaceStrength::Strong
+    );
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+}
+
+#[test]
+fn generic_flow() {
+    use crate::generic::builder::{GenericArchBuilder, GenericArchError};
+    use crate::generic::flow::{self, FlowError};
+    use arch_api::ArchAPI;
+    use base_context::BaseCtx;
+    use base_types::{ArcBounds, Loc};
+    use delay::DelayQuad;
+    use id_string_list::IdStringList;
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (lut4, dff, local) = (ctx.id("LUT4"), ctx.id("DFF"), ctx.id("LOCAL"));
+    let (o, d) = (ctx.id("O"), ctx.id("D"));
+    let names: Vec<_> = ["X0/Y0/O", "X1/Y0/D", "X1/Y0/O->D", "X0/Y0/lut", "X1/Y0/ff"]
+        .iter()
+        .map(|name| IdStringList::parse(&mut ctx, name))
+        .collect();
+
+    // A LUT driving the flip-flop next to it, clustered so they are placed side by side.
+    let (lut_name, ff_name, net_name) = (ctx.id("u_lut"), ctx.id("u_ff"), ctx.id("q"));
+    let lut_cell = ctx.create_cell(lut_name, lut4).unwrap();
+    let ff_cell = ctx.create_cell(ff_name, dff).unwrap();
+    ctx.cells[lut_cell].add_output(o);
+    ctx.cells[ff_cell].add_input(d);
+    ctx.create_net(net_name).unwrap();
+    ctx.connect_port(net_name, lut_name, o).unwrap();
+    ctx.connect_port(net_name, ff_name, d).unwrap();
+
+    let mut builder = GenericArchBuilder::new("tiny");
+    let out = builder.add_wire(names[0].clone(), local, 0, 0).unwrap();
+    let data = builder.add_wire(names[1].clone(), local, 1, 0).unwrap();
+    let pip = builder
+        .add_pip(
+            names[2].clone(),
+            local,
+            out,
+            data,
+            DelayQuad::new(),
+            Loc::new(1, 0, 0),
+        )
+        .unwrap();
+    let lut = builder
+        .add_bel(names[3].clone(), lut4, Loc::new(0, 0, 0), false, false)
+        .unwrap();
+    let ff = builder
+        .add_bel(names[4].clone(), dff, Loc::new(1, 0, 0), false, false)
+        .unwrap();
+    builder.add_bel_output(lut, o, out).unwrap();
+    builder.add_bel_input(ff, d, data).unwrap();
+    let cluster = ctx.id("lut_ff");
+    builder.add_cluster(cluster, lut_cell).unwrap();
+    builder
+        .add_cluster_cell(cluster, ff_cell, Loc::new(1, 0, 0))
+        .unwrap();
+    assert_eq!(
+        builder.add_cluster(cluster, ff_cell),
+        Err(GenericArchError::DuplicateCluster(cluster))
+    );
+    assert_eq!(
+        builder.add_cluster_cell(lut4, ff_cell, Loc::new(2, 0, 0)),
+        Err(GenericArchError::CellInCluster(ff_cell))
+    );
+
+    let mut arch = builder.build(&mut ctx);
+    assert_eq!(arch.get_cluster_root_cell(cluster), Some(lut_cell));
+    assert_eq!(arch.get_cluster_bounds(cluster), ArcBounds::new(0, 0, 1, 0));
+    assert_eq!(
+        arch.get_cluster_offset(&ctx.cells[ff_cell]),
+        Loc::new(1, 0, 0)
+    );
+    assert!(arch.is_cluster_strict(&ctx.cells[ff_cell]));
+    assert_eq!(
+        arch.get_cluster_placement(cluster, lut),
+        Some(vec![(lut_cell, lut), (ff_cell, ff)])
+    );
+    assert_eq!(arch.get_cluster_placement(cluster, ff), None);
+
+    flow::run(&mut ctx, &mut arch).unwrap();
+    assert_eq!(
+        (ctx.cells[lut_cell].bel(), ctx.cells[ff_cell].bel()),
+        (lut, ff)
+    );
+    let net = ctx.get_net_index(net_name).unwrap();
+    assert_eq!(ctx.nets[net].wires()[&data].pip(), pip);
+    assert_eq!(arch.get_bound_pip_net(pip), Some(net));
+    assert!(arch.route(&mut ctx));
+
+    // Routing again picks up whatever a partial rip-up left, on either side.
+    ctx.unbind_pip(&mut arch, pip).unwrap();
+    flow::route(&mut ctx, &mut arch).unwrap();
+    assert_eq!(arch.get_bound_pip_net(pip), Some(net));
+    arch.unbind_pip(pip).unwrap();
+    flow::route(&mut ctx, &mut arch).unwrap();
+    assert_eq!(arch.get_bound_pip_net(pip), Some(net));
+    ctx.nets[net].remove_wire(data);
+    flow::route(&mut ctx, &mut arch).unwrap();
+    assert_eq!(ctx.nets[net].wires()[&data].pip(), pip);
+    assert_eq!(ctx.check_arch(&arch), vec![]);
+
+    // A cell with a type the device lacks stops the flow at packing.
+    let bram = ctx.id("BRAM");
+    let extra = ctx.id("u_bram");
+    ctx.create_cell(extra, bram).unwrap();
+    let error = flow::pack(&mut ctx, &mut arch).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Cell u_bram has a type no bel of the device has."
+    );
+    assert!(matches!(error, FlowError::NoBelType(_)));
+    assert!(!arch.pack(&mut ctx));
+}
+
+#[cfg(feature = "testarch")]
+#[test]
+fn testarch_device() {
+    use crate::testarch::device::{self, TestArchArgs};
+    use arch_api::ArchAPI;
+    use base_context::BaseCtx;
+    use base_types::Loc;
+    use std::collections::{BTreeSet, VecDeque};
+
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let arch = device::build(&mut ctx, TestArchArgs::new(4, 3));
+    let (lut4, dff, iob) = (ctx.id("LUT4"), ctx.id("DFF"), ctx.id("IOB"));
+    let (o, i, d) = (ctx.id("O"), ctx.id("I"), ctx.id("D"));
+
+    // Ten edge tiles of two IOBs around two logic tiles of two LUT4+DFF sites.
+    assert_eq!((arch.get_grid_dim_x(), arch.get_grid_dim_y()), (4, 3));
+    let types: Vec<_> = arch.get_bels().map(|bel| arch.get_bel_type(bel)).collect();
+    assert_eq!(types.iter().filter(|t| **t == iob).count(), 20);
+    assert_eq!(types.iter().filter(|t| **t == lut4).count(), 4);
+    assert_eq!(types.iter().filter(|t| **t == dff).count(), 4);
+    let lut = arch.get_bel_by_location(Loc::new(1, 1, 2)).unwrap();
+    assert_eq!(arch.get_bel_type(lut), lut4);
+    assert_eq!(arch.get_tile_bel_dim_z(2, 1), 4);
+    assert_eq!(arch.get_tile_bel_dim_z(0, 0), 2);
+    let name = arch.get_bel_name(lut);
+    assert_eq!(name.to_string(&ctx).unwrap(), "X1/Y1/lc1_lut");
+    assert_eq!(arch.get_bel_by_name(&name), Some(lut));
+
+    // A LUT output reaches an IOB on the far side of the device through the switch boxes.
+    let src = arch.get_bel_pin_wire(lut, o).unwrap();
+    let pad = arch.get_bel_by_location(Loc::new(3, 2, 1)).unwrap();
+    let dst = arch.get_bel_pin_wire(pad, i).unwrap();
+    let mut seen = BTreeSet::from([src]);
+    let mut queue = VecDeque::from([src]);
+    while let Some(wire) = queue.pop_front() {
+        for pip in arch.get_pips_downhill(wire) {
+            let next = arch.get_pip_dst_wire(pip);
+            if seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    assert!(seen.contains(&dst));
+    assert!(arch.estimate_delay(src, dst) > arch.estimate_delay(src, src));
+
+    let cell = ctx.id("u_ff");
+    ctx.create_cell(cell, dff).unwrap();
+    let ff = ctx.get_cell(cell).unwrap();
+    let (class, clocks) = arch.get_port_timing_class(ff, d);
+    assert_eq!(class, timing::TimingPortClass::RegisterInput);
+    assert_eq!(clocks, 1);
+    let ff_bel = arch.get_bel_by_location(Loc::new(1, 1, 3)).unwrap();
+    assert_eq!(
+        arch.get_bel_bucket_for_cell_type(dff),
+        arch.get_bel_bucket_for_bel(ff_bel)
+    );
+}
@@ -2,6 +2,7 @@ use ordered_float::NotNan;
 
 use crate::kernel::delay::{DelayPair, DelayQuad, DelayTrait};
 use crate::kernel::id_string::IdString;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::hash::Hash;
 
@@ -40,7 +41,7 @@ impl const PartialEq for TimingPortClass {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub enum ClockEdge {
     RisingEdge,
     FallingEdge,
@@ -85,7 +86,7 @@ where
 
 impl<D> const PartialEq for TimingClockingInfo<D>
 where
-    D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.clock_port == other.clock_port
@@ -102,7 +103,7 @@ where
 {
     pub const fn new() -> Self
     where
-        D: ~const DelayTrait,
+        D: [const] DelayTrait,
     {
         Self {
             clock_port: IdString::new(),
@@ -112,9 +113,39 @@ where
             clock_to_q: DelayQuad::new(),
         }
     }
+    pub const fn with_timing(
+        clock_port: IdString,
+        edge: ClockEdge,
+        setup: DelayPair<D>,
+        hold: DelayPair<D>,
+        clock_to_q: DelayQuad<D>,
+    ) -> Self {
+        Self {
+            clock_port,
+            edge,
+            setup,
+            hold,
+            clock_to_q,
+        }
+    }
+    pub const fn clock_port(&self) -> IdString {
+        self.clock_port
+    }
+    pub const fn edge(&self) -> ClockEdge {
+        self.edge
+    }
+    pub const fn setup(&self) -> DelayPair<D> {
+        self.setup
+    }
+    pub const fn hold(&self) -> DelayPair<D> {
+        self.hold
+    }
+    pub const fn clock_to_q(&self) -> DelayQuad<D> {
+        self.clock_to_q
+    }
 }
 
-#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ClockConstraint<D>
 where
     D: DelayTrait,
@@ -130,7 +161,7 @@ where
 {
     pub const fn new() -> Self
     where
-        D: ~const DelayTrait,
+        D: [const] DelayTrait,
     {
         Self {
             high: DelayPair::new(),
@@ -140,13 +171,13 @@ where
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ClockFmax {
     achieved: NotNan<f32>,
     constraint: NotNan<f32>,
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct ClockEvent {
     clock: IdString,
     edge: ClockEdge,
@@ -165,7 +196,7 @@ impl const PartialEq for ClockEvent {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct ClockPair {
     start: ClockEvent,
     end: ClockEvent,
@@ -184,7 +215,7 @@ impl const PartialEq for ClockPair {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct CriticalPath<D: DelayTrait> {
     // Clock pair
     clock_pair: ClockPair,
@@ -196,8 +227,20 @@ pub struct CriticalPath<D: DelayTrait> {
     segments: Vec<Segment<D>>,
 }
 
+impl<D> CriticalPath<D>
+where
+    D: DelayTrait,
+{
+    pub const fn delay(&self) -> Delay<D> {
+        self.delay
+    }
+    pub fn segments(&self) -> &[Segment<D>] {
+        &self.segments
+    }
+}
+
 /// Holds timing information of a single source to sink path of a net
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct NetSinkTiming<D: DelayTrait> {
     // Clock event pair
     clock_pair: ClockPair,
@@ -223,7 +266,7 @@ where
 
 impl<D> const PartialEq for NetSinkTiming<D>
 where
-    D: DelayTrait + ~const PartialEq,
+    D: DelayTrait + [const] PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         self.clock_pair == other.clock_pair
@@ -232,7 +275,7 @@ where
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct TimingResult<D>
 where
     D: DelayTrait,
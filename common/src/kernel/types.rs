@@ -1,12 +1,10 @@
 /// Types defined in this header use one or more user defined types (e.g. BelId).
 /// If a new common type is desired that doesn't depend on a user defined type,
 /// either put it in it's own header, or in nextpnr_base_types.h.
-// TODO: Need to figure out the cargo feature based method to
-// import the relevant arch definitions.
-// TODO: Instead of the above, implement a unified database system that can cleanly represent Xilinx's story.
-use crate::ice40::arch_defs::{BelId, DecalId, PipId};
+use crate::arch::{BelId, DecalId, PipId};
 use crate::kernel::base_types::PlaceStrength;
 use crate::kernel::id_string::IdString;
+use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -26,6 +24,18 @@ impl DecalXY {
             y: 0.0,
         }
     }
+    pub const fn with_decal(decal: DecalId, x: f64, y: f64) -> Self {
+        Self { decal, x, y }
+    }
+    pub const fn decal(&self) -> DecalId {
+        self.decal
+    }
+    pub const fn x(&self) -> f64 {
+        self.x
+    }
+    pub const fn y(&self) -> f64 {
+        self.y
+    }
 }
 
 impl const PartialEq for DecalXY {
@@ -53,7 +63,7 @@ impl const PartialEq for BelPin {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 pub struct PipMap {
     pip: PipId,
     strength: PlaceStrength,
@@ -73,6 +83,15 @@ impl PipMap {
             strength: PlaceStrength::new(),
         }
     }
+    pub const fn with_pip(pip: PipId, strength: PlaceStrength) -> Self {
+        Self { pip, strength }
+    }
+    pub const fn pip(&self) -> PipId {
+        self.pip
+    }
+    pub const fn strength(&self) -> PlaceStrength {
+        self.strength
+    }
 }
 
 impl Default for PipMap {
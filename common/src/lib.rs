@@ -11,7 +11,23 @@
 #![feature(const_try)]
 #![feature(trait_alias)]
 
+pub mod generic;
 pub mod ice40;
 pub mod kernel;
 pub mod place;
 pub mod route;
+pub mod testarch;
+
+// The architecture whose ids the kernel is built with. Every arch's definitions are always
+// compiled, the feature picks which one BaseCtx, CellInfo and NetInfo use. A build has a single
+// kernel, so exactly one of the arch features has to be enabled across the whole dependency
+// graph, and testarch is built with --no-default-features. CI checks both configurations.
+// The test arch's device needs its ids in the kernel and is only built with its feature.
+#[cfg(feature = "ice40")]
+pub use ice40::arch_defs as arch;
+#[cfg(feature = "testarch")]
+pub use testarch::arch_defs as arch;
+#[cfg(not(any(feature = "ice40", feature = "testarch")))]
+compile_error!("No architecture selected, enable the ice40 or testarch feature.");
+#[cfg(all(feature = "ice40", feature = "testarch"))]
+compile_error!("The ice40 and testarch features are mutually exclusive, enable only one of them.");
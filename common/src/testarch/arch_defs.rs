@@ -0,0 +1,91 @@
+use crate::kernel::base_clusterinfo::BaseClusterInfo;
+use crate::kernel::delay::DelayTrait;
+use crate::kernel::id_string::IdString;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// Separates the levels of a hierarchical device object name, such as `X3/Y7/lc2`.
+pub const NAME_DELIMITER: char = '/';
+
+/// The test arch has no well known strings beyond the kernel's.
+pub const CONSTIDS: &[&str] = &[];
+
+// The test arch numbers every device object densely, so all its ids are a bare index.
+macro_rules! index_id {
+    ($($(#[$doc:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Copy, Clone, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+            pub struct $name {
+                index: Option<u64>,
+            }
+
+            impl Hash for $name {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    self.index.hash(state);
+                }
+            }
+
+            impl const PartialEq for $name {
+                fn eq(&self, other: &Self) -> bool {
+                    match (self.index, other.index) {
+                        (None, None) => true,
+                        (Some(s), Some(o)) => s == o,
+                        _ => false,
+                    }
+                }
+            }
+
+            impl $name {
+                pub const fn new() -> Self {
+                    Self { index: None }
+                }
+                pub const fn with_index(index: u64) -> Self {
+                    Self { index: Some(index) }
+                }
+                pub const fn index(&self) -> Option<u64> {
+                    self.index
+                }
+            }
+
+            impl Default for $name {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        )*
+    };
+}
+
+index_id!(BelId, WireId, PipId, GroupId, DecalId);
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ArchNetInfo {}
+
+impl ArchNetInfo {
+    pub const fn new() -> Self {
+        Self {}
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ArchCellInfo<D>
+where
+    D: DelayTrait,
+{
+    base_cluster_info: BaseClusterInfo<D>,
+}
+
+impl<D> ArchCellInfo<D>
+where
+    D: DelayTrait,
+{
+    pub const fn new() -> Self {
+        Self {
+            base_cluster_info: BaseClusterInfo::new(),
+        }
+    }
+}
+
+pub type BelBucketId = IdString;
+pub type ClusterId = IdString;
@@ -0,0 +1,209 @@
+//! A small synthetic device for tests. Every tile of a `width` by `height` grid has a switch box of
+//! [`TRACKS`] track wires, joined to the same track of its four neighbours. Edge tiles hold
+//! [`IOS_PER_TILE`] IOBs, every other tile [`SITES_PER_TILE`] LUT4+DFF sites, with the LUT at
+//! z = 2 * site and its flip-flop just above. Every bel output in a tile drives every track, and
+//! every track drives every bel input, so any two pins on the device can be routed.
+use super::arch_defs::{WireId, NAME_DELIMITER};
+use crate::generic::arch::GenericArch;
+use crate::generic::builder::GenericArchBuilder;
+use crate::kernel::base_context::BaseCtx;
+use crate::kernel::base_types::Loc;
+use crate::kernel::delay::{Delay, DelayPair, DelayQuad, DelayTrait};
+use crate::kernel::id_string::IdString;
+use crate::kernel::id_string_list::IdStringList;
+
+pub const TRACKS: usize = 4;
+pub const SITES_PER_TILE: i32 = 2;
+pub const IOS_PER_TILE: i32 = 2;
+
+// Delays in picoseconds.
+const LOCAL_PIP_DELAY: i64 = 50;
+const TRACK_PIP_DELAY: i64 = 100;
+const LUT_DELAY: i64 = 300;
+const DFF_SETUP: i64 = 50;
+const DFF_CLOCK_TO_Q: i64 = 150;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TestArchArgs {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl TestArchArgs {
+    pub const fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Default for TestArchArgs {
+    fn default() -> Self {
+        Self::new(4, 4)
+    }
+}
+
+// Interns names for the builder and remembers the wires of the tile being built.
+struct TileBuilder<'a, D: DelayTrait> {
+    ctx: &'a mut BaseCtx<D>,
+    builder: GenericArchBuilder,
+    x: i32,
+    y: i32,
+    // Wires the switch box drives and wires that drive it, with their names within the tile.
+    inputs: Vec<(WireId, String)>,
+    outputs: Vec<(WireId, String)>,
+}
+
+impl<D: DelayTrait> TileBuilder<'_, D> {
+    fn name(&mut self, x: i32, y: i32, local: &str) -> IdStringList {
+        let d = NAME_DELIMITER;
+        IdStringList::parse(self.ctx, &format!("X{x}{d}Y{y}{d}{local}"))
+    }
+    fn wire(&mut self, local: &str, wire_type: &str) -> WireId {
+        let (name, wire_type) = (self.name(self.x, self.y, local), self.ctx.id(wire_type));
+        self.builder
+            .add_wire(name, wire_type, self.x, self.y)
+            .expect("Test arch wire names are unique.")
+    }
+    fn pip(&mut self, src: WireId, src_name: &str, dst: WireId, dst_name: &str, delay: i64) {
+        let name = self.name(self.x, self.y, &format!("{src_name}->{dst_name}"));
+        let pip_type = self.ctx.id("PIP");
+        let delay = DelayQuad::with_delay(Delay::with_delay(delay));
+        let loc = Loc::new(self.x, self.y, 0);
+        self.builder
+            .add_pip(name, pip_type, src, dst, delay, loc)
+            .expect("Test arch pip names are unique.");
+    }
+    /// Adds a bel at `z` in the current tile with `pins`, each on a wire of its own.
+    fn bel(&mut self, local: &str, bel_type: &str, z: i32, pins: &[(&str, bool)]) -> Vec<WireId> {
+        let name = self.name(self.x, self.y, local);
+        let bel_type = self.ctx.id(bel_type);
+        let bel = self
+            .builder
+            .add_bel(name, bel_type, Loc::new(self.x, self.y, z), false, false)
+            .expect("Test arch bels have unique names and locations.");
+        let mut wires = Vec::new();
+        for (pin, is_output) in pins {
+            let wire_name = format!("{local}_{pin}");
+            let wire = self.wire(&wire_name, "LOCAL");
+            let pin = self.ctx.id(pin);
+            if *is_output {
+                self.builder.add_bel_output(bel, pin, wire).unwrap();
+                self.outputs.push((wire, wire_name));
+            } else {
+                self.builder.add_bel_input(bel, pin, wire).unwrap();
+                self.inputs.push((wire, wire_name));
+            }
+            wires.push(wire);
+        }
+        wires
+    }
+}
+
+/// Builds the test device, interning its names in `ctx`.
+pub fn build<D: DelayTrait>(ctx: &mut BaseCtx<D>, args: TestArchArgs) -> GenericArch {
+    let TestArchArgs { width, height } = args;
+    let mut tile = TileBuilder {
+        ctx,
+        builder: GenericArchBuilder::new(&format!("testarch_{width}x{height}")),
+        x: 0,
+        y: 0,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+    };
+    let mut tracks: Vec<Vec<WireId>> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            (tile.x, tile.y) = (x, y);
+            tile.inputs.clear();
+            tile.outputs.clear();
+            let tile_tracks: Vec<WireId> = (0..TRACKS)
+                .map(|track| tile.wire(&format!("T{track}"), "TRACK"))
+                .collect();
+            if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                for z in 0..IOS_PER_TILE {
+                    tile.bel(&format!("io{z}"), "IOB", z, &[("I", false), ("O", true)]);
+                }
+            } else {
+                for site in 0..SITES_PER_TILE {
+                    let lut_pins = [
+                        ("I0", false),
+                        ("I1", false),
+                        ("I2", false),
+                        ("I3", false),
+                        ("O", true),
+                    ];
+                    let lut_name = format!("lc{site}_lut");
+                    let lut = tile.bel(&lut_name, "LUT4", 2 * site, &lut_pins);
+                    let ff_pins = [("D", false), ("CLK", false), ("Q", true)];
+                    let ff_name = format!("lc{site}_ff");
+                    let ff = tile.bel(&ff_name, "DFF", 2 * site + 1, &ff_pins);
+                    // The LUT can feed its own flip-flop without going through the switch box.
+                    tile.pip(
+                        lut[4],
+                        &format!("{lut_name}_O"),
+                        ff[0],
+                        &format!("{ff_name}_D"),
+                        LOCAL_PIP_DELAY,
+                    );
+                }
+            }
+            let (inputs, outputs) = (tile.inputs.clone(), tile.outputs.clone());
+            for (track, wire) in tile_tracks.iter().enumerate() {
+                let track_name = format!("T{track}");
+                for (output, name) in &outputs {
+                    tile.pip(*output, name, *wire, &track_name, LOCAL_PIP_DELAY);
+                }
+                for (input, name) in &inputs {
+                    tile.pip(*wire, &track_name, *input, name, LOCAL_PIP_DELAY);
+                }
+            }
+            tracks.push(tile_tracks);
+        }
+    }
+
+    // Join each track to the same track of the neighbouring tiles, the pip living in the tile it
+    // drives into.
+    for y in 0..height {
+        for x in 0..width {
+            let neighbours = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+            for (nx, ny) in neighbours {
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+                (tile.x, tile.y) = (nx, ny);
+                let (from, to) = ((y * width + x) as usize, (ny * width + nx) as usize);
+                for (track, (src, dst)) in tracks[from].iter().zip(&tracks[to]).enumerate() {
+                    let (src_name, dst_name) = (format!("X{x}Y{y}_T{track}"), format!("T{track}"));
+                    tile.pip(*src, &src_name, *dst, &dst_name, TRACK_PIP_DELAY);
+                }
+            }
+        }
+    }
+
+    let TileBuilder {
+        ctx, mut builder, ..
+    } = tile;
+    add_cell_timing(ctx, &mut builder);
+    builder.set_delay_scaling(TRACK_PIP_DELAY, 2 * LOCAL_PIP_DELAY);
+    builder.build(ctx)
+}
+
+fn add_cell_timing<D: DelayTrait>(ctx: &mut BaseCtx<D>, builder: &mut GenericArchBuilder) {
+    let [lut4, dff, o, d, clk, q]: [IdString; 6] =
+        ["LUT4", "DFF", "O", "D", "CLK", "Q"].map(|name| ctx.id(name));
+    let lut_delay = DelayQuad::with_delay(Delay::with_delay(LUT_DELAY));
+    for input in ["I0", "I1", "I2", "I3"] {
+        let input = ctx.id(input);
+        builder.add_cell_timing_delay(lut4, input, o, lut_delay);
+    }
+    builder.add_cell_timing_clock(dff, clk);
+    let (setup, hold) = (Delay::with_delay(DFF_SETUP), Delay::with_delay(0));
+    builder.add_cell_timing_setup_hold(
+        dff,
+        d,
+        clk,
+        DelayPair::with_delay(setup),
+        DelayPair::with_delay(hold),
+    );
+    let clock_to_q = DelayQuad::with_delay(Delay::with_delay(DFF_CLOCK_TO_Q));
+    builder.add_cell_timing_clock_to_out(dff, q, clk, clock_to_q);
+}
@@ -0,0 +1,5 @@
+pub mod arch_defs;
+// The device is a generic arch, which uses the kernel's ids, so it only exists when those are the
+// test arch's.
+#[cfg(feature = "testarch")]
+pub mod device;
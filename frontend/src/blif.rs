@@ -0,0 +1,471 @@
+use crate::import::import_design;
+use crate::netlist::{BitUnion, Cell, Design, Module, ModulePort, NetName};
+use crate::FrontendError;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::delay::DelayTrait;
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+// Berkeley Logic Interchange Format, including the Yosys extensions (.attr, .param, .cname, .conn).
+// https://www.cs.uic.edu/~jlillis/courses/cs594/spring05/blif.pdf
+
+/// `.names` with more inputs than this are read as a sum of products rather than a LUT, whose
+/// truth table would double in size with every input.
+const MAX_LUT_WIDTH: usize = 12;
+
+/// A cell whose connections still refer to signals rather than bits, since `.conn` can merge two
+/// signals at any point in the model.
+struct PendingCell {
+    name: Option<String>,
+    cell_type: String,
+    parameters: BTreeMap<String, Property>,
+    attributes: BTreeMap<String, Property>,
+    port_directions: BTreeMap<String, PortType>,
+    connections: BTreeMap<String, Vec<usize>>,
+}
+
+impl PendingCell {
+    fn new(cell_type: &str) -> Self {
+        Self {
+            name: None,
+            cell_type: cell_type.to_string(),
+            parameters: BTreeMap::new(),
+            attributes: BTreeMap::new(),
+            port_directions: BTreeMap::new(),
+            connections: BTreeMap::new(),
+        }
+    }
+    fn connect(&mut self, port: &str, dir: Option<PortType>, signals: Vec<usize>) {
+        if let Some(dir) = dir {
+            self.port_directions.insert(port.to_string(), dir);
+        }
+        self.connections.insert(port.to_string(), signals);
+    }
+    fn set_param(&mut self, name: &str, value: Property) {
+        self.parameters.insert(name.to_string(), value);
+    }
+}
+
+#[derive(Default)]
+struct ModuleBuilder {
+    name: String,
+    attributes: BTreeMap<String, Property>,
+    ports: Vec<(String, PortType)>,
+    signals: BTreeMap<String, usize>,
+    // Merged by `.conn`.
+    bits: BitUnion,
+    cells: Vec<PendingCell>,
+    // Cover rows of the `.names` currently being read.
+    cover: Option<Vec<(String, char)>>,
+}
+
+impl ModuleBuilder {
+    fn signal(&mut self, name: &str) -> usize {
+        if let Some(id) = self.signals.get(name) {
+            return *id;
+        }
+        let id = self.bits.new_signal();
+        self.signals.insert(name.to_string(), id);
+        id
+    }
+    fn merge(&mut self, a: &str, b: &str) {
+        let a = self.signal(a);
+        let b = self.signal(b);
+        self.bits.merge(a, b);
+    }
+
+    /// Turn the finished `.names` cover into the truth table of its LUT, LSB first.
+    fn finish_cover(&mut self, line: usize) -> Result<(), FrontendError> {
+        let Some(cover) = self.cover.take() else {
+            return Ok(());
+        };
+        let cell = self.cells.last_mut().unwrap();
+        let width = cell.connections.get("A").map_or(0, Vec::len);
+        // An empty cover is constant zero, an off-set cover (output column 0) is inverted.
+        let off_set = cover.first().map_or(false, |(_, out)| *out == '0');
+        if cover
+            .iter()
+            .any(|(cube, out)| cube.len() != width || *out != cover[0].1)
+        {
+            return Err(FrontendError::Blif(
+                line,
+                "malformed .names cover".to_string(),
+            ));
+        }
+        if width > MAX_LUT_WIDTH {
+            self.finish_sop(&cover, width, off_set);
+            return Ok(());
+        }
+        let mut init = String::with_capacity(1 << width);
+        for minterm in 0..(1usize << width) {
+            let covered = cover.iter().any(|(cube, _)| {
+                cube.chars().enumerate().all(|(i, c)| match c {
+                    '0' => minterm & (1 << i) == 0,
+                    '1' => minterm & (1 << i) != 0,
+                    _ => true,
+                })
+            });
+            init.push(if covered != off_set { '1' } else { '0' });
+        }
+        let mut init = Property::Int(State::S0, 0, init);
+        init.update_intval();
+        cell.set_param("INIT", init);
+        cell.set_param("WIDTH", Property::with_width(width as i64, 32));
+        Ok(())
+    }
+
+    /// A cover too wide for a truth table becomes a `$sop` with one term per cube, followed by a
+    /// `$_NOT_` for an off-set cover.
+    fn finish_sop(&mut self, cover: &[(String, char)], width: usize, off_set: bool) {
+        // Each input of a term takes two bits of TABLE, the low one matching 0 and the high one 1.
+        let mut table = String::with_capacity(2 * width * cover.len());
+        for (cube, _) in cover {
+            for c in cube.chars() {
+                table.push_str(match c {
+                    '0' => "10",
+                    '1' => "01",
+                    _ => "00",
+                });
+            }
+        }
+        let mut table = Property::Int(State::S0, 0, table);
+        table.update_intval();
+        let inverted = off_set.then(|| self.bits.new_signal());
+        let cell = self.cells.last_mut().unwrap();
+        cell.cell_type = "$sop".to_string();
+        cell.set_param("WIDTH", Property::with_width(width as i64, 32));
+        cell.set_param("DEPTH", Property::with_width(cover.len() as i64, 32));
+        cell.set_param("TABLE", table);
+        if let Some(inverted) = inverted {
+            let output = cell
+                .connections
+                .insert("Y".to_string(), vec![inverted])
+                .unwrap();
+            let mut not = PendingCell::new("$_NOT_");
+            not.connect("A", Some(PortType::In), vec![inverted]);
+            not.connect("Y", Some(PortType::Out), output);
+            self.cells.push(not);
+        }
+    }
+
+    fn build(self) -> Module {
+        let bit = |id: usize| self.bits.bit(id);
+        let mut module = Module {
+            attributes: self.attributes.clone(),
+            ..Default::default()
+        };
+        for (name, direction) in &self.ports {
+            module.ports.insert(
+                name.clone(),
+                ModulePort {
+                    direction: *direction,
+                    bits: vec![bit(self.signals[name])],
+                    offset: 0,
+                    upto: false,
+                },
+            );
+        }
+        for (name, id) in &self.signals {
+            module.netnames.insert(
+                name.clone(),
+                NetName {
+                    hide_name: name.starts_with('$'),
+                    bits: vec![bit(*id)],
+                    offset: 0,
+                    upto: false,
+                    attributes: BTreeMap::new(),
+                },
+            );
+        }
+        for (i, cell) in self.cells.iter().enumerate() {
+            let (name, hide_name) = match &cell.name {
+                Some(name) => (name.clone(), name.starts_with('$')),
+                None => (
+                    format!("${}${}", cell.cell_type.trim_start_matches('$'), i),
+                    true,
+                ),
+            };
+            module.cells.insert(
+                name,
+                Cell {
+                    hide_name,
+                    cell_type: cell.cell_type.clone(),
+                    parameters: cell.parameters.clone(),
+                    attributes: cell.attributes.clone(),
+                    port_directions: cell.port_directions.clone(),
+                    connections: cell
+                        .connections
+                        .iter()
+                        .map(|(port, ids)| (port.clone(), ids.iter().map(|id| bit(*id)).collect()))
+                        .collect(),
+                },
+            );
+        }
+        module
+    }
+}
+
+/// `.attr` and `.param` values are either binary strings or quoted literal strings.
+fn parse_value(value: &str) -> Property {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(strval) => Property::with_str(strval),
+        None => Property::from_string(value),
+    }
+}
+
+/// Read every `.model` of a BLIF netlist into a [`Design`], the first model being the top.
+pub fn read_blif<R: BufRead>(reader: R) -> Result<Design, FrontendError> {
+    read_blif_with_library(reader, &Design::default())
+}
+
+/// Like [`read_blif`], taking the port directions of `.subckt` and `.gate` instances from the
+/// models of the netlist itself, or failing that from the modules of `library`.
+pub fn read_blif_with_library<R: BufRead>(
+    reader: R,
+    library: &Design,
+) -> Result<Design, FrontendError> {
+    let mut design = Design::default();
+    let mut module: Option<ModuleBuilder> = None;
+    let mut first_model = true;
+    let mut pending = String::new();
+    let mut pending_line = 0;
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default();
+        if pending.is_empty() {
+            pending_line = number + 1;
+        }
+        // A trailing backslash continues the statement on the next line.
+        if let Some(continued) = line.trim_end().strip_suffix('\\') {
+            pending.push_str(continued);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(line);
+        let statement = std::mem::take(&mut pending);
+        let tokens: Vec<&str> = statement.split_whitespace().collect();
+        let line = pending_line;
+        let error = |message: &str| FrontendError::Blif(line, message.to_string());
+        let Some((&keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+
+        if !keyword.starts_with('.') {
+            // Anything that isn't a command is a cover row of the current `.names`.
+            let cover = module
+                .as_mut()
+                .and_then(|m| m.cover.as_mut())
+                .ok_or_else(|| error("cover row outside of .names"))?;
+            let row = match tokens.as_slice() {
+                [out] => (String::new(), out.chars().next().unwrap()),
+                [cube, out] => (cube.to_string(), out.chars().next().unwrap()),
+                _ => return Err(error("malformed .names cover row")),
+            };
+            cover.push(row);
+            continue;
+        }
+        if let Some(m) = module.as_mut() {
+            m.finish_cover(line)?;
+        }
+        if keyword == ".model" {
+            if module.is_some() {
+                return Err(error(".model inside another .model"));
+            }
+            let mut m = ModuleBuilder {
+                name: args
+                    .first()
+                    .ok_or_else(|| error(".model without a name"))?
+                    .to_string(),
+                ..Default::default()
+            };
+            if first_model {
+                m.attributes
+                    .insert("top".to_string(), Property::with_width(1, 32));
+                first_model = false;
+            }
+            module = Some(m);
+            continue;
+        }
+        let m = module
+            .as_mut()
+            .ok_or_else(|| error("command outside of .model"))?;
+        match keyword {
+            ".inputs" | ".outputs" | ".clock" => {
+                let dir = if keyword == ".outputs" {
+                    PortType::Out
+                } else {
+                    PortType::In
+                };
+                for name in args {
+                    m.signal(name);
+                    if !m.ports.iter().any(|(port, _)| port.as_str() == *name) {
+                        m.ports.push((name.to_string(), dir));
+                    }
+                }
+            }
+            ".names" => {
+                let (output, inputs) = args
+                    .split_last()
+                    .ok_or_else(|| error(".names without an output"))?;
+                let mut cell = PendingCell::new("$lut");
+                let inputs = inputs.iter().map(|name| m.signal(name)).collect();
+                cell.connect("A", Some(PortType::In), inputs);
+                cell.connect("Y", Some(PortType::Out), vec![m.signal(output)]);
+                m.cells.push(cell);
+                m.cover = Some(Vec::new());
+            }
+            ".latch" => {
+                let (cell_type, input, output, control, init) = match *args {
+                    [input, output] => ("$_FF_", input, output, None, None),
+                    [input, output, init] => ("$_FF_", input, output, None, Some(init)),
+                    [input, output, kind, control] | [input, output, kind, control, _] => {
+                        let cell_type = match kind {
+                            "re" => "$_DFF_P_",
+                            "fe" => "$_DFF_N_",
+                            "ah" => "$_DLATCH_P_",
+                            "al" => "$_DLATCH_N_",
+                            _ => return Err(error("unsupported .latch type")),
+                        };
+                        (
+                            cell_type,
+                            input,
+                            output,
+                            Some(control),
+                            args.get(4).copied(),
+                        )
+                    }
+                    _ => return Err(error("malformed .latch")),
+                };
+                let mut cell = PendingCell::new(cell_type);
+                cell.connect("D", Some(PortType::In), vec![m.signal(input)]);
+                cell.connect("Q", Some(PortType::Out), vec![m.signal(output)]);
+                if let Some(control) = control.filter(|c| *c != "NIL") {
+                    let port = if cell_type.starts_with("$_DFF") {
+                        "C"
+                    } else {
+                        "E"
+                    };
+                    cell.connect(port, Some(PortType::In), vec![m.signal(control)]);
+                }
+                // 2 (don't care) and 3 (unknown) leave the initial value undefined.
+                match init {
+                    Some("0") => cell.set_param("INIT", Property::with_state(State::S0)),
+                    Some("1") => cell.set_param("INIT", Property::with_state(State::S1)),
+                    _ => {}
+                }
+                m.cells.push(cell);
+            }
+            ".subckt" | ".gate" => {
+                let (cell_type, formals) = args
+                    .split_first()
+                    .ok_or_else(|| error("instance without a type"))?;
+                let mut cell = PendingCell::new(cell_type);
+                for formal in formals {
+                    let (port, actual) = formal
+                        .split_once('=')
+                        .ok_or_else(|| error("expected formal=actual"))?;
+                    let actual = m.signal(actual);
+                    cell.connect(port, None, vec![actual]);
+                }
+                m.cells.push(cell);
+            }
+            ".attr" | ".param" => {
+                let (name, value) = match args {
+                    [name, value @ ..] if !value.is_empty() => (*name, value.join(" ")),
+                    _ => return Err(error("expected a name and a value")),
+                };
+                let cell = m
+                    .cells
+                    .last_mut()
+                    .ok_or_else(|| error("no cell to attach to"))?;
+                let map = if keyword == ".attr" {
+                    &mut cell.attributes
+                } else {
+                    &mut cell.parameters
+                };
+                map.insert(name.to_string(), parse_value(&value));
+            }
+            ".cname" => {
+                let cell = m.cells.last_mut().ok_or_else(|| error("no cell to name"))?;
+                cell.name = Some(args.join(" "));
+            }
+            ".conn" => match args {
+                [from, to] => m.merge(from, to),
+                _ => return Err(error("malformed .conn")),
+            },
+            ".blackbox" => {
+                m.attributes
+                    .insert("blackbox".to_string(), Property::with_width(1, 32));
+            }
+            ".end" => {
+                let m = module.take().unwrap();
+                design.modules.insert(m.name.clone(), m.build());
+            }
+            _ => log::warn!(
+                "Ignoring unsupported BLIF command {} on line {}",
+                keyword,
+                line
+            ),
+        }
+    }
+    if let Some(mut m) = module {
+        // Tolerate a missing trailing .end
+        m.finish_cover(pending_line)?;
+        design.modules.insert(m.name.clone(), m.build());
+    }
+    resolve_port_directions(&mut design, library);
+    Ok(design)
+}
+
+/// Fill in the directions BLIF instances leave out from the box models they instantiate. Ports
+/// found in neither are left for the importer to report.
+fn resolve_port_directions(design: &mut Design, library: &Design) {
+    // Models of the netlist take precedence over the library.
+    let directions: BTreeMap<String, BTreeMap<String, PortType>> = library
+        .modules
+        .iter()
+        .chain(&design.modules)
+        .map(|(name, module)| {
+            let ports = module
+                .ports
+                .iter()
+                .map(|(port, p)| (port.clone(), p.direction));
+            (name.clone(), ports.collect())
+        })
+        .collect();
+    for cell in design
+        .modules
+        .values_mut()
+        .flat_map(|m| m.cells.values_mut())
+    {
+        let Some(ports) = directions.get(&cell.cell_type) else {
+            continue;
+        };
+        for port in cell.connections.keys() {
+            if let Some(dir) = ports.get(port) {
+                cell.port_directions.entry(port.clone()).or_insert(*dir);
+            }
+        }
+    }
+}
+
+/// Load a BLIF netlist into the context.
+pub fn parse_blif<D: DelayTrait, R: BufRead>(
+    reader: R,
+    ctx: &mut BaseCtx<D>,
+) -> Result<(), FrontendError> {
+    parse_blif_with_library(reader, &Design::default(), ctx)
+}
+
+/// Load a BLIF netlist into the context, with the port directions of its gates from `library`.
+pub fn parse_blif_with_library<D: DelayTrait, R: BufRead>(
+    reader: R,
+    library: &Design,
+    ctx: &mut BaseCtx<D>,
+) -> Result<(), FrontendError> {
+    let design = read_blif_with_library(reader, library)?;
+    import_design(ctx, &design)
+}
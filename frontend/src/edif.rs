@@ -0,0 +1,502 @@
+use crate::import::import_design;
+use crate::netlist::{Bit, BitUnion, Cell, Design, Module, ModulePort, NetName};
+use crate::FrontendError;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::delay::DelayTrait;
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+// EDIF 2.0.0 (EIA-548) netlist view, as written by vendor synthesis tools.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    List(Pos, Vec<Node>),
+    Symbol(Pos, String),
+    Str(Pos, String),
+    Int(Pos, i64),
+}
+
+fn error(pos: Pos, message: &str) -> FrontendError {
+    FrontendError::Edif(pos.line, pos.column, message.to_string())
+}
+
+impl Node {
+    const fn pos(&self) -> Pos {
+        match self {
+            Node::List(pos, _) | Node::Symbol(pos, _) | Node::Str(pos, _) | Node::Int(pos, _) => {
+                *pos
+            }
+        }
+    }
+    /// The leading keyword of a list, EDIF keywords are case insensitive.
+    fn keyword(&self) -> Option<String> {
+        match self {
+            Node::List(_, children) => match children.first() {
+                Some(Node::Symbol(_, s)) => Some(s.to_ascii_lowercase()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    fn is(&self, keyword: &str) -> bool {
+        self.keyword().as_deref() == Some(keyword)
+    }
+    /// Everything after the keyword.
+    fn args(&self) -> &[Node] {
+        match self {
+            Node::List(_, children) if !children.is_empty() => &children[1..],
+            _ => &[],
+        }
+    }
+    fn arg(&self, i: usize) -> Result<&Node, FrontendError> {
+        self.args()
+            .get(i)
+            .ok_or_else(|| error(self.pos(), "missing argument"))
+    }
+    fn find(&self, keyword: &str) -> Option<&Node> {
+        self.args().iter().find(|n| n.is(keyword))
+    }
+    fn find_all<'a>(&'a self, keyword: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        self.args().iter().filter(move |n| n.is(keyword))
+    }
+    fn int(&self) -> Result<i64, FrontendError> {
+        match self {
+            Node::Int(_, i) => Ok(*i),
+            _ => Err(error(self.pos(), "expected an integer")),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    pos: Pos,
+}
+
+impl<'a> Lexer<'a> {
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+        } else {
+            self.pos.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Parse the single top level s-expression of the file.
+    fn parse(mut self) -> Result<Node, FrontendError> {
+        let mut stack: Vec<(Pos, Vec<Node>)> = Vec::new();
+        loop {
+            let pos = self.pos;
+            let Some(c) = self.bump() else {
+                return Err(error(pos, "unexpected end of file"));
+            };
+            let node = match c {
+                c if c.is_whitespace() => continue,
+                '(' => {
+                    stack.push((pos, Vec::new()));
+                    continue;
+                }
+                ')' => {
+                    let (start, children) =
+                        stack.pop().ok_or_else(|| error(pos, "unbalanced )"))?;
+                    Node::List(start, children)
+                }
+                '"' => {
+                    let mut s = String::new();
+                    loop {
+                        match self.bump() {
+                            Some('"') => break,
+                            // %nn% escapes an ASCII code inside EDIF strings.
+                            Some('%') => {
+                                let mut code = String::new();
+                                loop {
+                                    match self.bump() {
+                                        Some('%') => break,
+                                        Some(c) => code.push(c),
+                                        None => return Err(error(pos, "unterminated escape")),
+                                    }
+                                }
+                                for value in code.split_whitespace() {
+                                    let value: u8 = value
+                                        .parse()
+                                        .map_err(|_| error(pos, "invalid string escape"))?;
+                                    s.push(value as char);
+                                }
+                            }
+                            Some(c) => s.push(c),
+                            None => return Err(error(pos, "unterminated string")),
+                        }
+                    }
+                    Node::Str(pos, s)
+                }
+                c => {
+                    let mut s = String::from(c);
+                    while let Some(&c) = self.chars.peek() {
+                        if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                            break;
+                        }
+                        s.push(c);
+                        self.bump();
+                    }
+                    match s.parse() {
+                        Ok(i) => Node::Int(pos, i),
+                        Err(_) => Node::Symbol(pos, s),
+                    }
+                }
+            };
+            match stack.last_mut() {
+                Some((_, children)) => children.push(node),
+                None => return Ok(node),
+            }
+        }
+    }
+}
+
+/// A name definition, either a plain identifier or `(rename identifier "original name")`.
+/// Identifiers that start with `&` are escaped so they can begin with a digit.
+fn name_def(node: &Node) -> Result<(String, Option<String>), FrontendError> {
+    let ident = |s: &str| s.strip_prefix('&').unwrap_or(s).to_string();
+    match node {
+        Node::Symbol(_, s) => Ok((ident(s), None)),
+        Node::List(..) if node.is("rename") => match node.arg(0)? {
+            Node::Symbol(_, s) => {
+                let original = match node.arg(1)? {
+                    Node::Str(_, original) => original.clone(),
+                    other => name_def(other)?.0,
+                };
+                Ok((ident(s), Some(original)))
+            }
+            other => Err(error(other.pos(), "expected an identifier")),
+        },
+        _ => Err(error(node.pos(), "expected a name")),
+    }
+}
+
+/// `(property NAME value)` where value is one of string, integer, boolean or number.
+fn property(node: &Node) -> Result<(String, Property), FrontendError> {
+    let (name, _) = name_def(node.arg(0)?)?;
+    let value = node.arg(1)?;
+    let property = match value.keyword().as_deref() {
+        Some("string") => match value.arg(0)? {
+            Node::Str(_, s) => Property::with_str(s),
+            other => return Err(error(other.pos(), "expected a string")),
+        },
+        Some("integer") => Property::with_width(value.arg(0)?.int()?, 32),
+        Some("boolean") => Property::with_state(if value.arg(0)?.is("true") {
+            State::S1
+        } else {
+            State::S0
+        }),
+        Some("number") => {
+            let number = value.arg(0)?;
+            match number {
+                Node::Int(_, i) => Property::with_str(&i.to_string()),
+                // Scaled integer (e mantissa exponent)
+                _ if number.is("e") => {
+                    let value =
+                        number.arg(0)?.int()? as f64 * 10f64.powi(number.arg(1)?.int()? as i32);
+                    Property::with_str(&value.to_string())
+                }
+                other => return Err(error(other.pos(), "expected a number")),
+            }
+        }
+        _ => return Err(error(value.pos(), "unsupported property type")),
+    };
+    Ok((name, property))
+}
+
+fn properties(node: &Node) -> Result<BTreeMap<String, Property>, FrontendError> {
+    node.find_all("property").map(property).collect()
+}
+
+struct InterfacePort {
+    direction: PortType,
+    // First signal of the port, widths above one use the following ids in order.
+    signals: Vec<usize>,
+}
+
+struct Instance {
+    cell_type: String,
+    parameters: BTreeMap<String, Property>,
+    // Port -> member -> signal
+    connections: BTreeMap<String, BTreeMap<usize, usize>>,
+}
+
+/// A cell view before its instances are resolved against the interfaces of the cells they use.
+struct View {
+    attributes: BTreeMap<String, Property>,
+    bits: BitUnion,
+    ports: BTreeMap<String, InterfacePort>,
+    instances: BTreeMap<String, Instance>,
+    nets: BTreeMap<String, (usize, BTreeMap<String, Property>)>,
+}
+
+impl View {
+    fn new(cell: &Node, external: bool) -> Result<Self, FrontendError> {
+        let view = cell
+            .find("view")
+            .ok_or_else(|| error(cell.pos(), "cell without a view"))?;
+        let mut this = Self {
+            attributes: properties(cell)?,
+            bits: BitUnion::default(),
+            ports: BTreeMap::new(),
+            instances: BTreeMap::new(),
+            nets: BTreeMap::new(),
+        };
+        this.attributes.extend(properties(view)?);
+        let contents = view.find("contents");
+        // Cells only described by their interface are leaf cells.
+        if external || contents.is_none() {
+            this.attributes
+                .insert("blackbox".to_string(), Property::with_width(1, 32));
+        }
+        if let Some(interface) = view.find("interface") {
+            for port in interface.find_all("port") {
+                this.add_port(port)?;
+            }
+        }
+        if let Some(contents) = contents {
+            for instance in contents.find_all("instance") {
+                this.add_instance(instance)?;
+            }
+            for net in contents.find_all("net") {
+                this.add_net(net)?;
+            }
+        }
+        Ok(this)
+    }
+
+    fn add_port(&mut self, port: &Node) -> Result<(), FrontendError> {
+        let (name, width) = match port.arg(0)? {
+            array if array.is("array") => (name_def(array.arg(0)?)?.0, array.arg(1)?.int()?),
+            name => (name_def(name)?.0, 1),
+        };
+        let direction = match port.find("direction").map(|d| d.arg(0)) {
+            Some(Ok(Node::Symbol(_, dir))) => match dir.to_ascii_lowercase().as_str() {
+                "input" => PortType::In,
+                "output" => PortType::Out,
+                "inout" => PortType::InOut,
+                _ => return Err(error(port.pos(), "invalid port direction")),
+            },
+            _ => return Err(error(port.pos(), "port without a direction")),
+        };
+        let signals = (0..width).map(|_| self.bits.new_signal()).collect();
+        self.ports
+            .insert(name, InterfacePort { direction, signals });
+        Ok(())
+    }
+
+    fn add_instance(&mut self, instance: &Node) -> Result<(), FrontendError> {
+        let (name, _) = name_def(instance.arg(0)?)?;
+        let cell_ref = instance
+            .find("viewref")
+            .and_then(|view_ref| view_ref.find("cellref"))
+            .or_else(|| instance.find("cellref"))
+            .ok_or_else(|| error(instance.pos(), "instance without a cellRef"))?;
+        let (cell_type, _) = name_def(cell_ref.arg(0)?)?;
+        self.instances.insert(
+            name,
+            Instance {
+                cell_type,
+                parameters: properties(instance)?,
+                connections: BTreeMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    fn add_net(&mut self, net: &Node) -> Result<(), FrontendError> {
+        let (name, original) = name_def(net.arg(0)?)?;
+        let signal = self.bits.new_signal();
+        for port_ref in net
+            .find("joined")
+            .into_iter()
+            .flat_map(|joined| joined.find_all("portref"))
+        {
+            let (port, member) = match port_ref.arg(0)? {
+                member if member.is("member") => {
+                    (name_def(member.arg(0)?)?.0, member.arg(1)?.int()? as usize)
+                }
+                port => (name_def(port)?.0, 0),
+            };
+            match port_ref.find("instanceref") {
+                Some(instance_ref) => {
+                    let (instance, _) = name_def(instance_ref.arg(0)?)?;
+                    let connections = &mut self
+                        .instances
+                        .get_mut(&instance)
+                        .ok_or_else(|| error(instance_ref.pos(), "unknown instance"))?
+                        .connections;
+                    let port_signals = connections.entry(port).or_default();
+                    match port_signals.get(&member) {
+                        Some(other) => self.bits.merge(signal, *other),
+                        None => {
+                            port_signals.insert(member, signal);
+                        }
+                    }
+                }
+                None => {
+                    let port_signal = self
+                        .ports
+                        .get(&port)
+                        .and_then(|p| p.signals.get(member))
+                        .ok_or_else(|| error(port_ref.pos(), "unknown port"))?;
+                    self.bits.merge(*port_signal, signal);
+                }
+            }
+        }
+        let attributes = properties(net)?;
+        if let Some(original) = original {
+            self.nets.insert(original, (signal, attributes.clone()));
+        }
+        self.nets.insert(name, (signal, attributes));
+        Ok(())
+    }
+
+    /// Array members count from the most significant bit, so member 0 of a width 8 port is bit 7.
+    fn member_bit(width: usize, member: usize) -> usize {
+        width.saturating_sub(member + 1)
+    }
+
+    fn build(&self, interfaces: &BTreeMap<String, BTreeMap<String, (PortType, usize)>>) -> Module {
+        let mut module = Module {
+            attributes: self.attributes.clone(),
+            ..Default::default()
+        };
+        for (name, port) in &self.ports {
+            let width = port.signals.len();
+            let mut bits = vec![Bit::Const(State::Sx); width];
+            for (member, signal) in port.signals.iter().enumerate() {
+                bits[Self::member_bit(width, member)] = self.bits.bit(*signal);
+            }
+            module.ports.insert(
+                name.clone(),
+                ModulePort {
+                    direction: port.direction,
+                    bits,
+                    offset: 0,
+                    upto: false,
+                },
+            );
+        }
+        for (name, (signal, attributes)) in &self.nets {
+            module.netnames.insert(
+                name.clone(),
+                NetName {
+                    hide_name: false,
+                    bits: vec![self.bits.bit(*signal)],
+                    offset: 0,
+                    upto: false,
+                    attributes: attributes.clone(),
+                },
+            );
+        }
+        for (name, instance) in &self.instances {
+            let interface = interfaces.get(&instance.cell_type);
+            let mut cell = Cell {
+                hide_name: false,
+                cell_type: instance.cell_type.clone(),
+                parameters: instance.parameters.clone(),
+                attributes: BTreeMap::new(),
+                port_directions: BTreeMap::new(),
+                connections: BTreeMap::new(),
+            };
+            for (port, members) in &instance.connections {
+                let declared = interface.and_then(|i| i.get(port));
+                let width = declared.map_or_else(
+                    || members.keys().max().map_or(1, |m| m + 1),
+                    |(_, width)| *width,
+                );
+                let mut bits = vec![Bit::Const(State::Sx); width];
+                for (member, signal) in members {
+                    if *member < width {
+                        bits[Self::member_bit(width, *member)] = self.bits.bit(*signal);
+                    }
+                }
+                if let Some((direction, _)) = declared {
+                    cell.port_directions.insert(port.clone(), *direction);
+                }
+                cell.connections.insert(port.clone(), bits);
+            }
+            module.cells.insert(name.clone(), cell);
+        }
+        module
+    }
+}
+
+/// Read an EDIF netlist into a [`Design`], with one module per cell across all libraries.
+pub fn read_edif<R: Read>(mut reader: R) -> Result<Design, FrontendError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let root = Lexer {
+        chars: input.chars().peekable(),
+        pos: Pos { line: 1, column: 1 },
+    }
+    .parse()?;
+    if !root.is("edif") {
+        return Err(error(root.pos(), "expected (edif ...)"));
+    }
+
+    let mut views: BTreeMap<String, View> = BTreeMap::new();
+    for library in root.args() {
+        let external = library.is("external");
+        if !external && !library.is("library") {
+            continue;
+        }
+        for cell in library.find_all("cell") {
+            let (name, _) = name_def(cell.arg(0)?)?;
+            views.insert(name, View::new(cell, external)?);
+        }
+    }
+
+    let interfaces: BTreeMap<String, BTreeMap<String, (PortType, usize)>> = views
+        .iter()
+        .map(|(name, view)| {
+            let ports = view
+                .ports
+                .iter()
+                .map(|(port, p)| (port.clone(), (p.direction, p.signals.len())))
+                .collect();
+            (name.clone(), ports)
+        })
+        .collect();
+    let mut design = Design::default();
+    for (name, view) in &views {
+        design.modules.insert(name.clone(), view.build(&interfaces));
+    }
+
+    if let Some(top) = root.find("design") {
+        let cell_ref = top
+            .find("cellref")
+            .ok_or_else(|| error(top.pos(), "design without a cellRef"))?;
+        let (name, _) = name_def(cell_ref.arg(0)?)?;
+        let module = design
+            .modules
+            .get_mut(&name)
+            .ok_or_else(|| error(cell_ref.pos(), "design refers to an unknown cell"))?;
+        module
+            .attributes
+            .insert("top".to_string(), Property::with_width(1, 32));
+        module.attributes.extend(properties(top)?);
+    }
+    Ok(design)
+}
+
+/// Load an EDIF netlist into the context.
+pub fn parse_edif<D: DelayTrait, R: Read>(
+    reader: R,
+    ctx: &mut BaseCtx<D>,
+) -> Result<(), FrontendError> {
+    let design = read_edif(reader)?;
+    import_design(ctx, &design)
+}
@@ -0,0 +1,111 @@
+use crate::netlist::{Bit, Cell, Design, Module, ModulePort, NetName};
+use crate::FrontendError;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::delay::DelayTrait;
+use common::kernel::id_string::IdString;
+use common::kernel::net::NetInfo;
+use common::kernel::property::{Property, State};
+use std::collections::{BTreeMap, HashMap};
+use thunderdome::Index;
+
+/// Marks the cell standing in for a top level port, so importing the netlist again reuses it
+/// rather than adding another IO buffer.
+pub const PORT_CELL_ATTRIBUTE: &str = "NEXTPNR_PORT";
+
+/// Turn the flat netlist in the context back into a design holding just the top module.
+pub fn export_design<D: DelayTrait>(ctx: &BaseCtx<D>) -> Result<Design, FrontendError> {
+    let bits: HashMap<Index<NetInfo<D>>, Bit> = ctx
+        .nets
+        .iter()
+        .enumerate()
+        .map(|(i, (index, _))| (index, Bit::Net(i as u64 + 2)))
+        .collect();
+    let bit = |net: Option<Index<NetInfo<D>>>| {
+        net.and_then(|net| bits.get(&net).copied())
+            .unwrap_or(Bit::Const(State::Sx))
+    };
+    let names = |map: &BTreeMap<IdString, Property>| -> Result<_, FrontendError> {
+        map.iter()
+            .map(|(key, value)| Ok((ctx.name_of(*key)?, value.clone())))
+            .collect::<Result<BTreeMap<_, _>, FrontendError>>()
+    };
+
+    let mut module = Module::default();
+    module
+        .attributes
+        .insert("top".to_string(), Property::with_width(1, 32));
+    for (name, port) in ctx.ports.iter() {
+        module.ports.insert(
+            ctx.name_of(*name)?,
+            ModulePort {
+                direction: port.port_type,
+                bits: vec![bit(port.net)],
+                offset: 0,
+                upto: false,
+            },
+        );
+    }
+    for (_, cell) in ctx.cells.iter() {
+        let name = ctx.name_of(cell.name())?;
+        let mut attributes = names(cell.attributes())?;
+        if let Some((port, _)) = ctx
+            .port_cells
+            .iter()
+            .find(|(_, index)| Some(**index) == cell.self_index())
+        {
+            attributes.insert(
+                PORT_CELL_ATTRIBUTE.to_string(),
+                Property::with_str(&ctx.name_of(*port)?),
+            );
+        }
+        let mut exported = Cell {
+            hide_name: name.starts_with('$'),
+            cell_type: ctx.name_of(cell.cell_type())?,
+            parameters: names(cell.parameters())?,
+            attributes,
+            port_directions: BTreeMap::new(),
+            connections: BTreeMap::new(),
+        };
+        for (port_name, port) in cell.ports() {
+            let port_name = ctx.name_of(*port_name)?;
+            exported
+                .port_directions
+                .insert(port_name.clone(), port.port_type);
+            exported.connections.insert(port_name, vec![bit(port.net)]);
+        }
+        module.cells.insert(name, exported);
+    }
+    for (index, net) in ctx.nets.iter() {
+        let name = ctx.name_of(net.name())?;
+        let bits = vec![bits[&index]];
+        for alias in net.aliases() {
+            let alias = ctx.name_of(*alias)?;
+            if alias != name {
+                module.netnames.insert(
+                    alias.clone(),
+                    NetName {
+                        hide_name: alias.starts_with('$'),
+                        bits: bits.clone(),
+                        offset: 0,
+                        upto: false,
+                        attributes: BTreeMap::new(),
+                    },
+                );
+            }
+        }
+        module.netnames.insert(
+            name.clone(),
+            NetName {
+                hide_name: name.starts_with('$'),
+                bits,
+                offset: 0,
+                upto: false,
+                attributes: names(net.attributes())?,
+            },
+        );
+    }
+
+    let mut design = Design::default();
+    design.modules.insert(ctx.name_of(ctx.top_module)?, module);
+    Ok(design)
+}
@@ -0,0 +1,475 @@
+use crate::export::PORT_CELL_ATTRIBUTE;
+use crate::netlist::{bit_name, Bit, Cell, Design, Module};
+use crate::{FrontendError, ModuleInfo};
+use common::kernel::base_context::{BaseCtx, BaseCtxError};
+use common::kernel::cell::HierarchicalCell;
+use common::kernel::constids::{
+    ID_GND, ID_I, ID_NEXTPNR_IBUF, ID_NEXTPNR_IOBUF, ID_NEXTPNR_OBUF, ID_NEXTPNR_PORT, ID_O,
+    ID_PACKER_GND_DRV, ID_PACKER_GND_NET, ID_PACKER_VCC_DRV, ID_PACKER_VCC_NET, ID_VCC, ID_Y,
+};
+use common::kernel::delay::DelayTrait;
+use common::kernel::id_string::IdString;
+use common::kernel::net::NetInfo;
+use common::kernel::port::{HierarchicalPort, PortInfo, PortType};
+use common::kernel::property::{Property, State};
+use std::collections::BTreeMap;
+use thunderdome::Index;
+
+/// Import a parsed design into the context, flattening everything below its top module.
+/// Blackbox and whitebox modules, and cell types without a module, are kept as leaf cells.
+pub fn import_design<D: DelayTrait>(
+    ctx: &mut BaseCtx<D>,
+    design: &Design,
+) -> Result<(), FrontendError> {
+    let modules: BTreeMap<&str, ModuleInfo> = design
+        .modules
+        .iter()
+        .map(|(name, module)| (name.as_str(), ModuleInfo::with_module(module)))
+        .collect();
+    let top = find_top(ctx, design, &modules)?;
+    let top_id = ctx.id(top);
+    ctx.top_module = top_id;
+    ctx.hierarchy.insert(
+        top_id,
+        HierarchicalCell::new(top_id, top_id, IdString::new(), top_id),
+    );
+    ModuleImporter::new(&design.modules[top], String::new(), top_id, vec![top])
+        .import(ctx, design, &modules)?;
+    ctx.debug_check("import");
+    Ok(())
+}
+
+/// The top module is, in order of preference, the `frontend/top` setting, the module carrying a
+/// `top` attribute, or the only non-box module which no other module instantiates.
+fn find_top<'d, D: DelayTrait>(
+    ctx: &BaseCtx<D>,
+    design: &'d Design,
+    modules: &BTreeMap<&'d str, ModuleInfo>,
+) -> Result<&'d str, FrontendError> {
+    let top = ctx.get_str("frontend/top")?;
+    if !top.is_empty() {
+        return design
+            .modules
+            .get_key_value(&top)
+            .map(|(name, _)| name.as_str())
+            .ok_or(FrontendError::ModuleNotFound(top));
+    }
+    let tops: Vec<&str> = modules
+        .iter()
+        .filter(|(_, info)| info.is_top)
+        .map(|(name, _)| *name)
+        .collect();
+    let candidates: Vec<&str> = if tops.is_empty() {
+        modules
+            .iter()
+            .filter(|(name, info)| {
+                !info.is_box()
+                    && !modules
+                        .values()
+                        .any(|other| other.instantiated_celltypes.contains(**name))
+            })
+            .map(|(name, _)| *name)
+            .collect()
+    } else {
+        tops
+    };
+    match candidates.as_slice() {
+        [top] => Ok(*top),
+        [] => Err(FrontendError::NoTopModule),
+        _ => Err(FrontendError::MultipleTopModules),
+    }
+}
+
+struct ModuleImporter<'a, D>
+where
+    D: DelayTrait,
+{
+    module: &'a Module,
+    // Prepended to every cell and net name, empty for the top module and `path.` below it.
+    prefix: String,
+    // Key of this instance in BaseCtx::hierarchy.
+    path: IdString,
+    // Net names for each numbered bit, visible names sorted ahead of hidden ones.
+    bit_names: BTreeMap<u64, Vec<(bool, String, &'a BTreeMap<String, Property>)>>,
+    // By name rather than index, so a bit keeps its net when a pass-through submodule merges
+    // that net into another one.
+    bit_nets: BTreeMap<u64, IdString>,
+    // Module types from the top down to this one, to catch modules instantiating themselves.
+    ancestors: Vec<&'a str>,
+}
+
+impl<'a, D> ModuleImporter<'a, D>
+where
+    D: DelayTrait,
+{
+    fn new(module: &'a Module, prefix: String, path: IdString, ancestors: Vec<&'a str>) -> Self {
+        let mut bit_names: BTreeMap<u64, Vec<_>> = BTreeMap::new();
+        for (name, netname) in &module.netnames {
+            let width = netname.bits.len();
+            for (i, bit) in netname.bits.iter().enumerate() {
+                if let Bit::Net(n) = bit {
+                    bit_names.entry(*n).or_default().push((
+                        netname.hide_name,
+                        bit_name(name, i, width, netname.offset, netname.upto),
+                        &netname.attributes,
+                    ));
+                }
+            }
+        }
+        for names in bit_names.values_mut() {
+            // Stable, so among equally visible names the netnames order is kept.
+            names.sort_by_key(|(hidden, _, _)| *hidden);
+        }
+        Self {
+            module,
+            prefix,
+            path,
+            bit_names,
+            bit_nets: BTreeMap::new(),
+            ancestors,
+        }
+    }
+
+    fn is_top(&self) -> bool {
+        self.prefix.is_empty()
+    }
+
+    fn import(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        design: &'a Design,
+        modules: &BTreeMap<&str, ModuleInfo>,
+    ) -> Result<(), FrontendError> {
+        let module = self.module;
+        // Port cells kept from a netlist we wrote out ourselves, by port name.
+        let port_cells: BTreeMap<&str, &str> = module
+            .cells
+            .iter()
+            .filter_map(
+                |(name, cell)| match cell.attributes.get(PORT_CELL_ATTRIBUTE) {
+                    Some(Property::Str(_, port)) => Some((port.as_str(), name.as_str())),
+                    _ => None,
+                },
+            )
+            .collect();
+        for (name, cell) in &module.cells {
+            match design.modules.get(&cell.cell_type) {
+                Some(submodule) if !modules[cell.cell_type.as_str()].is_box() => {
+                    self.import_submodule(ctx, design, modules, name, cell, submodule)?
+                }
+                submodule => self.import_leaf_cell(ctx, name, cell, submodule)?,
+            }
+        }
+        // Top ports go last, like nextpnr's json frontend, so an inout only drives its net
+        // when nothing inside the design does.
+        let mut existing_ports = Vec::new();
+        if self.is_top() {
+            for (name, port) in &module.ports {
+                let width = port.bits.len();
+                for (i, bit) in port.bits.iter().enumerate() {
+                    let name = bit_name(name, i, width, port.offset, port.upto);
+                    match port_cells.get(name.as_str()) {
+                        Some(cell) => existing_ports.push((name, *cell, port.direction, *bit)),
+                        None => self.import_top_port(ctx, &name, port.direction, *bit)?,
+                    }
+                }
+            }
+        }
+        for (name, cell, dir, bit) in existing_ports {
+            let net = self.net_for_bit(ctx, bit)?;
+            let name = ctx.id(&name);
+            let cell = ctx.id(cell);
+            let index = ctx.get_cell_index(cell).ok_or(BaseCtxError::NameNotFound)?;
+            ctx.cells[index].unset_attribute(ID_NEXTPNR_PORT);
+            ctx.ports.insert(
+                name,
+                PortInfo {
+                    name,
+                    net,
+                    port_type: dir,
+                    user_index: None,
+                },
+            );
+            ctx.port_cells.insert(name, index);
+        }
+        Ok(())
+    }
+
+    fn import_leaf_cell(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        name: &str,
+        cell: &Cell,
+        box_module: Option<&Module>,
+    ) -> Result<(), FrontendError> {
+        let local_name = ctx.id(name);
+        let name = ctx.id(&format!("{}{}", self.prefix, name));
+        let cell_type = ctx.id(&cell.cell_type);
+        let index = ctx.create_cell(name, cell_type)?;
+        ctx.cells[index].set_hierarchy_path(self.path);
+        if let Some(hierarchy) = ctx.hierarchy.get_mut(&self.path) {
+            hierarchy.add_leaf_cell(local_name, name);
+        }
+        for (key, value) in &cell.parameters {
+            let key = ctx.id(key);
+            ctx.cells[index].set_param(key, value.clone());
+        }
+        for (key, value) in &cell.attributes {
+            let key = ctx.id(key);
+            ctx.cells[index].set_attribute(key, value.clone());
+        }
+        for (port, bits) in &cell.connections {
+            // Fall back on the box module's own port list for cells without port_directions.
+            let dir = cell
+                .port_directions
+                .get(port)
+                .or_else(|| box_module?.ports.get(port).map(|p| &p.direction))
+                .copied()
+                .ok_or_else(|| {
+                    FrontendError::MissingPortDirection(
+                        ctx.name_of(name).unwrap_or_default(),
+                        port.clone(),
+                    )
+                })?;
+            let width = bits.len();
+            for (i, bit) in bits.iter().enumerate() {
+                let port_name = ctx.id(&bit_name(port, i, width, 0, false));
+                let net = self.net_for_bit(ctx, *bit)?;
+                ctx.cells[index].add_port(port_name, dir);
+                if let Some(net) = net {
+                    ctx.cells[index]
+                        .connect_port(port_name, net, &mut ctx.nets)
+                        .map_err(|e| ctx.cell_error(index, e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Flatten an instance of a non-box module into the context, tying the nets on the outside
+    /// of the instance to its port bits on the inside.
+    fn import_submodule(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        design: &'a Design,
+        modules: &BTreeMap<&str, ModuleInfo>,
+        name: &str,
+        cell: &Cell,
+        submodule: &'a Module,
+    ) -> Result<(), FrontendError> {
+        if self.ancestors.contains(&cell.cell_type.as_str()) {
+            return Err(FrontendError::RecursiveInstance(cell.cell_type.clone()));
+        }
+        let local_name = ctx.id(name);
+        let fullpath = format!("{}{}", self.prefix, name);
+        let path = ctx.id(&fullpath);
+        let cell_type = ctx.id(&cell.cell_type);
+        ctx.hierarchy.insert(
+            path,
+            HierarchicalCell::new(local_name, cell_type, self.path, path),
+        );
+        if let Some(parent) = ctx.hierarchy.get_mut(&self.path) {
+            parent.add_hierarchical_cell(local_name, path);
+        }
+
+        let mut ancestors = self.ancestors.clone();
+        ancestors.push(&cell.cell_type);
+        let mut inner = ModuleImporter::new(submodule, format!("{}.", fullpath), path, ancestors);
+        for (port_name, port) in &submodule.ports {
+            let outer_bits = cell.connections.get(port_name);
+            let mut nets = Vec::with_capacity(port.bits.len());
+            for (i, bit) in port.bits.iter().enumerate() {
+                let net = match outer_bits.and_then(|bits| bits.get(i)) {
+                    Some(outer) => self.net_for_bit(ctx, *outer)?,
+                    None => None,
+                };
+                // Bits left unconnected outside keep a net of their own inside, named
+                // `<inst>.<net>`, so whatever drives and uses them stays connected.
+                let net = match (bit, net) {
+                    (Bit::Net(n), Some(net)) => Some(inner.adopt_net(ctx, *n, net)?),
+                    (Bit::Net(_), None) => inner.net_for_bit(ctx, *bit)?,
+                    _ => net,
+                };
+                nets.push(net.map_or(IdString::new(), |net| ctx.nets[net].name()));
+            }
+            let port_name = ctx.id(port_name);
+            ctx.hierarchy
+                .get_mut(&path)
+                .unwrap()
+                .add_port(HierarchicalPort::new(
+                    port_name,
+                    port.direction,
+                    nets,
+                    port.offset,
+                    port.upto,
+                ));
+        }
+        inner.import(ctx, design, modules)
+    }
+
+    /// Use a net from the enclosing module for bit `n`, its local names becoming aliases. If the
+    /// bit already has one, because it is wired straight from one port to another, the two
+    /// outer nets are merged. Returns the net the bit ends up on.
+    fn adopt_net(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        n: u64,
+        net: Index<NetInfo<D>>,
+    ) -> Result<Index<NetInfo<D>>, FrontendError> {
+        let global_name = ctx.nets[net].name();
+        if let Some(existing) = self.bit_nets.get(&n).copied() {
+            ctx.merge_nets(existing, global_name)?;
+            let net = ctx
+                .get_net_index(existing)
+                .ok_or(BaseCtxError::NameNotFound)?;
+            return Ok(net);
+        }
+        self.bit_nets.insert(n, global_name);
+        for (_, name, _) in self.bit_names.get(&n).into_iter().flatten() {
+            let local_name = ctx.id(name);
+            let alias = ctx.id(&format!("{}{}", self.prefix, name));
+            ctx.add_net_alias(alias, net)?;
+            if let Some(hierarchy) = ctx.hierarchy.get_mut(&self.path) {
+                hierarchy.add_net(local_name, global_name);
+            }
+        }
+        Ok(net)
+    }
+
+    /// Find or create the net for a bit. High impedance bits are left unconnected, and so are
+    /// undefined ones unless the `frontend/x_tieoff` setting ties them to 0 or 1.
+    fn net_for_bit(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        bit: Bit,
+    ) -> Result<Option<Index<NetInfo<D>>>, FrontendError> {
+        match bit {
+            Bit::Net(n) => {
+                if let Some(name) = self.bit_nets.get(&n) {
+                    let net = ctx.get_net_index(*name).ok_or(BaseCtxError::NameNotFound)?;
+                    return Ok(Some(net));
+                }
+                let net = match self.bit_names.get(&n).map(Vec::as_slice) {
+                    Some([(_, name, attributes), aliases @ ..]) => {
+                        let local_name = ctx.id(name);
+                        let global_name = ctx.id(&format!("{}{}", self.prefix, name));
+                        let net = ctx.create_net(global_name)?;
+                        for (key, value) in attributes.iter() {
+                            let key = ctx.id(key);
+                            ctx.nets[net].set_attribute(key, value.clone());
+                        }
+                        let mut local_names = vec![local_name];
+                        for (_, alias, _) in aliases {
+                            local_names.push(ctx.id(alias));
+                            let alias = ctx.id(&format!("{}{}", self.prefix, alias));
+                            ctx.add_net_alias(alias, net)?;
+                        }
+                        if let Some(hierarchy) = ctx.hierarchy.get_mut(&self.path) {
+                            for local_name in local_names {
+                                hierarchy.add_net(local_name, global_name);
+                            }
+                        }
+                        net
+                    }
+                    _ => {
+                        let name = ctx.id(&format!("{}$frontend${}", self.prefix, n));
+                        ctx.create_net(name)?
+                    }
+                };
+                ctx.nets[net].set_hierarchy_path(self.path);
+                self.bit_nets.insert(n, ctx.nets[net].name());
+                Ok(Some(net))
+            }
+            Bit::Const(State::Sx) => match x_tieoff(ctx)? {
+                Some(state) => Ok(Some(Self::constant_net(ctx, state)?)),
+                None => Ok(None),
+            },
+            Bit::Const(State::Sz) => Ok(None),
+            Bit::Const(state) => Ok(Some(Self::constant_net(ctx, state)?)),
+        }
+    }
+
+    /// The shared net for a constant, driven by the Y output of a GND or VCC cell.
+    fn constant_net(
+        ctx: &mut BaseCtx<D>,
+        state: State,
+    ) -> Result<Index<NetInfo<D>>, FrontendError> {
+        let (net_name, cell_name, cell_type) = match state {
+            State::S1 => (ID_PACKER_VCC_NET, ID_PACKER_VCC_DRV, ID_VCC),
+            _ => (ID_PACKER_GND_NET, ID_PACKER_GND_DRV, ID_GND),
+        };
+        if let Some(net) = ctx.get_net_index(net_name) {
+            return Ok(net);
+        }
+        let net = ctx.create_net(net_name)?;
+        let cell = ctx.create_cell(cell_name, cell_type)?;
+        let top = ctx.top_module;
+        ctx.nets[net].set_hierarchy_path(top);
+        ctx.cells[cell].set_hierarchy_path(top);
+        ctx.cells[cell].add_output(ID_Y);
+        ctx.cells[cell]
+            .connect_port(ID_Y, net, &mut ctx.nets)
+            .map_err(|e| ctx.cell_error(cell, e))?;
+        Ok(net)
+    }
+
+    /// Top level ports become IO buffer cells named after the port bit, which the packer later
+    /// replaces with the real IO cells.
+    fn import_top_port(
+        &mut self,
+        ctx: &mut BaseCtx<D>,
+        name: &str,
+        dir: PortType,
+        bit: Bit,
+    ) -> Result<(), FrontendError> {
+        let net = self.net_for_bit(ctx, bit)?;
+        let name = ctx.id(name);
+        let (cell_type, port_name) = match dir {
+            PortType::In => (ID_NEXTPNR_IBUF, ID_O),
+            PortType::Out => (ID_NEXTPNR_OBUF, ID_I),
+            PortType::InOut => (ID_NEXTPNR_IOBUF, ID_I),
+        };
+        let output_name = ID_O;
+        let index = ctx.create_cell(name, cell_type)?;
+        let cell = &mut ctx.cells[index];
+        cell.set_hierarchy_path(self.path);
+        match dir {
+            PortType::In => cell.add_output(port_name),
+            PortType::Out => cell.add_input(port_name),
+            PortType::InOut => {
+                cell.add_input(port_name);
+                cell.add_output(output_name);
+            }
+        }
+        if let Some(net) = net {
+            // An inout bit drives the net through O unless something inside already does.
+            let connect_name = match dir {
+                PortType::InOut if ctx.nets[net].driver.cell.is_none() => output_name,
+                _ => port_name,
+            };
+            ctx.cells[index]
+                .connect_port(connect_name, net, &mut ctx.nets)
+                .map_err(|e| ctx.cell_error(index, e))?;
+        }
+        ctx.ports.insert(
+            name,
+            PortInfo {
+                name,
+                net,
+                port_type: dir,
+                user_index: None,
+            },
+        );
+        ctx.port_cells.insert(name, index);
+        Ok(())
+    }
+}
+
+/// The constant undefined bits are tied to, from the `frontend/x_tieoff` setting.
+fn x_tieoff<D: DelayTrait>(ctx: &BaseCtx<D>) -> Result<Option<State>, FrontendError> {
+    Ok(match ctx.get_str("frontend/x_tieoff")?.as_str() {
+        "0" => Some(State::S0),
+        "1" => Some(State::S1),
+        _ => None,
+    })
+}
@@ -1,3 +1,333 @@
-pub fn parse_json() -> bool {
-    todo!();
+use crate::export::export_design;
+use crate::import::import_design;
+use crate::netlist::{Bit, Cell, Design, Module, ModulePort, NetName};
+use crate::FrontendError;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::delay::DelayTrait;
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+// Serde mirror of the Yosys `write_json` format.
+// https://yosyshq.readthedocs.io/projects/yosys/en/latest/cmd/write_json.html
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonDesign {
+    #[serde(default)]
+    creator: String,
+    #[serde(default)]
+    modules: BTreeMap<String, JsonModule>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonModule {
+    #[serde(default)]
+    attributes: BTreeMap<String, JsonValue>,
+    #[serde(default)]
+    parameter_default_values: BTreeMap<String, JsonValue>,
+    #[serde(default)]
+    ports: BTreeMap<String, JsonPort>,
+    #[serde(default)]
+    cells: BTreeMap<String, JsonCell>,
+    #[serde(default)]
+    netnames: BTreeMap<String, JsonNetName>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonPort {
+    direction: JsonDirection,
+    bits: Vec<JsonBit>,
+    #[serde(default)]
+    offset: i32,
+    #[serde(default)]
+    upto: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonCell {
+    #[serde(default)]
+    hide_name: i32,
+    #[serde(rename = "type")]
+    cell_type: String,
+    #[serde(default)]
+    parameters: BTreeMap<String, JsonValue>,
+    #[serde(default)]
+    attributes: BTreeMap<String, JsonValue>,
+    #[serde(default)]
+    port_directions: BTreeMap<String, JsonDirection>,
+    #[serde(default)]
+    connections: BTreeMap<String, Vec<JsonBit>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonNetName {
+    #[serde(default)]
+    hide_name: i32,
+    bits: Vec<JsonBit>,
+    #[serde(default)]
+    offset: i32,
+    #[serde(default)]
+    upto: i32,
+    #[serde(default)]
+    attributes: BTreeMap<String, JsonValue>,
+}
+
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum JsonDirection {
+    Input,
+    Output,
+    Inout,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum JsonBit {
+    Net(u64),
+    Const(String),
+}
+
+/// Parameters and attributes are either plain integers or (binary) strings.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+enum JsonValue {
+    Int(i64),
+    Str(String),
+}
+
+impl From<JsonDirection> for PortType {
+    fn from(value: JsonDirection) -> Self {
+        match value {
+            JsonDirection::Input => PortType::In,
+            JsonDirection::Output => PortType::Out,
+            JsonDirection::Inout => PortType::InOut,
+        }
+    }
+}
+
+impl From<PortType> for JsonDirection {
+    fn from(value: PortType) -> Self {
+        match value {
+            PortType::In => JsonDirection::Input,
+            PortType::Out => JsonDirection::Output,
+            PortType::InOut => JsonDirection::Inout,
+        }
+    }
+}
+
+impl From<JsonValue> for Property {
+    fn from(value: JsonValue) -> Self {
+        match value {
+            JsonValue::Int(intval) => Property::with_width(intval, 32),
+            JsonValue::Str(strval) => Property::from_string(&strval),
+        }
+    }
+}
+
+impl TryFrom<JsonBit> for Bit {
+    type Error = FrontendError;
+
+    fn try_from(value: JsonBit) -> Result<Self, Self::Error> {
+        match value {
+            JsonBit::Net(index) => Ok(Bit::Net(index)),
+            JsonBit::Const(s) => {
+                let mut chars = s.chars();
+                match (State::try_from(chars.next()), chars.next()) {
+                    (Ok(state), None) => Ok(Bit::Const(state)),
+                    _ => Err(FrontendError::InvalidBit(s)),
+                }
+            }
+        }
+    }
+}
+
+impl From<Bit> for JsonBit {
+    fn from(value: Bit) -> Self {
+        match value {
+            Bit::Net(index) => JsonBit::Net(index),
+            Bit::Const(state) => JsonBit::Const(state.to_char().to_string()),
+        }
+    }
+}
+
+fn convert_bits(bits: Vec<JsonBit>) -> Result<Vec<Bit>, FrontendError> {
+    bits.into_iter().map(Bit::try_from).collect()
+}
+
+fn convert_values(values: BTreeMap<String, JsonValue>) -> BTreeMap<String, Property> {
+    values.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+/// Written the way Yosys does, as binary strings or strings escaped by `Property`'s `Display`.
+fn export_values(values: &BTreeMap<String, Property>) -> BTreeMap<String, JsonValue> {
+    values
+        .iter()
+        .map(|(k, v)| (k.clone(), JsonValue::Str(v.to_string())))
+        .collect()
+}
+
+fn export_bits(bits: &[Bit]) -> Vec<JsonBit> {
+    bits.iter().map(|bit| (*bit).into()).collect()
+}
+
+impl From<&Module> for JsonModule {
+    fn from(value: &Module) -> Self {
+        Self {
+            attributes: export_values(&value.attributes),
+            parameter_default_values: export_values(&value.parameter_default_values),
+            ports: value
+                .ports
+                .iter()
+                .map(|(name, port)| {
+                    let port = JsonPort {
+                        direction: port.direction.into(),
+                        bits: export_bits(&port.bits),
+                        offset: port.offset,
+                        upto: port.upto.into(),
+                    };
+                    (name.clone(), port)
+                })
+                .collect(),
+            cells: value
+                .cells
+                .iter()
+                .map(|(name, cell)| {
+                    let cell = JsonCell {
+                        hide_name: cell.hide_name.into(),
+                        cell_type: cell.cell_type.clone(),
+                        parameters: export_values(&cell.parameters),
+                        attributes: export_values(&cell.attributes),
+                        port_directions: cell
+                            .port_directions
+                            .iter()
+                            .map(|(k, v)| (k.clone(), (*v).into()))
+                            .collect(),
+                        connections: cell
+                            .connections
+                            .iter()
+                            .map(|(k, v)| (k.clone(), export_bits(v)))
+                            .collect(),
+                    };
+                    (name.clone(), cell)
+                })
+                .collect(),
+            netnames: value
+                .netnames
+                .iter()
+                .map(|(name, netname)| {
+                    let netname = JsonNetName {
+                        hide_name: netname.hide_name.into(),
+                        bits: export_bits(&netname.bits),
+                        offset: netname.offset,
+                        upto: netname.upto.into(),
+                        attributes: export_values(&netname.attributes),
+                    };
+                    (name.clone(), netname)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<JsonModule> for Module {
+    type Error = FrontendError;
+
+    fn try_from(value: JsonModule) -> Result<Self, Self::Error> {
+        let mut module = Module {
+            attributes: convert_values(value.attributes),
+            parameter_default_values: convert_values(value.parameter_default_values),
+            ..Default::default()
+        };
+        for (name, port) in value.ports {
+            module.ports.insert(
+                name,
+                ModulePort {
+                    direction: port.direction.into(),
+                    bits: convert_bits(port.bits)?,
+                    offset: port.offset,
+                    upto: port.upto != 0,
+                },
+            );
+        }
+        for (name, cell) in value.cells {
+            module.cells.insert(
+                name,
+                Cell {
+                    hide_name: cell.hide_name != 0,
+                    cell_type: cell.cell_type,
+                    parameters: convert_values(cell.parameters),
+                    attributes: convert_values(cell.attributes),
+                    port_directions: cell
+                        .port_directions
+                        .into_iter()
+                        .map(|(k, v)| (k, v.into()))
+                        .collect(),
+                    connections: cell
+                        .connections
+                        .into_iter()
+                        .map(|(k, v)| Ok((k, convert_bits(v)?)))
+                        .collect::<Result<_, FrontendError>>()?,
+                },
+            );
+        }
+        for (name, netname) in value.netnames {
+            module.netnames.insert(
+                name,
+                NetName {
+                    hide_name: netname.hide_name != 0,
+                    bits: convert_bits(netname.bits)?,
+                    offset: netname.offset,
+                    upto: netname.upto != 0,
+                    attributes: convert_values(netname.attributes),
+                },
+            );
+        }
+        Ok(module)
+    }
+}
+
+/// Read a Yosys JSON netlist into a [`Design`] without touching any context.
+pub fn read_json<R: Read>(reader: R) -> Result<Design, FrontendError> {
+    let json: JsonDesign = serde_json::from_reader(reader)?;
+    let mut design = Design::default();
+    for (name, module) in json.modules {
+        design.modules.insert(name, module.try_into()?);
+    }
+    Ok(design)
+}
+
+/// Load a Yosys `write_json` netlist into the context.
+pub fn parse_json<D: DelayTrait, R: Read>(
+    reader: R,
+    ctx: &mut BaseCtx<D>,
+) -> Result<(), FrontendError> {
+    let design = read_json(reader)?;
+    import_design(ctx, &design)?;
+    Ok(ctx.attributes_to_arch_info()?)
+}
+
+/// Write a [`Design`] in the Yosys JSON format.
+pub fn write_design_json<W: Write>(design: &Design, writer: W) -> Result<(), FrontendError> {
+    let json = JsonDesign {
+        creator: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")).to_string(),
+        modules: design
+            .modules
+            .iter()
+            .map(|(name, module)| (name.clone(), module.into()))
+            .collect(),
+    };
+    serde_json::to_writer_pretty(writer, &json)?;
+    Ok(())
+}
+
+/// Write the context's netlist as Yosys JSON, including placement (`BEL`,
+/// `BEL_STRENGTH`) and routing (`ROUTING`) so [`parse_json`] can pick up where this run stopped.
+pub fn write_json<D: DelayTrait, W: Write>(
+    ctx: &mut BaseCtx<D>,
+    writer: W,
+) -> Result<(), FrontendError> {
+    ctx.arch_info_to_attributes();
+    write_design_json(&export_design(ctx)?, writer)
 }
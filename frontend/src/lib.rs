@@ -1,37 +1,91 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+use common::kernel::base_context::BaseCtxError;
+use common::kernel::cell::CellError;
+use common::kernel::settings::SettingsError;
+use netlist::Module;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+pub mod blif;
+pub mod edif;
+pub mod export;
+pub mod import;
+pub mod json;
+pub mod netlist;
+pub mod verilog;
 
-//#[cfg(test)]
-//mod tests {
-//    use super::*;
-//
-//    #[test]
-//    fn it_works() {
-//        let result = add(2, 2);
-//        assert_eq!(result, 4);
-//    }
-//}
+#[cfg(test)]
+mod tests;
+
+#[derive(Error, Debug)]
+pub enum FrontendError {
+    #[error("Failed to read netlist: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse JSON netlist: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("BLIF parse error on line {0}: {1}")]
+    Blif(usize, String),
+    #[error("EDIF parse error at {0}:{1}: {2}")]
+    Edif(usize, usize, String),
+    #[error("Verilog parse error on line {0}: {1}")]
+    Verilog(usize, String),
+    #[error("Invalid netlist bit {0:?}, expected a net number or one of 0, 1, x, z.")]
+    InvalidBit(String),
+    #[error("No top module found, mark one with the top attribute.")]
+    NoTopModule,
+    #[error("More than one module could be the top, pick one with the top attribute.")]
+    MultipleTopModules,
+    #[error("Module {0} not found in the netlist.")]
+    ModuleNotFound(String),
+    #[error("Module {0} instantiates itself.")]
+    RecursiveInstance(String),
+    #[error("Port {1} on cell {0} has no direction.")]
+    MissingPortDirection(String, String),
+    #[error(transparent)]
+    Context(#[from] BaseCtxError),
+    #[error(transparent)]
+    Cell(#[from] CellError),
+    #[error(transparent)]
+    Settings(#[from] SettingsError),
+}
 
 // Used for hierarchy resolution
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct ModuleInfo {
-    _is_top: bool,
-    _is_blackbox: bool,
-    _is_whitebox: bool,
-    // inline bool is_box() const { return is_blackbox || is_whitebox; }
-    _instantiated_celltypes: Option<String>,
+    pub is_top: bool,
+    pub is_blackbox: bool,
+    pub is_whitebox: bool,
+    pub instantiated_celltypes: BTreeSet<String>,
 }
 
 impl ModuleInfo {
     pub const fn new() -> Self {
         Self {
-            _is_top: false,
-            _is_blackbox: false,
-            _is_whitebox: false,
-            _instantiated_celltypes: None, // IdString
+            is_top: false,
+            is_blackbox: false,
+            is_whitebox: false,
+            instantiated_celltypes: BTreeSet::new(),
+        }
+    }
+    pub fn with_module(module: &Module) -> Self {
+        let flag = |key: &str| {
+            module
+                .attributes
+                .get(key)
+                .map_or(false, |p| bool::from(p.clone()))
+        };
+        Self {
+            is_top: flag("top"),
+            is_blackbox: flag("blackbox"),
+            is_whitebox: flag("whitebox"),
+            instantiated_celltypes: module
+                .cells
+                .values()
+                .map(|cell| cell.cell_type.clone())
+                .collect(),
         }
     }
-    fn _is_box(&self) -> bool {
-        self._is_blackbox || self._is_whitebox
+    /// Boxes are kept as leaf cells instead of being flattened.
+    pub fn is_box(&self) -> bool {
+        self.is_blackbox || self.is_whitebox
     }
 }
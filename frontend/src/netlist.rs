@@ -0,0 +1,96 @@
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use std::collections::BTreeMap;
+
+/// A single bit of a signal, either one of the module's numbered nets or a constant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bit {
+    Net(u64),
+    Const(State),
+}
+
+/// A netlist as read from disk, before it has been imported into a context.
+/// This is shaped after the Yosys JSON format so every frontend can share the import code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Design {
+    pub modules: BTreeMap<String, Module>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Module {
+    pub attributes: BTreeMap<String, Property>,
+    pub parameter_default_values: BTreeMap<String, Property>,
+    pub ports: BTreeMap<String, ModulePort>,
+    pub cells: BTreeMap<String, Cell>,
+    pub netnames: BTreeMap<String, NetName>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModulePort {
+    pub direction: PortType,
+    pub bits: Vec<Bit>,
+    pub offset: i32,
+    pub upto: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub hide_name: bool,
+    pub cell_type: String,
+    pub parameters: BTreeMap<String, Property>,
+    pub attributes: BTreeMap<String, Property>,
+    pub port_directions: BTreeMap<String, PortType>,
+    pub connections: BTreeMap<String, Vec<Bit>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetName {
+    pub hide_name: bool,
+    pub bits: Vec<Bit>,
+    pub offset: i32,
+    pub upto: bool,
+    pub attributes: BTreeMap<String, Property>,
+}
+
+/// The name of bit `i` of a `width` wide signal, following the Yosys `name[index]` convention.
+pub fn bit_name(name: &str, i: usize, width: usize, offset: i32, upto: bool) -> String {
+    if width == 1 {
+        name.to_string()
+    } else {
+        let index = if upto {
+            offset + (width - 1 - i) as i32
+        } else {
+            offset + i as i32
+        };
+        format!("{}[{}]", name, index)
+    }
+}
+
+/// Union-find over signal ids, for formats that can join two signals after both have been used.
+/// Each set of joined signals becomes a single numbered bit.
+#[derive(Debug, Clone, Default)]
+pub struct BitUnion {
+    parents: Vec<usize>,
+}
+
+impl BitUnion {
+    pub fn new_signal(&mut self) -> usize {
+        let id = self.parents.len();
+        self.parents.push(id);
+        id
+    }
+    pub fn root(&self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            id = self.parents[id];
+        }
+        id
+    }
+    pub fn merge(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        self.parents[b] = a;
+    }
+    /// Bits 0 and 1 are never used by Yosys, keep numbering from 2 the same way.
+    pub fn bit(&self, id: usize) -> Bit {
+        Bit::Net(self.root(id) as u64 + 2)
+    }
+}
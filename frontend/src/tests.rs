@@ -0,0 +1,645 @@
+use super::*;
+use common::arch::{BelId, PipId, WireId};
+use common::generic::builder::GenericArchBuilder;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::base_types::{Loc, PlaceStrength};
+use common::kernel::constids::ID_BEL;
+use common::kernel::delay::{Delay, DelayQuad};
+use common::kernel::id_string_list::IdStringList;
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use common::kernel::types::PipMap;
+
+const INVERTER: &str = r#"{
+  "creator": "Yosys 0.22",
+  "modules": {
+    "top": {
+      "attributes": { "top": "00000000000000000000000000000001" },
+      "ports": {
+        "a": { "direction": "input", "bits": [ 2 ] },
+        "y": { "direction": "output", "bits": [ 3 ] }
+      },
+      "cells": {
+        "lut": {
+          "hide_name": 0,
+          "type": "SB_LUT4",
+          "parameters": { "LUT_INIT": "0000000000000001" },
+          "attributes": {},
+          "port_directions": { "I0": "input", "I1": "input", "O": "output" },
+          "connections": { "I0": [ 2 ], "I1": [ "0" ], "O": [ 3 ] }
+        }
+      },
+      "netnames": {
+        "a": { "hide_name": 0, "bits": [ 2 ], "attributes": {} },
+        "y": { "hide_name": 0, "bits": [ 3 ], "attributes": {} },
+        "$auto$y": { "hide_name": 1, "bits": [ 3 ], "attributes": {} }
+      }
+    }
+  }
+}"#;
+
+#[test]
+fn json_import_inverter() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    json::parse_json(INVERTER.as_bytes(), &mut ctx).unwrap();
+
+    // The LUT, the ground driver and one IO buffer per top level port.
+    assert_eq!(ctx.cells.len(), 4);
+    // a, y and the shared ground net.
+    assert_eq!(ctx.nets.len(), 3);
+
+    let a = ctx.id("a");
+    let y = ctx.id("y");
+    let hidden = ctx.id("$auto$y");
+    assert_eq!(ctx.ports[&a].port_type, PortType::In);
+    assert_eq!(ctx.ports[&y].port_type, PortType::Out);
+    assert_eq!(ctx.get_net_index(hidden), ctx.get_net_index(y));
+
+    let lut = ctx.id("lut");
+    let out = ctx.id("O");
+    let y_net = ctx.get_net(y).unwrap();
+    assert_eq!(y_net.driver.cell, ctx.get_cell_index(lut));
+    assert_eq!(y_net.driver.port, out);
+    assert_eq!(y_net.users.len(), 1);
+
+    let lut_init = ctx.id("LUT_INIT");
+    let lut = ctx.get_cell(lut).unwrap();
+    assert_eq!(lut.parameters()[&lut_init], Property::with_width(1, 16));
+}
+
+#[test]
+fn json_invalid_bit() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let result = json::parse_json(INVERTER.replace("\"0\"", "\"q\"").as_bytes(), &mut ctx);
+    assert!(matches!(result, Err(FrontendError::InvalidBit(_))));
+}
+
+// One bel, two wires and the pip between them, built as a generic arch so their names are
+// registered in the context the way any device's are.
+fn build_device(ctx: &mut BaseCtx<i64>) -> (BelId, WireId, WireId, PipId) {
+    let (lut4, local) = (ctx.id("SB_LUT4"), ctx.id("LOCAL"));
+    let names: Vec<_> = ["X1/Y1/lc0", "X1/Y1/lc0_O", "X1/Y1/T0", "X1/Y1/lc0_O->T0"]
+        .iter()
+        .map(|name| IdStringList::parse(ctx, name))
+        .collect();
+    let mut builder = GenericArchBuilder::new("tiny");
+    let loc = Loc::new(1, 1, 0);
+    let bel = builder
+        .add_bel(names[0].clone(), lut4, loc, false, false)
+        .unwrap();
+    let out = builder.add_wire(names[1].clone(), local, 1, 1).unwrap();
+    let track = builder.add_wire(names[2].clone(), local, 1, 1).unwrap();
+    let delay = DelayQuad::with_delay(Delay::with_delay(50));
+    let pip = builder
+        .add_pip(names[3].clone(), local, out, track, delay, loc)
+        .unwrap();
+    builder.build(ctx);
+    (bel, out, track, pip)
+}
+
+#[test]
+fn json_write_restores_placement_and_routing() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let (bel, out, track, pip) = build_device(&mut ctx);
+    json::parse_json(INVERTER.as_bytes(), &mut ctx).unwrap();
+    let lut = ctx.id("lut");
+    let y = ctx.id("y");
+    let lut_index = ctx.get_cell_index(lut).unwrap();
+    let y_index = ctx.get_net_index(y).unwrap();
+    ctx.cells[lut_index].set_bel(bel, PlaceStrength::Placer);
+    let source = PipMap::with_pip(PipId::new(), PlaceStrength::Strong);
+    let hop = PipMap::with_pip(pip, PlaceStrength::Strong);
+    ctx.nets[y_index].set_wire(out, source);
+    ctx.nets[y_index].set_wire(track, hop);
+
+    let mut written = Vec::new();
+    json::write_json(&mut ctx, &mut written).unwrap();
+    let placed = Property::with_str("X1/Y1/lc0");
+    assert_eq!(ctx.cells[lut_index].attributes()[&ID_BEL], placed);
+    let mut restored: BaseCtx<i64> = BaseCtx::new();
+    build_device(&mut restored);
+    json::parse_json(written.as_slice(), &mut restored).unwrap();
+
+    // The IO buffers are carried over rather than created a second time.
+    assert_eq!(restored.cells.len(), ctx.cells.len());
+    let a = restored.id("a");
+    assert_eq!(restored.port_cells[&a], restored.get_cell_index(a).unwrap());
+
+    let lut = restored.id("lut");
+    let y = restored.id("y");
+    let lut = restored.get_cell(lut).unwrap();
+    assert_eq!(lut.bel(), bel);
+    assert_eq!(lut.bel_strength(), PlaceStrength::Placer);
+    let wires = restored.get_net(y).unwrap().wires();
+    assert_eq!(wires.len(), 2);
+    assert_eq!(wires[&out], source);
+    assert_eq!(wires[&track], hop);
+}
+
+#[cfg(feature = "testarch")]
+const LUT_TO_FF: &str = r#"{
+  "modules": {
+    "top": {
+      "attributes": { "top": "00000000000000000000000000000001" },
+      "ports": {},
+      "cells": {
+        "lut": {
+          "type": "LUT4",
+          "port_directions": { "O": "output" },
+          "connections": { "O": [ 2 ] }
+        },
+        "ff": {
+          "type": "DFF",
+          "port_directions": { "D": "input" },
+          "connections": { "D": [ 2 ] }
+        }
+      },
+      "netnames": {
+        "q": { "hide_name": 0, "bits": [ 2 ], "attributes": {} }
+      }
+    }
+  }
+}"#;
+
+#[cfg(feature = "testarch")]
+#[test]
+fn json_round_trip_on_test_device() {
+    use common::generic::flow;
+    use common::testarch::device::{self, TestArchArgs};
+
+    // Placed and routed by the generic flow, nothing named by hand.
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let mut arch = device::build(&mut ctx, TestArchArgs::new(3, 3));
+    json::parse_json(LUT_TO_FF.as_bytes(), &mut ctx).unwrap();
+    flow::run(&mut ctx, &mut arch).unwrap();
+    let mut written = Vec::new();
+    json::write_json(&mut ctx, &mut written).unwrap();
+
+    let mut restored: BaseCtx<i64> = BaseCtx::new();
+    device::build(&mut restored, TestArchArgs::new(3, 3));
+    json::parse_json(written.as_slice(), &mut restored).unwrap();
+    for name in ["lut", "ff"] {
+        let (cell, restored_cell) = (ctx.id(name), restored.id(name));
+        let placed = ctx.get_cell(cell).unwrap();
+        assert!(placed.bel().index().is_some());
+        assert_eq!(
+            restored.get_cell(restored_cell).unwrap().bel(),
+            placed.bel()
+        );
+    }
+    let (q, restored_q) = (ctx.id("q"), restored.id("q"));
+    let routing = ctx.get_net(q).unwrap().wires();
+    assert!(!routing.is_empty());
+    assert_eq!(restored.get_net(restored_q).unwrap().wires(), routing);
+}
+
+#[test]
+fn json_constant_drivers() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    ctx.set_str("frontend/x_tieoff", "1").unwrap();
+    let netlist = INVERTER.replace("[ \"0\" ]", "[ \"x\" ]");
+    json::parse_json(netlist.as_bytes(), &mut ctx).unwrap();
+
+    let vcc = ctx.id("$PACKER_VCC_NET");
+    let vcc_driver = ctx.id("$PACKER_VCC_DRV");
+    let vcc_type = ctx.id("VCC");
+    let vcc_net = ctx.get_net(vcc).unwrap();
+    assert_eq!(vcc_net.driver.cell, ctx.get_cell_index(vcc_driver));
+    assert_eq!(vcc_net.users.len(), 1);
+    assert_eq!(ctx.get_cell(vcc_driver).unwrap().cell_type(), vcc_type);
+    let gnd = ctx.id("$PACKER_GND_NET");
+    assert!(ctx.get_net(gnd).is_none());
+}
+
+const TRISTATE: &str = r#"{
+  "modules": {
+    "top": {
+      "ports": {
+        "d": { "direction": "input", "bits": [ 2 ] },
+        "en": { "direction": "input", "bits": [ 3 ] },
+        "io": { "direction": "inout", "bits": [ 4 ] }
+      },
+      "cells": {
+        "tbuf": {
+          "type": "$_TBUF_",
+          "port_directions": { "A": "input", "E": "input", "Y": "output" },
+          "connections": { "A": [ 2 ], "E": [ 3 ], "Y": [ 4 ] }
+        }
+      },
+      "netnames": {
+        "d": { "bits": [ 2 ] },
+        "en": { "bits": [ 3 ] },
+        "io": { "bits": [ 4 ] }
+      }
+    }
+  }
+}"#;
+
+#[test]
+fn json_inout_driven_inside() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    json::parse_json(TRISTATE.as_bytes(), &mut ctx).unwrap();
+
+    // The tristate buffer keeps driving the inout net, its IO buffer only listens on I.
+    let (io, tbuf, input) = (ctx.id("io"), ctx.id("tbuf"), ctx.id("I"));
+    let io_net = ctx.get_net(io).unwrap();
+    assert_eq!(io_net.driver.cell, ctx.get_cell_index(tbuf));
+    assert_eq!(io_net.users.len(), 1);
+    let (_, user) = io_net.users.iter().next().unwrap();
+    assert_eq!(user.cell, ctx.get_cell_index(io));
+    assert_eq!(user.port, input);
+}
+
+const HIERARCHICAL: &str = r#"{
+  "modules": {
+    "top": {
+      "ports": { "a": { "direction": "input", "bits": [ 2 ] } },
+      "cells": {
+        "u0": {
+          "type": "sub",
+          "connections": { "i": [ 2 ] }
+        },
+        "bb": {
+          "type": "macro",
+          "connections": { "i": [ 2 ] }
+        }
+      },
+      "netnames": { "a": { "bits": [ 2 ] } }
+    },
+    "sub": {
+      "ports": { "i": { "direction": "input", "bits": [ 5 ] } },
+      "cells": {
+        "lut": {
+          "type": "SB_LUT4",
+          "port_directions": { "I0": "input" },
+          "connections": { "I0": [ 5 ] }
+        }
+      },
+      "netnames": { "i": { "bits": [ 5 ] } }
+    },
+    "macro": {
+      "attributes": { "blackbox": "00000000000000000000000000000001" },
+      "ports": { "i": { "direction": "input", "bits": [ 2 ] } }
+    }
+  }
+}"#;
+
+#[test]
+fn json_hierarchy_flattening() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    json::parse_json(HIERARCHICAL.as_bytes(), &mut ctx).unwrap();
+
+    let top = ctx.id("top");
+    let u0 = ctx.id("u0");
+    let lut = ctx.id("lut");
+    let flat_lut = ctx.id("u0.lut");
+    let a = ctx.id("a");
+    let inner_net = ctx.id("u0.i");
+    assert_eq!(ctx.top_module, top);
+
+    // The sub instance is flattened, the blackbox stays a leaf cell of its own type.
+    assert!(ctx.get_cell(u0).is_none());
+    assert_eq!(ctx.get_cell(flat_lut).unwrap().hierarchy_path(), u0);
+    let bb = ctx.id("bb");
+    let macro_type = ctx.id("macro");
+    assert_eq!(ctx.get_cell(bb).unwrap().cell_type(), macro_type);
+
+    // The port net inside the instance is an alias of the outer net.
+    assert_eq!(ctx.get_net_index(inner_net), ctx.get_net_index(a));
+    assert_eq!(ctx.get_net(a).unwrap().users.len(), 2);
+
+    assert_eq!(ctx.hierarchy[&top].hierachical_cells()[&u0], u0);
+    assert_eq!(ctx.hierarchy[&u0].leaf_cells()[&lut], flat_lut);
+    assert_eq!(ctx.hierarchy[&u0].parent(), top);
+}
+
+#[test]
+fn json_unconnected_instance_port() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let connected = r#""connections": { "i": [ 2 ] }"#;
+    let netlist = HIERARCHICAL.replacen(connected, r#""connections": {}"#, 1);
+    json::parse_json(netlist.as_bytes(), &mut ctx).unwrap();
+
+    // The LUT inside keeps its input net, now private to the instance.
+    let (a, inner_net, flat_lut) = (ctx.id("a"), ctx.id("u0.i"), ctx.id("u0.lut"));
+    assert_ne!(ctx.get_net_index(inner_net), ctx.get_net_index(a));
+    let inner = ctx.get_net(inner_net).unwrap();
+    let (_, user) = inner.users.iter().next().unwrap();
+    assert_eq!(user.cell, ctx.get_cell_index(flat_lut));
+}
+
+#[test]
+fn json_recursive_instance() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let netlist = HIERARCHICAL.replace(r#""type": "SB_LUT4""#, r#""type": "sub""#);
+    let result = json::parse_json(netlist.as_bytes(), &mut ctx);
+    assert!(matches!(result, Err(FrontendError::RecursiveInstance(module)) if module == "sub"));
+}
+
+// A submodule wiring its input straight to its output, so the two nets around it are one.
+const FEEDTHROUGH: &str = r#"{
+  "modules": {
+    "top": {
+      "ports": {
+        "a": { "direction": "input", "bits": [ 2 ] },
+        "y": { "direction": "output", "bits": [ 4 ] }
+      },
+      "cells": {
+        "lut": {
+          "type": "SB_LUT4",
+          "port_directions": { "I0": "input", "O": "output" },
+          "connections": { "I0": [ 3 ], "O": [ 4 ] }
+        },
+        "u0": {
+          "type": "thru",
+          "connections": { "i": [ 2 ], "o": [ 3 ] }
+        }
+      },
+      "netnames": {
+        "a": { "bits": [ 2 ] },
+        "w": { "bits": [ 3 ] },
+        "y": { "bits": [ 4 ] }
+      }
+    },
+    "thru": {
+      "ports": {
+        "i": { "direction": "input", "bits": [ 5 ] },
+        "o": { "direction": "output", "bits": [ 5 ] }
+      },
+      "cells": {},
+      "netnames": { "i": { "bits": [ 5 ] }, "o": { "bits": [ 5 ] } }
+    }
+  }
+}"#;
+
+#[test]
+fn json_feedthrough_merges_nets() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    json::parse_json(FEEDTHROUGH.as_bytes(), &mut ctx).unwrap();
+
+    // a and w are merged, every name on either side of the instance finds the one net.
+    let (a, w, y) = (ctx.id("a"), ctx.id("w"), ctx.id("y"));
+    let (inner_i, inner_o) = (ctx.id("u0.i"), ctx.id("u0.o"));
+    let net = ctx.get_net_index(a);
+    assert!(net.is_some());
+    for name in [w, inner_i, inner_o] {
+        assert_eq!(ctx.get_net_index(name), net);
+    }
+    assert_eq!(ctx.nets.len(), 2);
+    assert_ne!(ctx.get_net_index(y), net);
+
+    // The input buffer drives the LUT that was on w.
+    let (lut, i0, out) = (ctx.id("lut"), ctx.id("I0"), ctx.id("O"));
+    let merged = ctx.get_net(a).unwrap();
+    assert_eq!(merged.driver.cell, ctx.get_cell_index(a));
+    assert_eq!(merged.driver.port, out);
+    assert_eq!(merged.users.len(), 1);
+    let (_, user) = merged.users.iter().next().unwrap();
+    assert_eq!((user.cell, user.port), (ctx.get_cell_index(lut), i0));
+    assert_eq!(ctx.get_cell(lut).unwrap().get_port(i0), net);
+    assert_eq!(ctx.ports[&a].net, net);
+    assert_eq!(ctx.check(), vec![]);
+}
+
+const BLIF_AND_FF: &str = "\
+# and gate feeding a flop
+.model top
+.inputs a b clk
+.outputs q
+.names a b \\
+  y
+11 1
+.latch y q re clk 0
+.cname ff
+.attr src \"top.v:3.1-3.20\"
+.end
+";
+
+#[test]
+fn blif_import_and_ff() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    blif::parse_blif(BLIF_AND_FF.as_bytes(), &mut ctx).unwrap();
+
+    let lut_name = ctx.id("$lut$0");
+    let init = ctx.id("INIT");
+    let lut = ctx.get_cell(lut_name).unwrap();
+    // Only minterm 0b11 is in the on-set.
+    assert_eq!(lut.parameters()[&init], Property::with_width(8, 4));
+
+    let ff = ctx.id("ff");
+    let src = ctx.id("src");
+    let dff_type = ctx.id("$_DFF_P_");
+    let ff = ctx.get_cell(ff).unwrap();
+    assert_eq!(ff.cell_type(), dff_type);
+    assert_eq!(ff.parameters()[&init], Property::with_state(State::S0));
+    assert_eq!(ff.attributes()[&src], Property::with_str("top.v:3.1-3.20"));
+
+    let y = ctx.id("y");
+    assert_eq!(
+        ctx.get_net(y).unwrap().driver.cell,
+        ctx.get_cell_index(lut_name)
+    );
+}
+
+const BLIF_GATE: &str = "\
+.model top
+.inputs a b
+.outputs y
+.gate SB_LUT4 I0=a I1=b I2=$false I3=$false O=y
+.end
+";
+
+#[test]
+fn blif_gate_directions_from_library() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    let result = blif::parse_blif(BLIF_GATE.as_bytes(), &mut ctx);
+    assert!(matches!(
+        result,
+        Err(FrontendError::MissingPortDirection(_, _))
+    ));
+
+    let library = verilog::read_verilog(
+        "(* blackbox *) module SB_LUT4 (output O, input I0, input I1, input I2, input I3); \
+         endmodule"
+            .as_bytes(),
+    )
+    .unwrap();
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    blif::parse_blif_with_library(BLIF_GATE.as_bytes(), &library, &mut ctx).unwrap();
+    let (y, gate) = (ctx.id("y"), ctx.id("$SB_LUT4$0"));
+    assert_eq!(
+        ctx.get_net(y).unwrap().driver.cell,
+        ctx.get_cell_index(gate)
+    );
+}
+
+#[test]
+fn blif_wide_names() {
+    // Too wide for a truth table, the off-set cover is read as an inverted sum of products.
+    let inputs: Vec<String> = (0..40).map(|i| format!("i{i}")).collect();
+    let netlist = format!(
+        ".model top\n.inputs {0}\n.outputs y\n.names {0} y\n{1} 0\n.end\n",
+        inputs.join(" "),
+        "1".repeat(40)
+    );
+    let design = blif::read_blif(netlist.as_bytes()).unwrap();
+    let top = &design.modules["top"];
+    let sop = top.cells.values().find(|c| c.cell_type == "$sop").unwrap();
+    assert_eq!(sop.parameters["DEPTH"], Property::with_width(1, 32));
+    assert_eq!(
+        sop.parameters["TABLE"],
+        Property::from_string(&"10".repeat(40))
+    );
+    let not = top
+        .cells
+        .values()
+        .find(|c| c.cell_type == "$_NOT_")
+        .unwrap();
+    assert_eq!(not.connections["A"], sop.connections["Y"]);
+    assert_eq!(not.connections["Y"], top.ports["y"].bits);
+}
+
+const EDIF_BUFFER: &str = r#"(edif top
+  (edifVersion 2 0 0)
+  (edifLevel 0)
+  (keywordMap (keywordLevel 0))
+  (external hdi_primitives (edifLevel 0) (technology (numberDefinition))
+    (cell LUT1 (cellType GENERIC)
+      (view netlist (viewType NETLIST)
+        (interface
+          (port O (direction OUTPUT))
+          (port I0 (direction INPUT))))))
+  (library work (edifLevel 0) (technology (numberDefinition))
+    (cell top (cellType GENERIC)
+      (view netlist (viewType NETLIST)
+        (interface
+          (port (array (rename d "d[1:0]") 2) (direction INPUT))
+          (port q (direction OUTPUT)))
+        (contents
+          (instance buf (viewRef netlist (cellRef LUT1 (libraryRef hdi_primitives)))
+            (property INIT (string "2'h2")))
+          (net (rename d_1_ "d[1]") (joined (portRef (member d 0)) (portRef I0 (instanceRef buf))))
+          (net q (joined (portRef q) (portRef O (instanceRef buf))))))))
+  (design top (cellRef top (libraryRef work))))
+"#;
+
+#[test]
+fn edif_import_renamed_net() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    edif::parse_edif(EDIF_BUFFER.as_bytes(), &mut ctx).unwrap();
+
+    let renamed = ctx.id("d_1_");
+    let original = ctx.id("d[1]");
+    let buf = ctx.id("buf");
+    let init = ctx.id("INIT");
+    assert!(ctx.get_net_index(renamed).is_some());
+    assert_eq!(ctx.get_net_index(renamed), ctx.get_net_index(original));
+    assert_eq!(
+        ctx.get_cell(buf).unwrap().parameters()[&init],
+        Property::with_str("2'h2")
+    );
+    // Member 0 of the array is its most significant bit, which is the top level port d[1].
+    assert_eq!(ctx.ports[&original].net, ctx.get_net_index(renamed));
+}
+
+#[test]
+fn edif_error_position() {
+    let result = edif::read_edif("(edif top\n  (library".as_bytes());
+    assert!(matches!(result, Err(FrontendError::Edif(2, _, _))));
+}
+
+const VERILOG_HIERARCHY: &str = r#"
+(* blackbox *)
+module SB_LUT4 (output O, input I0, input I1, input I2, input I3);
+  parameter [15:0] LUT_INIT = 0;
+endmodule
+
+module inv (a, y);
+  input a;
+  output y;
+  SB_LUT4 #(.LUT_INIT(16'h0001)) lut (.O(y), .I0(a), .I1(1'b0), .I2(1'b0), .I3(1'b0));
+endmodule
+
+module top (input [1:0] d, output q);
+  wire n;
+  assign n = d[1];
+  inv #() u0 (n, q);
+endmodule
+"#;
+
+#[test]
+fn verilog_import_hierarchy() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    verilog::parse_verilog(VERILOG_HIERARCHY.as_bytes(), &mut ctx).unwrap();
+
+    let lut_name = ctx.id("u0.lut");
+    let lut_init = ctx.id("LUT_INIT");
+    let lut = ctx.get_cell(lut_name).unwrap();
+    assert_eq!(lut.parameters()[&lut_init], Property::with_width(1, 16));
+
+    // The assign only adds an alias, both names refer to the same net.
+    let n = ctx.id("n");
+    let d1 = ctx.id("d[1]");
+    assert!(ctx.get_net_index(n).is_some());
+    assert_eq!(ctx.get_net_index(n), ctx.get_net_index(d1));
+    // Three IO buffers, the LUT and the ground driver.
+    assert_eq!(ctx.cells.len(), 5);
+}
+
+#[test]
+fn verilog_error_line() {
+    let result = verilog::read_verilog("module top;\n  always @(*) begin\nend\n".as_bytes());
+    assert!(matches!(result, Err(FrontendError::Verilog(2, _))));
+}
+
+#[test]
+fn verilog_write_and_read_back() {
+    let mut ctx: BaseCtx<i64> = BaseCtx::new();
+    json::parse_json(INVERTER.as_bytes(), &mut ctx).unwrap();
+    let lut = ctx.id("lut");
+    let lut_init = ctx.id("LUT_INIT");
+    let src = ctx.id("src");
+    let lut_index = ctx.get_cell_index(lut).unwrap();
+    ctx.cells[lut_index].set_param(lut_init, Property::with_width(1, 16));
+    ctx.cells[lut_index].set_attribute(src, Property::with_str("top.v:3"));
+    // A GND cell of the design's own is written like any other.
+    let (tie, gnd, tie_net, y) = (ctx.id("tie"), ctx.id("GND"), ctx.id("tie_net"), ctx.id("Y"));
+    let tie_index = ctx.create_cell(tie, gnd).unwrap();
+    ctx.cells[tie_index].add_output(y);
+    ctx.create_net(tie_net).unwrap();
+    ctx.connect_port(tie_net, tie, y).unwrap();
+    // An unconnected port, and a net named after a keyword.
+    let (en, z, wire) = (ctx.id("EN"), ctx.id("Z"), ctx.id("wire"));
+    ctx.cells[tie_index].add_input(en);
+    ctx.cells[tie_index].add_output(z);
+    ctx.create_net(wire).unwrap();
+    ctx.connect_port(wire, tie, z).unwrap();
+
+    let mut written = Vec::new();
+    verilog::write_verilog(&ctx, &mut written).unwrap();
+    let text = String::from_utf8(written.clone()).unwrap();
+    assert!(text.contains(".EN()"));
+    assert!(text.contains("\\wire "));
+    let design = verilog::read_verilog(written.as_slice()).unwrap();
+    let top = &design.modules["top"];
+
+    // The LUT and the design's GND cell. The IO buffers are the module ports and the frontend's
+    // ground driver is a constant.
+    assert_eq!(top.cells.len(), 2);
+    assert_eq!(top.cells["tie"].cell_type, "GND");
+    assert_eq!(
+        top.cells["tie"].connections["Y"],
+        top.netnames["tie_net"].bits
+    );
+    assert_eq!(top.cells["tie"].connections["Z"], top.netnames["wire"].bits);
+    // The open port reads back as no connection at all.
+    assert!(!top.cells["tie"].connections.contains_key("EN"));
+    assert_eq!(top.ports["a"].direction, PortType::In);
+    let cell = &top.cells["lut"];
+    assert_eq!(cell.cell_type, "SB_LUT4");
+    assert_eq!(cell.parameters["LUT_INIT"], Property::with_width(1, 16));
+    assert_eq!(cell.attributes["src"], Property::with_str("top.v:3"));
+    assert_eq!(cell.connections["O"], top.netnames["y"].bits);
+    assert_eq!(cell.connections["I1"], vec![netlist::Bit::Const(State::S0)]);
+    assert_eq!(top.netnames["$auto$y"].bits, top.netnames["y"].bits);
+}
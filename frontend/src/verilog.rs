@@ -0,0 +1,1236 @@
+use crate::export::export_design;
+use crate::import::import_design;
+use crate::netlist::{bit_name, Bit, BitUnion, Cell, Design, Module, ModulePort, NetName};
+use crate::FrontendError;
+use common::kernel::base_context::BaseCtx;
+use common::kernel::constids::{
+    ID_NEXTPNR_IBUF, ID_NEXTPNR_IOBUF, ID_NEXTPNR_OBUF, ID_PACKER_GND_NET, ID_PACKER_VCC_NET,
+};
+use common::kernel::delay::DelayTrait;
+use common::kernel::port::PortType;
+use common::kernel::property::{Property, State};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
+
+// Structural (gate-level) Verilog: module declarations, wires, assigns between nets and module
+// instances. Library cells can be described by modules marked (* blackbox *).
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Punct(&'static str),
+}
+
+const PUNCTUATION: [&str; 15] = [
+    "(*", "*)", "(", ")", "[", "]", "{", "}", ",", ";", ":", ".", "#", "=", "-",
+];
+
+fn lex(input: &str) -> Result<Vec<(usize, Token)>, FrontendError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let (mut i, mut line) = (0, 1);
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if rest == "//" || c == '`' {
+            // Comments and compiler directives run to the end of the line.
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if rest == "/*" {
+            i += 2;
+            while i < chars.len() && chars[i..(i + 2).min(chars.len())] != ['*', '/'] {
+                line += usize::from(chars[i] == '\n');
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            tokens.push((line, Token::Str(chars[start..i].iter().collect())));
+            i += 1;
+        } else if c == '\\' {
+            // Escaped identifiers run to the next whitespace.
+            let start = i + 1;
+            while i < chars.len() && !chars[i].is_whitespace() {
+                i += 1;
+            }
+            tokens.push((line, Token::Ident(chars[start..i].iter().collect())));
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '$' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+            {
+                i += 1;
+            }
+            tokens.push((line, Token::Ident(chars[start..i].iter().collect())));
+        } else if c.is_ascii_digit() || c == '\'' {
+            // Sized and based literals, 8'hff or 'b1, possibly with spaces around the base.
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '_') {
+                i += 1;
+            }
+            let mut j = i;
+            while j < chars.len() && chars[j].is_whitespace() && chars[j] != '\n' {
+                j += 1;
+            }
+            if j < chars.len() && chars[j] == '\'' {
+                i = j + 1;
+                while i < chars.len() && chars[i].is_whitespace() && chars[i] != '\n' {
+                    i += 1;
+                }
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '?')
+                {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i]
+                .iter()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            tokens.push((line, Token::Number(text)));
+        } else if let Some(p) = PUNCTUATION.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push((line, Token::Punct(*p)));
+            i += p.len();
+        } else {
+            return Err(FrontendError::Verilog(
+                line,
+                format!("unexpected character {:?}", c),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Bits of a Verilog literal such as `8'hA5`, `4'b10x1`, `'d3` or `42`, LSB first.
+fn literal_bits(text: &str) -> Option<Vec<State>> {
+    let text = text.replace('_', "");
+    let Some((size, value)) = text.split_once('\'') else {
+        let value: i64 = text.parse().ok()?;
+        return Some(
+            (0..32)
+                .map(|i| {
+                    if value & (1 << i) != 0 {
+                        State::S1
+                    } else {
+                        State::S0
+                    }
+                })
+                .collect(),
+        );
+    };
+    let width: usize = if size.is_empty() {
+        32
+    } else {
+        size.parse().ok()?
+    };
+    let value = value.trim_start_matches(['s', 'S']);
+    let mut chars = value.chars();
+    let base = chars.next()?.to_ascii_lowercase();
+    let digits: Vec<char> = chars.map(|c| c.to_ascii_lowercase()).collect();
+    let mut bits = Vec::new();
+    let digit_bits = |c: char, n: u32| -> Option<Vec<State>> {
+        match c {
+            'x' => Some(vec![State::Sx; n as usize]),
+            'z' | '?' => Some(vec![State::Sz; n as usize]),
+            _ => {
+                let v = c.to_digit(1 << n)?;
+                Some(
+                    (0..n)
+                        .map(|i| {
+                            if v & (1 << i) != 0 {
+                                State::S1
+                            } else {
+                                State::S0
+                            }
+                        })
+                        .collect(),
+                )
+            }
+        }
+    };
+    match base {
+        'b' | 'o' | 'h' => {
+            let n = match base {
+                'b' => 1,
+                'o' => 3,
+                _ => 4,
+            };
+            for c in digits.iter().rev() {
+                bits.extend(digit_bits(*c, n)?);
+            }
+        }
+        'd' => {
+            let digits: String = digits.into_iter().collect();
+            match digits.as_str() {
+                "x" => bits = vec![State::Sx; width],
+                "z" | "?" => bits = vec![State::Sz; width],
+                _ => {
+                    let mut value: u128 = digits.parse().ok()?;
+                    while value != 0 {
+                        bits.push(if value & 1 != 0 { State::S1 } else { State::S0 });
+                        value >>= 1;
+                    }
+                }
+            }
+        }
+        _ => return None,
+    }
+    // Pad with x or z if the leftmost digit was one, otherwise with zeroes.
+    let padding = match bits.last() {
+        Some(State::Sx) => State::Sx,
+        Some(State::Sz) => State::Sz,
+        _ => State::S0,
+    };
+    bits.resize(width, padding);
+    Some(bits)
+}
+
+fn literal_property(bits: &[State]) -> Property {
+    let mut property = Property::Int(State::S0, 0, bits.iter().map(State::to_char).collect());
+    property.update_intval();
+    property
+}
+
+/// A single bit of a net expression.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum BitRef {
+    Signal(usize),
+    Const(State),
+}
+
+struct Wire {
+    // Declared [left:right] bounds.
+    left: i32,
+    right: i32,
+    // Signal of each element, starting from the right hand bound.
+    signals: Vec<usize>,
+    attributes: BTreeMap<String, Property>,
+}
+
+impl Wire {
+    fn position(&self, index: i32) -> Option<usize> {
+        let position = if self.left >= self.right {
+            index - self.right
+        } else {
+            self.right - index
+        };
+        usize::try_from(position)
+            .ok()
+            .filter(|p| *p < self.signals.len())
+    }
+}
+
+enum Connections {
+    Named(BTreeMap<String, Vec<BitRef>>),
+    Ordered(Vec<Vec<BitRef>>),
+}
+
+enum Parameters {
+    Named(BTreeMap<String, Property>),
+    Ordered(Vec<Property>),
+}
+
+struct Instance {
+    cell_type: String,
+    parameters: Parameters,
+    attributes: BTreeMap<String, Property>,
+    connections: Connections,
+}
+
+#[derive(Default)]
+struct ModuleBuilder {
+    // Line of the module declaration, for errors found while building.
+    line: usize,
+    attributes: BTreeMap<String, Property>,
+    // Ports in declaration order, for positional connections.
+    port_order: Vec<String>,
+    directions: BTreeMap<String, PortType>,
+    parameter_order: Vec<String>,
+    parameters: BTreeMap<String, Property>,
+    wires: BTreeMap<String, Wire>,
+    bits: BitUnion,
+    // Signals tied to a constant by an assign, keyed by any member of the joined set.
+    constants: Vec<(usize, State)>,
+    instances: BTreeMap<String, Instance>,
+    defparams: Vec<(String, String, Property)>,
+}
+
+impl ModuleBuilder {
+    fn declare(&mut self, name: &str, range: Option<(i32, i32)>) {
+        let (left, right) = range.unwrap_or((0, 0));
+        match self.wires.get(name) {
+            // Re-declaring a port as a wire is fine, as long as the width doesn't change.
+            Some(wire) if (wire.left, wire.right) == (left, right) || range.is_none() => {}
+            _ => {
+                let width = (left - right).unsigned_abs() as usize + 1;
+                let signals = (0..width).map(|_| self.bits.new_signal()).collect();
+                self.wires.insert(
+                    name.to_string(),
+                    Wire {
+                        left,
+                        right,
+                        signals,
+                        attributes: BTreeMap::new(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn wire(&mut self, name: &str) -> &Wire {
+        // Undeclared identifiers are implicit single bit wires.
+        if !self.wires.contains_key(name) {
+            self.declare(name, None);
+        }
+        &self.wires[name]
+    }
+
+    fn assign(&mut self, lhs: &[BitRef], rhs: &[BitRef]) {
+        let zero = BitRef::Const(State::S0);
+        for (i, l) in lhs.iter().enumerate() {
+            match (*l, *rhs.get(i).unwrap_or(&zero)) {
+                (BitRef::Signal(a), BitRef::Signal(b)) => self.bits.merge(a, b),
+                (BitRef::Signal(a), BitRef::Const(c)) | (BitRef::Const(c), BitRef::Signal(a)) => {
+                    self.constants.push((a, c))
+                }
+                (BitRef::Const(_), BitRef::Const(_)) => {}
+            }
+        }
+    }
+
+    fn bit(&self, bit: BitRef) -> Bit {
+        match bit {
+            BitRef::Signal(signal) => {
+                let root = self.bits.root(signal);
+                match self
+                    .constants
+                    .iter()
+                    .find(|(s, _)| self.bits.root(*s) == root)
+                {
+                    Some((_, state)) => Bit::Const(*state),
+                    None => self.bits.bit(signal),
+                }
+            }
+            BitRef::Const(state) => Bit::Const(state),
+        }
+    }
+
+    fn build(&self, modules: &BTreeMap<String, ModuleBuilder>) -> Result<Module, String> {
+        let mut module = Module {
+            attributes: self.attributes.clone(),
+            parameter_default_values: self.parameters.clone(),
+            ..Default::default()
+        };
+        for name in &self.port_order {
+            let wire = &self.wires[name];
+            let direction = *self
+                .directions
+                .get(name)
+                .ok_or_else(|| format!("port {} has no direction", name))?;
+            module.ports.insert(
+                name.clone(),
+                ModulePort {
+                    direction,
+                    bits: wire
+                        .signals
+                        .iter()
+                        .map(|s| self.bit(BitRef::Signal(*s)))
+                        .collect(),
+                    offset: wire.left.min(wire.right),
+                    upto: wire.left < wire.right,
+                },
+            );
+        }
+        for (name, wire) in &self.wires {
+            module.netnames.insert(
+                name.clone(),
+                NetName {
+                    hide_name: name.starts_with('$'),
+                    bits: wire
+                        .signals
+                        .iter()
+                        .map(|s| self.bit(BitRef::Signal(*s)))
+                        .collect(),
+                    offset: wire.left.min(wire.right),
+                    upto: wire.left < wire.right,
+                    attributes: wire.attributes.clone(),
+                },
+            );
+        }
+        for (name, instance) in &self.instances {
+            let definition = modules.get(&instance.cell_type);
+            let mut cell = Cell {
+                hide_name: name.starts_with('$'),
+                cell_type: instance.cell_type.clone(),
+                parameters: BTreeMap::new(),
+                attributes: instance.attributes.clone(),
+                port_directions: BTreeMap::new(),
+                connections: BTreeMap::new(),
+            };
+            match &instance.parameters {
+                Parameters::Named(parameters) => cell.parameters = parameters.clone(),
+                Parameters::Ordered(values) => {
+                    let order = &definition
+                        .ok_or_else(|| format!("positional parameters on unknown cell {}", name))?
+                        .parameter_order;
+                    for (key, value) in order.iter().zip(values) {
+                        cell.parameters.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            let connections: Vec<(String, &Vec<BitRef>)> = match &instance.connections {
+                Connections::Named(named) => named.iter().map(|(k, v)| (k.clone(), v)).collect(),
+                Connections::Ordered(ordered) => definition
+                    .ok_or_else(|| format!("positional connections on unknown cell {}", name))?
+                    .port_order
+                    .iter()
+                    .cloned()
+                    .zip(ordered)
+                    .collect(),
+            };
+            for (port, bits) in connections {
+                let width = definition
+                    .and_then(|d| d.wires.get(&port))
+                    .map_or(bits.len(), |w| w.signals.len());
+                let mut port_bits: Vec<Bit> = bits.iter().map(|b| self.bit(*b)).collect();
+                port_bits.resize(width, Bit::Const(State::Sx));
+                if let Some(direction) = definition.and_then(|d| d.directions.get(&port)) {
+                    cell.port_directions.insert(port.clone(), *direction);
+                }
+                cell.connections.insert(port, port_bits);
+            }
+            module.cells.insert(name.clone(), cell);
+        }
+        for (instance, key, value) in &self.defparams {
+            module
+                .cells
+                .get_mut(instance)
+                .ok_or_else(|| format!("defparam on unknown instance {}", instance))?
+                .parameters
+                .insert(key.clone(), value.clone());
+        }
+        Ok(module)
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(0, |(line, _)| *line)
+    }
+    fn error<T>(&self, message: &str) -> Result<T, FrontendError> {
+        Err(FrontendError::Verilog(self.line(), message.to_string()))
+    }
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+    fn next(&mut self) -> Result<Token, FrontendError> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token.map_or_else(|| self.error("unexpected end of file"), Ok)
+    }
+    fn accept(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, punct: &str) -> Result<(), FrontendError> {
+        if self.accept(punct) {
+            Ok(())
+        } else {
+            self.error(&format!("expected {}", punct))
+        }
+    }
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(i)) if i == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn ident(&mut self) -> Result<String, FrontendError> {
+        match self.next()? {
+            Token::Ident(ident) => Ok(ident),
+            _ => self.error("expected an identifier"),
+        }
+    }
+    fn integer(&mut self) -> Result<i32, FrontendError> {
+        let negative = self.accept("-");
+        match self.next()? {
+            Token::Number(n) => match n.parse::<i32>() {
+                Ok(n) if negative => Ok(-n),
+                Ok(n) => Ok(n),
+                Err(_) => self.error("expected an integer"),
+            },
+            _ => self.error("expected an integer"),
+        }
+    }
+
+    /// `(* key = value, key *)`, keys without a value are set to 1.
+    fn attributes(&mut self) -> Result<BTreeMap<String, Property>, FrontendError> {
+        let mut attributes = BTreeMap::new();
+        while self.accept("(*") {
+            loop {
+                let key = self.ident()?;
+                let value = if self.accept("=") {
+                    self.constant()?
+                } else {
+                    Property::with_width(1, 32)
+                };
+                attributes.insert(key, value);
+                if !self.accept(",") {
+                    break;
+                }
+            }
+            self.expect("*)")?;
+        }
+        Ok(attributes)
+    }
+
+    fn constant(&mut self) -> Result<Property, FrontendError> {
+        let negative = self.accept("-");
+        match self.next()? {
+            Token::Str(s) => Ok(Property::with_str(&s)),
+            Token::Number(n) if negative => match n.parse::<i64>() {
+                Ok(n) => Ok(Property::with_width(-n, 32)),
+                Err(_) => self.error("invalid negative literal"),
+            },
+            // Real values are kept as strings, like Yosys does.
+            Token::Number(n) if self.accept(".") => match self.next()? {
+                Token::Number(fraction) => Ok(Property::with_str(&format!("{}.{}", n, fraction))),
+                _ => self.error("invalid real literal"),
+            },
+            Token::Number(n) => match literal_bits(&n) {
+                Some(bits) => Ok(literal_property(&bits)),
+                None => self.error("invalid literal"),
+            },
+            _ => self.error("expected a constant"),
+        }
+    }
+
+    fn range(&mut self) -> Result<Option<(i32, i32)>, FrontendError> {
+        if !self.accept("[") {
+            return Ok(None);
+        }
+        let left = self.integer()?;
+        self.expect(":")?;
+        let right = self.integer()?;
+        self.expect("]")?;
+        Ok(Some((left, right)))
+    }
+
+    /// A net expression, LSB first: identifiers, bit and part selects, concatenations,
+    /// replications and literals.
+    fn net_expr(&mut self, m: &mut ModuleBuilder) -> Result<Vec<BitRef>, FrontendError> {
+        match self.next()? {
+            Token::Punct("{") => {
+                if let Some(Token::Number(_)) = self.peek() {
+                    let save = self.pos;
+                    let count = self.integer()?;
+                    if self.accept("{") {
+                        let mut inner = Vec::new();
+                        loop {
+                            let mut part = self.net_expr(m)?;
+                            part.extend(inner);
+                            inner = part;
+                            if !self.accept(",") {
+                                break;
+                            }
+                        }
+                        self.expect("}")?;
+                        self.expect("}")?;
+                        return Ok((0..count).flat_map(|_| inner.iter().copied()).collect());
+                    }
+                    self.pos = save;
+                }
+                // The first element of a concatenation is the most significant.
+                let mut bits = Vec::new();
+                loop {
+                    let mut part = self.net_expr(m)?;
+                    part.extend(bits);
+                    bits = part;
+                    if !self.accept(",") {
+                        break;
+                    }
+                }
+                self.expect("}")?;
+                Ok(bits)
+            }
+            Token::Number(n) => match literal_bits(&n) {
+                Some(bits) => Ok(bits.into_iter().map(BitRef::Const).collect()),
+                None => self.error("invalid literal"),
+            },
+            Token::Ident(name) => {
+                if !self.accept("[") {
+                    return Ok(m
+                        .wire(&name)
+                        .signals
+                        .iter()
+                        .map(|s| BitRef::Signal(*s))
+                        .collect());
+                }
+                let left = self.integer()?;
+                let right = if self.accept(":") {
+                    self.integer()?
+                } else {
+                    left
+                };
+                self.expect("]")?;
+                let wire = m.wire(&name);
+                let (low, high) = (left.min(right), left.max(right));
+                let mut bits = Vec::new();
+                // Keep the select in the wire's own bit order.
+                let indices: Vec<i32> = if wire.left >= wire.right {
+                    (low..=high).collect()
+                } else {
+                    (low..=high).rev().collect()
+                };
+                for index in indices {
+                    match wire.position(index) {
+                        Some(p) => bits.push(BitRef::Signal(wire.signals[p])),
+                        None => {
+                            return self
+                                .error(&format!("index {} out of range for {}", index, name))
+                        }
+                    }
+                }
+                Ok(bits)
+            }
+            _ => self.error("expected a net expression"),
+        }
+    }
+
+    /// `input [7:0] a, b` style declarations, shared by the port list and the module body.
+    fn declaration(
+        &mut self,
+        m: &mut ModuleBuilder,
+        keyword: &str,
+        attributes: &BTreeMap<String, Property>,
+        ansi: bool,
+    ) -> Result<(), FrontendError> {
+        let direction = match keyword {
+            "input" => Some(PortType::In),
+            "output" => Some(PortType::Out),
+            "inout" => Some(PortType::InOut),
+            _ => None,
+        };
+        if direction.is_some() {
+            for net_type in ["wire", "reg", "tri", "logic"] {
+                self.accept_keyword(net_type);
+            }
+        }
+        self.accept_keyword("signed");
+        let range = self.range()?;
+        loop {
+            let name = self.ident()?;
+            m.declare(&name, range);
+            if let Some(wire) = m.wires.get_mut(&name) {
+                wire.attributes.extend(attributes.clone());
+            }
+            if let Some(direction) = direction {
+                m.directions.insert(name.clone(), direction);
+                if !m.port_order.contains(&name) {
+                    m.port_order.push(name.clone());
+                }
+            }
+            if keyword == "supply0" || keyword == "supply1" {
+                let state = if keyword == "supply0" {
+                    State::S0
+                } else {
+                    State::S1
+                };
+                let bits = m.wire(&name).signals.clone();
+                for bit in bits {
+                    m.constants.push((bit, state));
+                }
+            }
+            if self.accept("=") {
+                let lhs: Vec<BitRef> = m
+                    .wire(&name)
+                    .signals
+                    .iter()
+                    .map(|s| BitRef::Signal(*s))
+                    .collect();
+                let rhs = self.net_expr(m)?;
+                m.assign(&lhs, &rhs);
+            }
+            // In an ANSI port list a comma may be followed by the next direction keyword.
+            if ansi {
+                let next = self.tokens.get(self.pos + 1).map(|(_, t)| t);
+                let new_port = match next {
+                    Some(Token::Ident(next)) => {
+                        ["input", "output", "inout"].contains(&next.as_str())
+                    }
+                    Some(Token::Punct("(*")) => true,
+                    _ => false,
+                };
+                if new_port {
+                    return Ok(());
+                }
+            }
+            if !self.accept(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parameter_list(&mut self, m: &mut ModuleBuilder) -> Result<(), FrontendError> {
+        loop {
+            self.accept_keyword("parameter");
+            self.accept_keyword("signed");
+            self.range()?;
+            let name = self.ident()?;
+            self.expect("=")?;
+            let value = self.constant()?;
+            m.parameter_order.push(name.clone());
+            m.parameters.insert(name, value);
+            if !self.accept(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn instance(
+        &mut self,
+        m: &mut ModuleBuilder,
+        cell_type: String,
+        attributes: BTreeMap<String, Property>,
+    ) -> Result<(), FrontendError> {
+        let mut parameters = Parameters::Named(BTreeMap::new());
+        if self.accept("#") {
+            self.expect("(")?;
+            if self.accept(")") {
+                self.pos -= 1;
+            } else if self.accept(".") {
+                self.pos -= 1;
+                let mut named = BTreeMap::new();
+                while self.accept(".") {
+                    let key = self.ident()?;
+                    self.expect("(")?;
+                    named.insert(key, self.constant()?);
+                    self.expect(")")?;
+                    if !self.accept(",") {
+                        break;
+                    }
+                }
+                parameters = Parameters::Named(named);
+            } else {
+                let mut ordered = Vec::new();
+                loop {
+                    ordered.push(self.constant()?);
+                    if !self.accept(",") {
+                        break;
+                    }
+                }
+                parameters = Parameters::Ordered(ordered);
+            }
+            self.expect(")")?;
+        }
+        loop {
+            let name = self.ident()?;
+            if self.range()?.is_some() {
+                return self.error("instance arrays are not supported");
+            }
+            self.expect("(")?;
+            let connections = if matches!(self.peek(), Some(Token::Punct("."))) {
+                let mut named = BTreeMap::new();
+                while self.accept(".") {
+                    let port = self.ident()?;
+                    self.expect("(")?;
+                    if !self.accept(")") {
+                        named.insert(port, self.net_expr(m)?);
+                        self.expect(")")?;
+                    }
+                    if !self.accept(",") {
+                        break;
+                    }
+                }
+                Connections::Named(named)
+            } else {
+                let mut ordered = Vec::new();
+                while !matches!(self.peek(), Some(Token::Punct(")"))) {
+                    ordered.push(if matches!(self.peek(), Some(Token::Punct(","))) {
+                        Vec::new()
+                    } else {
+                        self.net_expr(m)?
+                    });
+                    if !self.accept(",") {
+                        break;
+                    }
+                }
+                Connections::Ordered(ordered)
+            };
+            self.expect(")")?;
+            if m.instances.contains_key(&name) {
+                return self.error(&format!("duplicate instance {}", name));
+            }
+            m.instances.insert(
+                name,
+                Instance {
+                    cell_type: cell_type.clone(),
+                    parameters: match &parameters {
+                        Parameters::Named(p) => Parameters::Named(p.clone()),
+                        Parameters::Ordered(p) => Parameters::Ordered(p.clone()),
+                    },
+                    attributes: attributes.clone(),
+                    connections,
+                },
+            );
+            if !self.accept(",") {
+                return self.expect(";");
+            }
+        }
+    }
+
+    fn module(
+        &mut self,
+        attributes: BTreeMap<String, Property>,
+    ) -> Result<(String, ModuleBuilder), FrontendError> {
+        let line = self.line();
+        let name = self.ident()?;
+        let mut m = ModuleBuilder {
+            line,
+            attributes,
+            ..Default::default()
+        };
+        if self.accept("#") {
+            self.expect("(")?;
+            self.parameter_list(&mut m)?;
+            self.expect(")")?;
+        }
+        if self.accept("(") {
+            while !self.accept(")") {
+                let attributes = self.attributes()?;
+                match self.ident()?.as_str() {
+                    keyword @ ("input" | "output" | "inout") => {
+                        self.declaration(&mut m, keyword, &attributes, true)?
+                    }
+                    port => {
+                        m.port_order.push(port.to_string());
+                    }
+                }
+                self.accept(",");
+            }
+        }
+        self.expect(";")?;
+        loop {
+            let attributes = self.attributes()?;
+            let keyword = self.ident()?;
+            match keyword.as_str() {
+                "endmodule" => break,
+                "input" | "output" | "inout" | "wire" | "reg" | "tri" | "supply0" | "supply1" => {
+                    self.declaration(&mut m, &keyword, &attributes, false)?;
+                    self.expect(";")?;
+                }
+                "parameter" | "localparam" => {
+                    self.pos -= 1;
+                    self.parameter_list(&mut m)?;
+                    self.expect(";")?;
+                }
+                "assign" => loop {
+                    let lhs = self.net_expr(&mut m)?;
+                    self.expect("=")?;
+                    let rhs = self.net_expr(&mut m)?;
+                    m.assign(&lhs, &rhs);
+                    if !self.accept(",") {
+                        self.expect(";")?;
+                        break;
+                    }
+                },
+                "defparam" => loop {
+                    let instance = self.ident()?;
+                    self.expect(".")?;
+                    let key = self.ident()?;
+                    self.expect("=")?;
+                    let value = self.constant()?;
+                    m.defparams.push((instance, key, value));
+                    if !self.accept(",") {
+                        self.expect(";")?;
+                        break;
+                    }
+                },
+                "always" | "initial" | "function" | "task" | "generate" | "specify" => {
+                    return self.error(&format!(
+                        "{} blocks are not supported in structural netlists",
+                        keyword
+                    ))
+                }
+                _ => self.instance(&mut m, keyword, attributes)?,
+            }
+        }
+        // Ports only named in a non-ANSI header still need a wire.
+        for port in m.port_order.clone() {
+            m.wire(&port);
+        }
+        Ok((name, m))
+    }
+}
+
+/// Read a structural Verilog netlist into a [`Design`].
+pub fn read_verilog<R: Read>(mut reader: R) -> Result<Design, FrontendError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let mut parser = Parser {
+        tokens: lex(&input)?,
+        pos: 0,
+    };
+    let mut modules = BTreeMap::new();
+    while parser.peek().is_some() {
+        let attributes = parser.attributes()?;
+        if !parser.accept_keyword("module") {
+            return parser.error("expected module");
+        }
+        let (name, module) = parser.module(attributes)?;
+        let line = module.line;
+        if modules.insert(name.clone(), module).is_some() {
+            return Err(FrontendError::Verilog(
+                line,
+                format!("duplicate module {}", name),
+            ));
+        }
+    }
+    let mut design = Design::default();
+    for (name, module) in &modules {
+        let built = module
+            .build(&modules)
+            .map_err(|e| FrontendError::Verilog(module.line, e))?;
+        design.modules.insert(name.clone(), built);
+    }
+    Ok(design)
+}
+
+/// Load a structural Verilog netlist into the context.
+pub fn parse_verilog<D: DelayTrait, R: Read>(
+    reader: R,
+    ctx: &mut BaseCtx<D>,
+) -> Result<(), FrontendError> {
+    let design = read_verilog(reader)?;
+    import_design(ctx, &design)
+}
+
+// The reserved words of IEEE 1364-2005, annex B, in order.
+#[rustfmt::skip]
+const KEYWORDS: [&str; 124] = [
+    "always", "and", "assign", "automatic", "begin", "buf", "bufif0", "bufif1", "case", "casex",
+    "casez", "cell", "cmos", "config", "deassign", "default", "defparam", "design", "disable",
+    "edge", "else", "end", "endcase", "endconfig", "endfunction", "endgenerate", "endmodule",
+    "endprimitive", "endspecify", "endtable", "endtask", "event", "for", "force", "forever",
+    "fork", "function", "generate", "genvar", "highz0", "highz1", "if", "ifnone", "incdir",
+    "include", "initial", "inout", "input", "instance", "integer", "join", "large", "liblist",
+    "library", "localparam", "macromodule", "medium", "module", "nand", "negedge", "nmos", "nor",
+    "noshowcancelled", "not", "notif0", "notif1", "or", "output", "parameter", "pmos", "posedge",
+    "primitive", "pull0", "pull1", "pulldown", "pullup", "pulsestyle_ondetect",
+    "pulsestyle_onevent", "rcmos", "real", "realtime", "reg", "release", "repeat", "rnmos",
+    "rpmos", "rtran", "rtranif0", "rtranif1", "scalared", "showcancelled", "signed", "small",
+    "specify", "specparam", "strong0", "strong1", "supply0", "supply1", "table", "task", "time",
+    "tran", "tranif0", "tranif1", "tri", "tri0", "tri1", "triand", "trior", "trireg", "unsigned",
+    "use", "uwire", "vectored", "wait", "wand", "weak0", "weak1", "while", "wire", "wor", "xnor",
+    "xor",
+];
+
+/// Names that aren't plain Verilog identifiers are written as escaped identifiers.
+fn identifier(name: &str) -> String {
+    let simple = name
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if simple && KEYWORDS.binary_search(&name).is_err() {
+        name.to_string()
+    } else {
+        format!("\\{} ", name)
+    }
+}
+
+fn property_literal(property: &Property) -> String {
+    match property {
+        Property::Str(_, strval) => {
+            format!("\"{}\"", strval.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+        Property::Int(..) if property.size() == 0 => "0".to_string(),
+        Property::Int(..) => format!("{}'b{}", property.size(), property),
+    }
+}
+
+fn range(width: usize, offset: i32, upto: bool) -> String {
+    let high = offset + width as i32 - 1;
+    match (width, upto) {
+        (1, _) => String::new(),
+        (_, false) => format!("[{}:{}] ", high, offset),
+        (_, true) => format!("[{}:{}] ", offset, high),
+    }
+}
+
+/// How bit `i` of a signal is referred to, `name` or `name[index]`.
+fn bit_reference(name: &str, i: usize, width: usize, offset: i32, upto: bool) -> String {
+    if width == 1 {
+        identifier(name)
+    } else {
+        let index = if upto {
+            offset + (width - 1 - i) as i32
+        } else {
+            offset + i as i32
+        };
+        format!("{}[{}]", identifier(name), index)
+    }
+}
+
+fn attributes_verilog<W: Write>(
+    writer: &mut W,
+    indent: &str,
+    attributes: &BTreeMap<String, Property>,
+) -> Result<(), FrontendError> {
+    for (key, value) in attributes {
+        writeln!(
+            writer,
+            "{}(* {} = {} *)",
+            indent,
+            identifier(key),
+            property_literal(value)
+        )?;
+    }
+    Ok(())
+}
+
+fn module_verilog<W: Write>(
+    writer: &mut W,
+    name: &str,
+    module: &Module,
+) -> Result<(), FrontendError> {
+    // Every numbered bit is referred to by one name, ports first, then visible net names.
+    let mut references: BTreeMap<u64, String> = BTreeMap::new();
+    let signals = module
+        .ports
+        .iter()
+        .map(|(name, port)| (name, &port.bits, port.offset, port.upto))
+        .chain(
+            module
+                .netnames
+                .iter()
+                .filter(|(_, netname)| !netname.hide_name)
+                .chain(module.netnames.iter().filter(|(_, n)| n.hide_name))
+                .map(|(name, n)| (name, &n.bits, n.offset, n.upto)),
+        );
+    for (name, bits, offset, upto) in signals {
+        for (i, bit) in bits.iter().enumerate() {
+            if let Bit::Net(n) = bit {
+                references
+                    .entry(*n)
+                    .or_insert_with(|| bit_reference(name, i, bits.len(), offset, upto));
+            }
+        }
+    }
+    // Bits only used by cell connections still need a wire.
+    let mut unnamed = Vec::new();
+    for cell in module.cells.values() {
+        for bit in cell.connections.values().flatten() {
+            if let Bit::Net(n) = bit {
+                if !references.contains_key(n) {
+                    let name = format!("$net${}", n);
+                    references.insert(*n, identifier(&name));
+                    unnamed.push(name);
+                }
+            }
+        }
+    }
+    let expression = |bits: &[Bit]| -> String {
+        let mut parts: Vec<String> = bits
+            .iter()
+            .rev()
+            .map(|bit| match bit {
+                Bit::Net(n) => references[n].clone(),
+                Bit::Const(state) => format!("1'b{}", state.to_char()),
+            })
+            .collect();
+        match parts.len() {
+            1 => parts.remove(0),
+            _ => format!("{{{}}}", parts.join(", ")),
+        }
+    };
+
+    attributes_verilog(writer, "", &module.attributes)?;
+    let ports: Vec<String> = module.ports.keys().map(|name| identifier(name)).collect();
+    writeln!(writer, "module {}({});", identifier(name), ports.join(", "))?;
+    for (name, port) in &module.ports {
+        let direction = match port.direction {
+            PortType::In => "input",
+            PortType::Out => "output",
+            PortType::InOut => "inout",
+        };
+        let range = range(port.bits.len(), port.offset, port.upto);
+        writeln!(writer, "  {} {}{};", direction, range, identifier(name))?;
+    }
+    for (name, netname) in &module.netnames {
+        if module.ports.contains_key(name) {
+            continue;
+        }
+        attributes_verilog(writer, "  ", &netname.attributes)?;
+        let range = range(netname.bits.len(), netname.offset, netname.upto);
+        writeln!(writer, "  wire {}{};", range, identifier(name))?;
+    }
+    for name in &unnamed {
+        writeln!(writer, "  wire {};", identifier(name))?;
+    }
+    // Other names of a bit follow the name it is referred to by.
+    for (name, netname) in &module.netnames {
+        let width = netname.bits.len();
+        for (i, bit) in netname.bits.iter().enumerate() {
+            let own = bit_reference(name, i, width, netname.offset, netname.upto);
+            let source = expression(std::slice::from_ref(bit));
+            if own != source {
+                writeln!(writer, "  assign {} = {};", own, source)?;
+            }
+        }
+    }
+    for (name, cell) in &module.cells {
+        attributes_verilog(writer, "  ", &cell.attributes)?;
+        write!(writer, "  {} ", identifier(&cell.cell_type))?;
+        if !cell.parameters.is_empty() {
+            let parameters: Vec<String> = cell
+                .parameters
+                .iter()
+                .map(|(key, value)| {
+                    format!("    .{}({})", identifier(key), property_literal(value))
+                })
+                .collect();
+            write!(writer, "#(\n{}\n  ) ", parameters.join(",\n"))?;
+        }
+        let connections: Vec<String> = cell
+            .connections
+            .iter()
+            .map(|(port, bits)| {
+                if bits.is_empty() {
+                    format!("    .{}()", identifier(port))
+                } else {
+                    format!("    .{}({})", identifier(port), expression(bits))
+                }
+            })
+            .collect();
+        writeln!(
+            writer,
+            "{} (\n{}\n  );",
+            identifier(name),
+            connections.join(",\n")
+        )?;
+    }
+    writeln!(writer, "endmodule")?;
+    Ok(())
+}
+
+/// Write every module of a [`Design`] as structural Verilog.
+pub fn write_design_verilog<W: Write>(design: &Design, mut writer: W) -> Result<(), FrontendError> {
+    for (name, module) in &design.modules {
+        module_verilog(&mut writer, name, module)?;
+    }
+    Ok(())
+}
+
+/// Write the context's netlist as a single structural Verilog module. Every cell is written
+/// except the ones the frontend added itself: the IO buffers still standing in for top level
+/// ports, whose nets are the ports themselves, and the drivers of the shared constant nets,
+/// which are written as constants. Unconnected cell ports are written as `.P()`.
+pub fn write_verilog<D: DelayTrait, W: Write>(
+    ctx: &BaseCtx<D>,
+    writer: W,
+) -> Result<(), FrontendError> {
+    let io_buffers = [ID_NEXTPNR_IBUF, ID_NEXTPNR_OBUF, ID_NEXTPNR_IOBUF];
+    let mut added = BTreeSet::new();
+    for index in ctx.port_cells.values() {
+        match ctx.cells.get(*index) {
+            Some(cell) if io_buffers.contains(&cell.cell_type()) => {
+                added.insert(ctx.name_of(cell.name())?);
+            }
+            _ => {}
+        }
+    }
+    // Found by the nets rather than the cell types, a user's own GND or VCC cell is kept.
+    let mut constant_nets = BTreeMap::new();
+    for (name, state) in [
+        (ID_PACKER_GND_NET, State::S0),
+        (ID_PACKER_VCC_NET, State::S1),
+    ] {
+        let Some(net) = ctx.get_net(name) else {
+            continue;
+        };
+        if let Some(driver) = net.driver.cell.and_then(|cell| ctx.cells.get(cell)) {
+            added.insert(ctx.name_of(driver.name())?);
+            constant_nets.insert(ctx.name_of(net.name())?, state);
+        }
+    }
+
+    // Unconnected ports are left open, tying them to x would drive them.
+    let mut open_ports = BTreeSet::new();
+    for (_, cell) in ctx.cells.iter() {
+        for (port, info) in cell.ports() {
+            if info.net.is_none() {
+                open_ports.insert((ctx.name_of(cell.name())?, ctx.name_of(*port)?));
+            }
+        }
+    }
+
+    let mut design = export_design(ctx)?;
+    for module in design.modules.values_mut() {
+        for (name, cell) in module.cells.iter_mut() {
+            for (port, bits) in cell.connections.iter_mut() {
+                if open_ports.contains(&(name.clone(), port.clone())) {
+                    bits.clear();
+                }
+            }
+        }
+        let constants: BTreeMap<Bit, Bit> = constant_nets
+            .iter()
+            .filter_map(|(name, state)| {
+                let bit = *module.netnames.get(name)?.bits.first()?;
+                Some((bit, Bit::Const(*state)))
+            })
+            .collect();
+        module.cells.retain(|name, _| !added.contains(name));
+        let bits = module
+            .ports
+            .values_mut()
+            .map(|port| &mut port.bits)
+            .chain(
+                module
+                    .netnames
+                    .values_mut()
+                    .map(|netname| &mut netname.bits),
+            )
+            .chain(
+                module
+                    .cells
+                    .values_mut()
+                    .flat_map(|cell| cell.connections.values_mut()),
+            );
+        for bits in bits {
+            for bit in bits.iter_mut() {
+                *bit = *constants.get(bit).unwrap_or(bit);
+            }
+        }
+    }
+    write_design_verilog(&design, writer)
+}